@@ -0,0 +1,21 @@
+/* C interface of the web_search library, built with `--features ffi`. */
+#ifndef WEB_VERSIONS_H
+#define WEB_VERSIONS_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Fetches the current Spotify web player version. Returns a JSON object,
+ * {"success":true,"key":"1.2.86.316","data":{...}} or
+ * {"success":false,"error":"..."}; free it with web_versions_string_free. */
+char *web_versions_fetch_latest_json(void);
+
+/* Frees a string returned by this library; NULL is ignored. */
+void web_versions_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* WEB_VERSIONS_H */
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS versions (
+    surface TEXT NOT NULL,
+    key TEXT NOT NULL,
+    entry JSONB NOT NULL,
+    first_seen TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (surface, key)
+);
+
+CREATE INDEX IF NOT EXISTS versions_first_seen_idx ON versions (first_seen);
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+
+use crate::config::{AnomalyConfig, Surface};
+use crate::stats;
+use crate::version::WebVersion;
+
+/// Why a new entry looks like scraping garbage rather than a release; empty
+/// when it looks fine. `newest` is the newest version already stored. Only
+/// the build time is checked for other services than Spotify: their versions
+/// have neither Spotify's shape nor its numbering.
+pub fn inspect(
+    config: &AnomalyConfig,
+    surface: &Surface<'_>,
+    key: &str,
+    entry: &Value,
+    newest: Option<&str>,
+    now: DateTime<Utc>,
+) -> Vec<String> {
+    let mut reasons = Vec::new();
+    let spotify = surface.is_spotify();
+
+    let client_version = entry["clientVersion"].as_str().unwrap_or_default();
+    if spotify && !is_client_version(client_version) {
+        reasons.push(format!(
+            "clientVersion '{}' doesn't look like 1.2.86.316.gcd065fc0",
+            client_version
+        ));
+    }
+
+    if let Some(built) = stats::build_time(entry) {
+        if built > now + Duration::hours(config.max_future_hours) {
+            reasons.push(format!(
+                "build time {} is in the future",
+                built.to_rfc3339()
+            ));
+        } else if built < now - Duration::days(config.max_age_days) {
+            reasons.push(format!(
+                "build time {} is more than {} days old",
+                built.to_rfc3339(),
+                config.max_age_days
+            ));
+        }
+    }
+
+    if let Some(newest) = newest.filter(|_| spotify) {
+        if is_far_behind(key, newest, config.max_backward_series) {
+            reasons.push(format!(
+                "{} is far behind the newest stored version {}",
+                key, newest
+            ));
+        }
+    }
+    reasons
+}
+
+/// Four numeric segments and a `g<hex>` commit, e.g. `1.2.86.316.gcd065fc0`.
+pub fn is_client_version(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('.').collect();
+    let [major, minor, series, build, commit] = parts[..] else {
+        return false;
+    };
+    let numeric = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    [major, minor, series, build].into_iter().all(numeric)
+        && commit
+            .strip_prefix('g')
+            .is_some_and(|hex| !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Older than `newest` by a major/minor release, or by at least `max_series`
+/// in the third segment.
+fn is_far_behind(key: &str, newest: &str, max_series: u64) -> bool {
+    if WebVersion::new(key) >= WebVersion::new(newest) {
+        return false;
+    }
+    let segments = |version: &str| -> Vec<u64> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    let (key, newest) = (segments(key), segments(newest));
+    let segment = |parts: &[u64], i: usize| parts.get(i).copied().unwrap_or(0);
+    if (segment(&key, 0), segment(&key, 1)) != (segment(&newest, 0), segment(&newest, 1)) {
+        return true;
+    }
+    segment(&newest, 2).saturating_sub(segment(&key, 2)) >= max_series
+}
@@ -0,0 +1,106 @@
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::BackupConfig;
+use crate::log::{log_info, log_success};
+use crate::paths;
+use crate::Result;
+
+/// Backups of `file` are named `<stem>-<timestamp>.<ext>`, so they sort oldest first.
+fn prefix(file: &Path) -> String {
+    let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+    format!("{}-", stem)
+}
+
+fn extension(file: &Path) -> String {
+    file.extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default()
+}
+
+/// Copies `file` into the backup dir (if it exists) and prunes old backups of it.
+pub fn backup(config: &BackupConfig, file: &Path) -> Result<Option<PathBuf>> {
+    if !config.enabled || !paths::long_path(file).exists() {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(paths::long_path(&config.dir))?;
+    let target = config.dir.join(format!(
+        "{}{}{}",
+        prefix(file),
+        Local::now().format("%Y%m%d-%H%M%S-%3f"),
+        extension(file)
+    ));
+    fs::copy(paths::long_path(file), paths::long_path(&target))
+        .map_err(|e| format!("Failed to back up {}: {}", file.display(), e))?;
+    log_info("BACKUP", &format!("Backed up to {}", target.display()));
+
+    let backups = list(&config.dir, file)?;
+    for old in backups.iter().skip(config.keep.max(1)) {
+        fs::remove_file(paths::long_path(old))?;
+    }
+    Ok(Some(target))
+}
+
+/// Backups of `file` in `dir`, newest first.
+pub fn list(dir: &Path, file: &Path) -> Result<Vec<PathBuf>> {
+    let (prefix, extension) = (prefix(file), extension(file));
+    let entries = match fs::read_dir(paths::long_path(dir)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.starts_with(&prefix) && name.ends_with(&extension)
+        })
+        .map(|entry| dir.join(entry.file_name()))
+        .collect();
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Finds a backup by file name, path or `latest`.
+pub fn find(dir: &Path, file: &Path, name: &str) -> Result<PathBuf> {
+    let backups = list(dir, file)?;
+    let found = if name == "latest" {
+        backups.into_iter().next()
+    } else {
+        backups.into_iter().find(|backup| {
+            backup.as_path() == Path::new(name)
+                || backup
+                    .file_name()
+                    .is_some_and(|file_name| file_name == name)
+        })
+    };
+    found.ok_or_else(|| {
+        format!(
+            "No backup '{}' of {} in {}",
+            name,
+            file.display(),
+            dir.display()
+        )
+        .into()
+    })
+}
+
+/// Replaces `file` with `backup`, backing up the current file first so the restore can be undone.
+pub fn restore(config: &BackupConfig, file: &Path, backup: &Path) -> Result<()> {
+    let content = paths::read_optional(backup)?
+        .ok_or_else(|| format!("{} does not exist", backup.display()))?;
+    serde_json::from_str::<serde_json::Value>(&content)
+        .map_err(|e| format!("{} is not valid JSON: {}", backup.display(), e))?;
+
+    self::backup(config, file)?;
+    paths::write_atomic(file, content.as_bytes())?;
+    log_success(
+        "BACKUP",
+        &format!("Restored {} from {}", file.display(), backup.display()),
+    );
+    Ok(())
+}
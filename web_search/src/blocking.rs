@@ -0,0 +1,77 @@
+//! A synchronous [`Scraper`] for callers without a tokio runtime of their own,
+//! in the spirit of `reqwest::blocking`.
+//!
+//! Each scraper owns a current-thread runtime, so its methods must not be called
+//! from inside another async runtime; they panic there, as `block_on` does.
+
+use serde_json::Value;
+use std::time::Duration;
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::broadcast;
+
+use crate::check::Observation;
+use crate::config::Config;
+use crate::events::VersionEvent;
+use crate::store::VersionStore;
+use crate::Result;
+
+#[derive(Debug)]
+pub struct Scraper {
+    inner: crate::Scraper,
+    runtime: Runtime,
+}
+
+impl Scraper {
+    /// See [`crate::Scraper::new`].
+    pub fn new() -> Result<Self> {
+        Scraper::from_async(crate::Scraper::new())
+    }
+
+    /// See [`crate::Scraper::with_config`].
+    pub fn with_config(config: Config) -> Result<Self> {
+        Scraper::from_async(crate::Scraper::with_config(config))
+    }
+
+    /// See [`crate::Scraper::builder`]; finish with `build_blocking`.
+    pub fn builder() -> crate::scraper::ScraperBuilder {
+        crate::Scraper::builder()
+    }
+
+    /// Runs an async [`crate::Scraper`] on a runtime of its own.
+    pub fn from_async(inner: crate::Scraper) -> Result<Self> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| format!("Failed to start runtime: {}", e))?;
+        Ok(Scraper { inner, runtime })
+    }
+
+    pub fn config(&self) -> &Config {
+        self.inner.config()
+    }
+
+    /// See [`crate::Scraper::latest`].
+    pub fn latest(&self) -> Result<Observation> {
+        self.runtime.block_on(self.inner.latest())
+    }
+
+    /// See [`crate::Scraper::check`].
+    pub fn check(&self) -> Result<Value> {
+        self.runtime.block_on(self.inner.check())
+    }
+
+    /// See [`crate::Scraper::check_with_store`].
+    pub fn check_with_store(&self, store: &dyn VersionStore) -> Result<Value> {
+        self.runtime.block_on(self.inner.check_with_store(store))
+    }
+
+    /// See [`crate::Scraper::subscribe`]; read it with `blocking_recv` from another thread.
+    pub fn subscribe(&self) -> broadcast::Receiver<VersionEvent> {
+        self.inner.subscribe()
+    }
+
+    /// See [`crate::Scraper::watch`]; blocks the calling thread for good.
+    pub fn watch(&self, interval: Duration) {
+        self.runtime.block_on(self.inner.watch(interval))
+    }
+}
@@ -0,0 +1,389 @@
+use regex::Regex;
+use reqwest::header::ACCEPT_ENCODING;
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::config::{Compression, HttpConfig};
+use crate::log::{log_info, log_success, log_warning};
+use crate::pace;
+use crate::paths;
+use crate::prettify;
+use crate::record;
+use crate::version::{version_key, WebVersion};
+use crate::Result;
+
+const BUNDLE_VERSION_REGEX: &str = r"\b\d+\.\d+\.\d+\.\d+\.g[0-9a-f]{7,}\b";
+const STRING_LITERAL_REGEX: &str = r#""((?:[^"\\\n]|\\.){4,200})"|'((?:[^'\\\n]|\\.){4,200})'"#;
+const SOURCE_MAP_REGEX: &str = r"(?m)^//[#@]\s*sourceMappingURL=(\S+)\s*$";
+const ENDPOINT_REGEX: &str = r"https?://[A-Za-z0-9.-]+(?:/[A-Za-z0-9._~%/{}:-]*)?";
+/// Every compression an archive may have been written with.
+const ARCHIVE_COMPRESSIONS: [Compression; 3] =
+    [Compression::None, Compression::Zstd, Compression::Gzip];
+
+pub fn resolve_url(page_url: &str, src: &str) -> String {
+    Url::parse(page_url)
+        .and_then(|base| base.join(src))
+        .map(|url| url.to_string())
+        .unwrap_or_else(|_| src.to_string())
+}
+
+/// The last path segment of `url`, without any query.
+pub fn file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Uncompressed size of each of `urls` from a HEAD request's Content-Length.
+/// Assets that fail or give no length are logged and left out.
+pub async fn asset_sizes(
+    client: &reqwest::Client,
+    http: &HttpConfig,
+    urls: &[String],
+) -> BTreeMap<String, u64> {
+    let mut sizes = BTreeMap::new();
+    for url in urls {
+        let permit = pace::wait(http).await;
+        let response = async {
+            let request = client
+                .head(url)
+                .header(ACCEPT_ENCODING, "identity")
+                .build()?;
+            record::send(client, request).await
+        }
+        .await;
+        drop(permit);
+        let length = response.and_then(|response| {
+            if !response.is_success() {
+                return Err(format!("HTTP {}", response.status).into());
+            }
+            response
+                .header("content-length")
+                .and_then(|length| length.parse::<u64>().ok())
+                .ok_or_else(|| "no Content-Length".into())
+        });
+        match length {
+            Ok(bytes) => {
+                sizes.insert(url.clone(), bytes);
+            }
+            Err(e) => log_warning("ASSETS", &format!("No size for {}: {}", url, e)),
+        }
+    }
+    log_info(
+        "ASSETS",
+        &format!("Sizes of {} of {} assets recorded", sizes.len(), urls.len()),
+    );
+    sizes
+}
+
+#[tracing::instrument(skip(client))]
+pub async fn fetch_bundle(client: &reqwest::Client, url: &str) -> Result<String> {
+    log_info("BUNDLE", &format!("Downloading {}", url));
+    let js = record::send(client, client.get(url).build()?)
+        .await?
+        .text()?;
+    log_success("BUNDLE", &format!("Bundle received ({} bytes)", js.len()));
+    Ok(js)
+}
+
+pub fn find_versions(js: &str) -> Vec<String> {
+    let re = Regex::new(BUNDLE_VERSION_REGEX).expect("Bundle version regex error");
+    let mut versions: Vec<String> = re.find_iter(js).map(|m| m.as_str().to_string()).collect();
+    versions.sort();
+    versions.dedup();
+    versions
+}
+
+pub fn cross_check(url: &str, js: &str, client_version: &str) -> Value {
+    let versions = find_versions(js);
+    let status = if js.contains(client_version) {
+        log_success("BUNDLE", &format!("Bundle agrees on {}", client_version));
+        "match"
+    } else if versions.is_empty() {
+        log_warning("BUNDLE", "No version string found in web-player");
+        "not_found"
+    } else {
+        log_warning(
+            "BUNDLE",
+            &format!(
+                "Version mismatch: appServerConfig has {}, web-player has {}",
+                client_version,
+                versions.join(", ")
+            ),
+        );
+        "mismatch"
+    };
+
+    json!({
+        "status": status,
+        "url": url,
+        "versions": versions
+    })
+}
+
+pub fn archive_path(dir: &Path, surface: &str, key: &str, compression: Compression) -> PathBuf {
+    dir.join(surface)
+        .join(format!("{}.js{}", key, compression.extension()))
+}
+
+/// Always uncompressed, to be opened and diffed by hand.
+pub fn pretty_path(dir: &Path, surface: &str, key: &str) -> PathBuf {
+    dir.join(surface).join(format!("{}.pretty.js", key))
+}
+
+pub fn scan_path(dir: &Path, surface: &str, key: &str) -> PathBuf {
+    dir.join(surface).join(format!("{}.scan.json", key))
+}
+
+pub fn source_map_path(dir: &Path, surface: &str, key: &str, compression: Compression) -> PathBuf {
+    dir.join(surface)
+        .join(format!("{}.js.map{}", key, compression.extension()))
+}
+
+fn write_archive(path: PathBuf, content: &str) -> Result<PathBuf> {
+    paths::write_atomic(&path, content.as_bytes())?;
+    log_success("BUNDLE", &format!("Archived to {}", path.display()));
+    Ok(path)
+}
+
+pub fn archive(
+    dir: &Path,
+    surface: &str,
+    key: &str,
+    js: &str,
+    compression: Compression,
+) -> Result<PathBuf> {
+    write_archive(archive_path(dir, surface, key, compression), js)
+}
+
+pub fn archive_source_map(
+    dir: &Path,
+    surface: &str,
+    key: &str,
+    map: &str,
+    compression: Compression,
+) -> Result<PathBuf> {
+    write_archive(source_map_path(dir, surface, key, compression), map)
+}
+
+pub fn find_source_map_url(js: &str) -> Option<String> {
+    let re = Regex::new(SOURCE_MAP_REGEX).expect("Source map regex error");
+    re.captures_iter(js)
+        .last()
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string())
+}
+
+/// Returns the source map location and content if the bundle advertises one
+/// that can actually be retrieved.
+pub async fn fetch_source_map(
+    client: &reqwest::Client,
+    bundle_url: &str,
+    js: &str,
+) -> Option<(String, String)> {
+    let Some(src) = find_source_map_url(js) else {
+        log_info("BUNDLE", "Bundle has no sourceMappingURL");
+        return None;
+    };
+
+    if let Some(data) = src.strip_prefix("data:") {
+        log_success("BUNDLE", "Bundle has an inline source map");
+        let (meta, payload) = data.split_once(',')?;
+        let map = if meta.ends_with(";base64") {
+            String::from_utf8(crate::extract::decode_base64(payload).ok()?).ok()?
+        } else {
+            payload.to_string()
+        };
+        return Some(("inline".to_string(), map));
+    }
+
+    let map_url = resolve_url(bundle_url, &src);
+    log_info("BUNDLE", &format!("Fetching source map {}", map_url));
+    let map = async {
+        record::send(client, client.get(&map_url).build()?)
+            .await?
+            .text()
+    }
+    .await;
+
+    match map {
+        Ok(map) => {
+            log_success(
+                "BUNDLE",
+                &format!("Source map available ({} bytes)", map.len()),
+            );
+            Some((map_url, map))
+        }
+        Err(e) => {
+            log_warning("BUNDLE", &format!("Source map not available: {}", e));
+            None
+        }
+    }
+}
+
+/// Whether a bundle for `version` was archived, whichever compression it was written with.
+pub fn is_archived(dir: &Path, surface: &str, version: &str) -> bool {
+    let key = version_key(version);
+    ARCHIVE_COMPRESSIONS
+        .into_iter()
+        .any(|compression| archive_path(dir, surface, &key, compression).exists())
+}
+
+/// Loads an archived bundle whichever compression it was written with.
+pub fn load_archived(dir: &Path, surface: &str, version: &str) -> Result<String> {
+    let key = version_key(version);
+    for compression in ARCHIVE_COMPRESSIONS {
+        if let Some(js) = paths::read_optional(&archive_path(dir, surface, &key, compression))? {
+            return Ok(js);
+        }
+    }
+    Err(format!(
+        "No archived bundle at {}",
+        archive_path(dir, surface, &key, Compression::None).display()
+    )
+    .into())
+}
+
+/// Pretty-prints the archived bundle of `version` next to it and returns where.
+pub fn prettify_archived(dir: &Path, surface: &str, version: &str) -> Result<PathBuf> {
+    let js = load_archived(dir, surface, version)?;
+    let path = pretty_path(dir, surface, &version_key(version));
+    paths::write_atomic(&path, prettify::prettify(&js).as_bytes())?;
+    log_success("BUNDLE", &format!("Pretty-printed to {}", path.display()));
+    Ok(path)
+}
+
+/// The newest stored version whose archived bundle has the same SHA-256 as
+/// `entry`'s, if any.
+pub fn same_bundle(versions: &HashMap<String, Value>, entry: &Value) -> Option<String> {
+    let hash = entry["bundleSha256"].as_str()?;
+    versions
+        .iter()
+        .filter(|(_, other)| other["bundleSha256"].as_str() == Some(hash))
+        .map(|(key, _)| key)
+        .max_by_key(|key| WebVersion::new(key))
+        .cloned()
+}
+
+/// The distinct matches of each named pattern in `js`: capture group 1 when
+/// the pattern has one, else the whole match.
+pub fn scan(
+    js: &str,
+    patterns: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, BTreeSet<String>>> {
+    patterns
+        .iter()
+        .map(|(name, pattern)| {
+            let re = Regex::new(pattern)
+                .map_err(|e| format!("Invalid [bundle] scan pattern '{}': {}", name, e))?;
+            let found = re
+                .captures_iter(js)
+                .filter_map(|caps| caps.get(1).or_else(|| caps.get(0)))
+                .map(|m| m.as_str().to_string())
+                .collect();
+            Ok((name.clone(), found))
+        })
+        .collect()
+}
+
+/// Runs `patterns` over an archived bundle and saves what they found next to it.
+pub fn archive_scan(
+    dir: &Path,
+    surface: &str,
+    key: &str,
+    js: &str,
+    patterns: &BTreeMap<String, String>,
+) -> Result<BTreeMap<String, usize>> {
+    let found = scan(js, patterns)?;
+    let path = scan_path(dir, surface, key);
+    paths::write_atomic(&path, serde_json::to_string_pretty(&found)?.as_bytes())?;
+    log_success(
+        "BUNDLE",
+        &format!("Scan results written to {}", path.display()),
+    );
+    Ok(found
+        .into_iter()
+        .map(|(name, found)| (name, found.len()))
+        .collect())
+}
+
+fn load_scan(
+    dir: &Path,
+    surface: &str,
+    version: &str,
+) -> Result<Option<BTreeMap<String, BTreeSet<String>>>> {
+    let path = scan_path(dir, surface, &version_key(version));
+    paths::read_optional(&path)?
+        .map(|content| {
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e).into())
+        })
+        .transpose()
+}
+
+fn string_literals(js: &str) -> BTreeSet<String> {
+    let re = Regex::new(STRING_LITERAL_REGEX).expect("String literal regex error");
+    re.captures_iter(js)
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(2)))
+        .map(|m| m.as_str().to_string())
+        .collect()
+}
+
+fn endpoints(js: &str) -> BTreeSet<String> {
+    let re = Regex::new(ENDPOINT_REGEX).expect("Endpoint regex error");
+    re.find_iter(js).map(|m| m.as_str().to_string()).collect()
+}
+
+fn added_removed(old: &BTreeSet<String>, new: &BTreeSet<String>) -> (Vec<String>, Vec<String>) {
+    (
+        new.difference(old).cloned().collect(),
+        old.difference(new).cloned().collect(),
+    )
+}
+
+pub fn diff(from: &str, old_js: &str, to: &str, new_js: &str) -> Value {
+    let (strings_added, strings_removed) =
+        added_removed(&string_literals(old_js), &string_literals(new_js));
+    let (endpoints_added, endpoints_removed) =
+        added_removed(&endpoints(old_js), &endpoints(new_js));
+
+    json!({
+        "from": from,
+        "to": to,
+        "identical": old_js == new_js,
+        "size_from": old_js.len(),
+        "size_to": new_js.len(),
+        "size_delta": new_js.len() as i64 - old_js.len() as i64,
+        "strings_added": strings_added,
+        "strings_removed": strings_removed,
+        "endpoints_added": endpoints_added,
+        "endpoints_removed": endpoints_removed
+    })
+}
+
+pub fn diff_archived(dir: &Path, surface: &str, from: &str, to: &str) -> Result<Value> {
+    let old_js = load_archived(dir, surface, from)?;
+    let new_js = load_archived(dir, surface, to)?;
+    let mut output = diff(from, &old_js, to, &new_js);
+    if let (Some(old), Some(new)) = (load_scan(dir, surface, from)?, load_scan(dir, surface, to)?) {
+        output["scan"] = diff_scans(&old, &new);
+    }
+    Ok(output)
+}
+
+/// `{name: {added, removed}}` for every pattern scanned in the newer bundle.
+pub fn diff_scans(
+    old: &BTreeMap<String, BTreeSet<String>>,
+    new: &BTreeMap<String, BTreeSet<String>>,
+) -> Value {
+    let empty = BTreeSet::new();
+    let diffs: serde_json::Map<String, Value> = new
+        .iter()
+        .map(|(name, found)| {
+            let (added, removed) = added_removed(old.get(name).unwrap_or(&empty), found);
+            (name.clone(), json!({ "added": added, "removed": removed }))
+        })
+        .collect();
+    Value::Object(diffs)
+}
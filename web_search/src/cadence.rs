@@ -0,0 +1,52 @@
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::stats;
+
+const HOURS_PER_WEEK: usize = 7 * 24;
+
+/// How often builds have historically landed in each hour of the week (UTC).
+#[derive(Debug, Clone)]
+pub struct Cadence {
+    bins: [f64; HOURS_PER_WEEK],
+}
+
+fn hour_of_week(time: &DateTime<Utc>) -> usize {
+    time.weekday().num_days_from_monday() as usize * 24 + time.hour() as usize
+}
+
+impl Cadence {
+    pub fn from_versions(versions: &HashMap<String, Value>) -> Cadence {
+        let mut bins = [0.0; HOURS_PER_WEEK];
+        for (_, time) in stats::timeline(versions) {
+            let bin = hour_of_week(&time);
+            bins[bin] += 1.0;
+            bins[(bin + 1) % HOURS_PER_WEEK] += 0.5;
+            bins[(bin + HOURS_PER_WEEK - 1) % HOURS_PER_WEEK] += 0.5;
+        }
+        Cadence { bins }
+    }
+
+    /// Relative release likelihood for the hour containing `time`, from 0.0 to 1.0.
+    pub fn weight_at(&self, time: &DateTime<Utc>) -> Option<f64> {
+        let max = self.bins.iter().cloned().fold(0.0, f64::max);
+        if max == 0.0 {
+            return None;
+        }
+        Some(self.bins[hour_of_week(time)] / max)
+    }
+
+    /// Short intervals in busy hours, long ones when releases never happen.
+    pub fn interval_at(
+        &self,
+        time: &DateTime<Utc>,
+        min: Duration,
+        max: Duration,
+    ) -> Option<Duration> {
+        let weight = self.weight_at(time)?;
+        let span = max.saturating_sub(min).as_secs_f64();
+        Some(max.saturating_sub(Duration::from_secs_f64(span * weight)))
+    }
+}
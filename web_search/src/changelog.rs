@@ -0,0 +1,72 @@
+use serde_json::Value;
+use std::path::Path;
+
+use crate::log::{log_info, log_success};
+use crate::paths;
+use crate::Result;
+
+const HEADER: &str = "# Changelog\n\n";
+
+/// Content hash from a bundle URL such as `.../web-player.ca73afa1.js`.
+pub fn bundle_hash(url: &str) -> Option<&str> {
+    let file = url.rsplit('/').next()?;
+    let mut parts = file.rsplit('.');
+    parts.next().filter(|ext| *ext == "js")?;
+    parts
+        .next()
+        .filter(|hash| hash.len() >= 8 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn section(key: &str, entry: &Value) -> String {
+    let field = |name: &str| entry.get(name).and_then(|v| v.as_str());
+
+    let mut section = match field("buildDate") {
+        Some(date) => format!("## {} ({})\n\n", key, date),
+        None => format!("## {}\n\n", key),
+    };
+    if let Some(client_version) = field("clientVersion") {
+        section.push_str(&format!("- clientVersion: `{}`\n", client_version));
+    }
+    if let Some(build_version) = field("buildVersion") {
+        section.push_str(&format!("- buildVersion: `{}`\n", build_version));
+    }
+    if let Some(url) = field("webPlayer") {
+        match bundle_hash(url) {
+            Some(hash) => section.push_str(&format!("- bundle: `{}` ({})\n", hash, url)),
+            None => section.push_str(&format!("- bundle: {}\n", url)),
+        }
+    }
+    if let Some(other) = field("sameBundleAs") {
+        section.push_str(&format!(
+            "- same bundle as {}, no web-player changes\n",
+            other
+        ));
+    }
+    section.push('\n');
+    section
+}
+
+/// Prepends a section for `key` unless the changelog already has one.
+/// Returns whether the file changed.
+pub fn record(path: &Path, key: &str, entry: &Value) -> Result<bool> {
+    let existing = paths::read_optional(path)?.unwrap_or_default();
+
+    let heading = format!("## {}", key);
+    let already_listed = existing
+        .lines()
+        .any(|line| line == heading || line.starts_with(&format!("{} ", heading)));
+    if already_listed {
+        log_info(
+            "CHANGELOG",
+            &format!("{} already in {}", key, path.display()),
+        );
+        return Ok(false);
+    }
+
+    let body = existing.strip_prefix(HEADER).unwrap_or(&existing);
+    let content = format!("{}{}{}", HEADER, section(key, entry), body);
+    paths::write_atomic(path, content.as_bytes())?;
+
+    log_success("CHANGELOG", &format!("Added {} to {}", key, path.display()));
+    Ok(true)
+}
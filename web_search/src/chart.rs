@@ -0,0 +1,145 @@
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+
+use crate::site::escape;
+
+const WIDTH: f64 = 960.0;
+const HEIGHT: f64 = 400.0;
+const MARGIN_LEFT: f64 = 60.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 30.0;
+const MARGIN_BOTTOM: f64 = 50.0;
+
+/// Renders the cumulative number of builds over time, one dot per version.
+pub fn timeline_svg(title: &str, points: &[(String, DateTime<Utc>)]) -> String {
+    let series: Vec<Point> = points
+        .iter()
+        .enumerate()
+        .map(|(i, (key, time))| Point {
+            key: key.clone(),
+            time: *time,
+            value: i as f64 + 1.0,
+            label: String::new(),
+        })
+        .collect();
+    plot(title, &series, &points.len().to_string())
+}
+
+/// Renders the web-player bundle size of each version over time, in MB.
+pub fn size_svg(title: &str, points: &[(String, DateTime<Utc>, u64)]) -> String {
+    let mb = |bytes: u64| bytes as f64 / 1_000_000.0;
+    let series: Vec<Point> = points
+        .iter()
+        .map(|(key, time, bytes)| Point {
+            key: key.clone(),
+            time: *time,
+            value: mb(*bytes),
+            label: format!(": {:.2} MB", mb(*bytes)),
+        })
+        .collect();
+    let top = series.iter().map(|point| point.value).fold(0.0, f64::max);
+    plot(title, &series, &format!("{:.1} MB", top))
+}
+
+struct Point {
+    key: String,
+    time: DateTime<Utc>,
+    value: f64,
+    /// Appended to the tooltip.
+    label: String,
+}
+
+/// A line from 0 up to the largest value (labelled `top`) against time.
+fn plot(title: &str, points: &[Point], top: &str) -> String {
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"11\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n<text x=\"{x}\" y=\"18\" font-size=\"14\">{}</text>\n",
+        escape(title),
+        w = WIDTH,
+        h = HEIGHT,
+        x = MARGIN_LEFT
+    );
+
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        svg.push_str("<text x=\"50%\" y=\"50%\" text-anchor=\"middle\">No data</text>\n</svg>\n");
+        return svg;
+    };
+
+    let start = first.time.timestamp() as f64;
+    let span = ((last.time.timestamp() as f64) - start).max(1.0);
+    let highest = points
+        .iter()
+        .map(|point| point.value)
+        .fold(0.0, f64::max)
+        .max(f64::MIN_POSITIVE);
+    let x_of =
+        |time: &DateTime<Utc>| MARGIN_LEFT + (time.timestamp() as f64 - start) / span * plot_width;
+    let y_of = |value: f64| MARGIN_TOP + plot_height - value / highest * plot_height;
+
+    svg.push_str(&format!(
+        "<line x1=\"{l}\" y1=\"{b}\" x2=\"{r}\" y2=\"{b}\" stroke=\"#333\"/>\n<line x1=\"{l}\" y1=\"{t}\" x2=\"{l}\" y2=\"{b}\" stroke=\"#333\"/>\n",
+        l = MARGIN_LEFT,
+        r = WIDTH - MARGIN_RIGHT,
+        t = MARGIN_TOP,
+        b = MARGIN_TOP + plot_height
+    ));
+
+    let mut month = Utc
+        .with_ymd_and_hms(first.time.year(), first.time.month(), 1, 0, 0, 0)
+        .single();
+    let months = (last.time.year() - first.time.year()) * 12 + last.time.month() as i32
+        - first.time.month() as i32;
+    let label_every = (months / 12 + 1).max(1) as u32;
+    while let Some(tick) = month.filter(|tick| tick <= &last.time) {
+        if tick >= first.time && (tick.month() - 1) % label_every == 0 {
+            let x = x_of(&tick);
+            svg.push_str(&format!(
+                "<line x1=\"{x:.1}\" y1=\"{t}\" x2=\"{x:.1}\" y2=\"{b}\" stroke=\"#eee\"/>\n<text x=\"{x:.1}\" y=\"{ly}\" text-anchor=\"middle\">{}</text>\n",
+                tick.format("%Y-%m"),
+                x = x,
+                t = MARGIN_TOP,
+                b = MARGIN_TOP + plot_height,
+                ly = MARGIN_TOP + plot_height + 16.0
+            ));
+        }
+        let (year, next_month) = if tick.month() == 12 {
+            (tick.year() + 1, 1)
+        } else {
+            (tick.year(), tick.month() + 1)
+        };
+        month = Utc.with_ymd_and_hms(year, next_month, 1, 0, 0, 0).single();
+    }
+
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n<text x=\"{}\" y=\"{}\" text-anchor=\"end\">0</text>\n",
+        MARGIN_LEFT - 6.0,
+        MARGIN_TOP + 4.0,
+        escape(top),
+        MARGIN_LEFT - 6.0,
+        MARGIN_TOP + plot_height
+    ));
+
+    let path: Vec<String> = points
+        .iter()
+        .map(|point| format!("{:.1},{:.1}", x_of(&point.time), y_of(point.value)))
+        .collect();
+    svg.push_str(&format!(
+        "<polyline fill=\"none\" stroke=\"#1db954\" stroke-width=\"1.5\" points=\"{}\"/>\n",
+        path.join(" ")
+    ));
+
+    for point in points {
+        svg.push_str(&format!(
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"2\" fill=\"#1db954\"><title>{} ({}){}</title></circle>\n",
+            x_of(&point.time),
+            y_of(point.value),
+            escape(&point.key),
+            point.time.format("%Y-%m-%d %H:%M UTC"),
+            escape(&point.label)
+        ));
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
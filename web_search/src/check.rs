@@ -0,0 +1,724 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use crate::anomaly;
+use crate::bundle;
+use crate::changelog;
+use crate::config::{AnomalyAction, Config, EntryKey, Surface};
+use crate::events;
+use crate::extract;
+use crate::fetch::{self, FetchedPage};
+use crate::ignore;
+use crate::integrity;
+use crate::log::{log_error, log_info, log_success, log_warning};
+use crate::notify;
+use crate::pace;
+use crate::publish;
+use crate::regions::{self, RegionVersions};
+use crate::result::{CheckError, CheckResult, Diagnostics, ErrorKind, RedirectHop};
+use crate::robots;
+use crate::snapshot;
+use crate::source::VersionSource;
+use crate::store::{self, VersionStore};
+use crate::timing::Timings;
+use crate::token;
+use crate::tor;
+use crate::version::{first_seen_key, VersionEntry};
+use crate::Result;
+
+pub async fn run(config: &Config) -> Result<Value> {
+    let surface = config
+        .surface("web")
+        .expect("web surface is always configured");
+    run_surface(config, &surface).await
+}
+
+pub async fn run_surface(config: &Config, surface: &Surface<'_>) -> Result<Value> {
+    let store = store::open(config, surface).await?;
+    run_with_store(config, surface, store.as_ref()).await
+}
+
+/// Checks `surface` against an already opened store, e.g. a [`store::MemoryStore`]
+/// when embedding the library without touching disk.
+pub async fn run_with_store(
+    config: &Config,
+    surface: &Surface<'_>,
+    store: &dyn VersionStore,
+) -> Result<Value> {
+    let started = Instant::now();
+    let mut timings = Timings::default();
+    let check = check_surface(config, surface, store, &mut timings);
+    let result = match config.deadline_secs {
+        // Dropping the check cancels whatever request it is waiting on
+        Some(secs) => match tokio::time::timeout(Duration::from_secs(secs), check).await {
+            Ok(result) => result,
+            Err(_) => {
+                log_error(
+                    "DEADLINE",
+                    &format!("Check did not finish within {} s, cancelled", secs),
+                );
+                Ok(CheckResult {
+                    timed_out: true,
+                    ..CheckResult::failure(CheckError::new(
+                        ErrorKind::Timeout,
+                        format!("Check did not finish within {} s", secs),
+                    ))
+                })
+            }
+        },
+        None => check.await,
+    };
+    let elapsed = started.elapsed();
+
+    let outcome = match &result {
+        Ok(output) if output.timed_out => "timeout",
+        Ok(output) if !output.success => "failure",
+        Ok(output) if output.is_new == Some(true) => "new",
+        Ok(_) => "unchanged",
+        Err(_) => "error",
+    };
+    // monotonic_counter./histogram. fields become OTLP metrics under the `otel` feature
+    tracing::info!(
+        monotonic_counter.web_search_checks = 1u64,
+        histogram.web_search_check_duration_seconds = elapsed.as_secs_f64(),
+        surface = surface.name,
+        outcome,
+        "check finished"
+    );
+
+    log_info("TIMING", &timings.summary(elapsed));
+    let result = result.map(|mut output| {
+        output.timings = Some(timings.to_json(elapsed));
+        output.to_value()
+    });
+    if let Ok(output) = &result {
+        tor::record(&config.tor, output).await;
+    }
+    result
+}
+
+/// Compares what `source` reports with `store` and saves it if it is new.
+/// Unlike [`run_with_store`] there is no page to snapshot or bundle to archive.
+pub async fn run_source(source: &dyn VersionSource, store: &dyn VersionStore) -> Result<Value> {
+    Ok(check_source(source, store).await.to_value())
+}
+
+async fn check_source(source: &dyn VersionSource, store: &dyn VersionStore) -> CheckResult {
+    let name = source.name();
+    log_info("SOURCE", &format!("Fetching {}", name));
+    let VersionEntry { mut key, mut data } = match source.fetch().await {
+        Ok(entry) => entry,
+        Err(e) => {
+            log_error("SOURCE", &format!("{}: {}", name, e));
+            return CheckResult::failure(CheckError::from_error(e.as_ref(), ErrorKind::Source))
+                .with_surface(name);
+        }
+    };
+
+    let mut versions = match store.load().await {
+        Ok(versions) => versions,
+        Err(e) => {
+            log_error("FILE", &format!("Failed to load versions: {}", e));
+            return CheckResult::failure(store_error("load", &e)).with_surface(name);
+        }
+    };
+
+    let now = Utc::now();
+    if source.key() == EntryKey::FirstSeen {
+        key = first_seen(&versions, &data, now);
+    }
+    let ignored = match source.ignored() {
+        Ok(ignored) => ignored,
+        Err(e) => {
+            log_error("FILE", &format!("Failed to load the ignore list: {}", e));
+            return CheckResult::failure(store_error("load", &e)).with_surface(name);
+        }
+    };
+    let client_version = data["clientVersion"].as_str().unwrap_or_default();
+    if ignored.contains(&key) || ignored.contains(client_version) {
+        log_warning("CHECK", &format!("Version {} is on the ignore list", key));
+        let message = format!("Version {} is ignored", key);
+        return CheckResult::found(name, &key, false, message);
+    }
+    if versions.contains_key(&key) {
+        log_warning("CHECK", &format!("Version {} already exists", key));
+        let message = format!("Version {} already exists", key);
+        return CheckResult::found(name, &key, false, message);
+    }
+
+    log_success("CHECK", &format!("Version {} is NEW!", key));
+    // When we noticed it, for detection-lag stats
+    data["firstSeen"] = json!(now.to_rfc3339_opts(SecondsFormat::Secs, true));
+    // No [anomaly] checks: they expect the web player's clientVersion shape and
+    // a Config, while library sources report their upstream's own versions
+    versions.insert(key.clone(), data.clone());
+    if let Err(e) = store.save(&versions).await {
+        log_error("FILE", &format!("Failed to save versions: {}", e));
+        return CheckResult::failure(store_error("save", &e)).with_surface(name);
+    }
+    if let Err(e) = store.announce(&key, &data).await {
+        log_warning("STORE", &format!("Failed to announce {}: {}", key, e));
+    }
+
+    let message = format!("New version {} detected and saved", key);
+    CheckResult {
+        data: Some(data),
+        ..CheckResult::found(name, &key, true, message)
+    }
+}
+
+/// The key a hash-named build is stored under: the one it got when first seen,
+/// or `now` for a build not stored yet.
+fn first_seen(versions: &HashMap<String, Value>, entry: &Value, now: DateTime<Utc>) -> String {
+    versions
+        .iter()
+        .find(|(_, stored)| stored["clientVersion"] == entry["clientVersion"])
+        .map(|(key, _)| key.clone())
+        .unwrap_or_else(|| first_seen_key(now))
+}
+
+/// A version read from a surface, before it is compared with the store.
+#[derive(Debug)]
+pub struct Observation {
+    pub key: String,
+    pub entry: Value,
+    /// Reported with the check output (bundle check, regions, bundle size).
+    pub diagnostics: Diagnostics,
+    client: reqwest::Client,
+    /// Web-player URL and body, kept for archiving once the version turns out to be new.
+    bundle: Option<(String, String)>,
+    /// Content-hashed assets referenced by the page, as absolute URLs.
+    assets: Vec<String>,
+    regions: RegionVersions,
+}
+
+#[derive(Debug)]
+pub enum Observed {
+    Version(Box<Observation>),
+    /// The page held no usable version; carries the failure output.
+    Failed(Box<CheckResult>),
+}
+
+#[tracing::instrument(name = "check", skip_all, fields(surface = surface.name, key))]
+async fn check_surface(
+    config: &Config,
+    surface: &Surface<'_>,
+    store: &dyn VersionStore,
+    timings: &mut Timings,
+) -> Result<CheckResult> {
+    let observation = match observe(config, surface, timings).await? {
+        Observed::Version(observation) => *observation,
+        Observed::Failed(output) => return Ok(*output),
+    };
+    tracing::Span::current().record("key", observation.key.as_str());
+    record(config, surface, store, observation, timings).await
+}
+
+/// Fetches and parses `surface` without reading or writing the versions store.
+pub async fn observe(
+    config: &Config,
+    surface: &Surface<'_>,
+    timings: &mut Timings,
+) -> Result<Observed> {
+    let user_agent = match &config.http.user_agent {
+        Some(user_agent) => user_agent.clone(),
+        None => {
+            log_info("NET", "Getting actual User-Agent...");
+            let phase = Instant::now();
+            let user_agent = fetch::get_latest_user_agent(&config.user_agent_api)
+                .await
+                .unwrap_or_else(|_| {
+                    log_warning("NET", "Failed to get UA from API, using fallback");
+                    fetch::FALLBACK_USER_AGENT.to_string()
+                });
+            timings.record("user_agent", phase);
+            user_agent
+        }
+    };
+
+    log_success("NET", &format!("User-Agent set: {}", user_agent));
+
+    let client = fetch::build_client(&user_agent, &config.http, config.trace_http)?;
+    if !robots::allowed(&config.robots, &client, surface.url, &user_agent).await? {
+        return Ok(Observed::Failed(Box::new(CheckResult {
+            robots: Some("disallowed".to_string()),
+            ..CheckResult::failure(CheckError::new(
+                ErrorKind::RobotsDisallowed,
+                format!("robots.txt disallows {}", surface.url),
+            ))
+        })));
+    }
+
+    pace::pause(Duration::from_millis(config.http.request_jitter_ms)).await;
+    log_info(
+        "HTTP",
+        &format!("Sending request to {} ({})", surface.url, surface.name),
+    );
+
+    let phase = Instant::now();
+    let page_client = fetch::build_page_client(&user_agent, &config.http, config.trace_http)?;
+    let page =
+        fetch::fetch_page_with_retry(&page_client, surface.url, &config.http, config.trace_http)
+            .await?;
+    timings.record("page", phase);
+    timings.add_bytes(page.body.len());
+
+    log_success(
+        "HTTP",
+        &format!("HTML received ({} bytes)", page.body.len()),
+    );
+
+    if let Some(off_site) = fetch::off_site(surface.url, &page.url) {
+        let error = format!("Redirected to {}: {}", off_site.description(), page.url);
+        log_error("HTTP", &error);
+        let mut output = failure(config, &page, CheckError::new(ErrorKind::Blocked, error));
+        output.blocked = true;
+        output.redirect = Some(off_site.code().to_string());
+        output.diagnostics.redirects = redirect_chain(&page);
+        return Ok(Observed::Failed(Box::new(output)));
+    }
+
+    let phase = Instant::now();
+    let parsed = extract::parse_page(&page.body, surface.extract);
+    timings.record("parse", phase);
+    let parsed = parsed?;
+
+    let (key, mut entry) = match extract::entry_from_page(&parsed, surface.extract) {
+        Ok(VersionEntry { key, data }) => (key, data),
+        Err(e) => {
+            let error = if (200..300).contains(&page.status) {
+                CheckError::new(ErrorKind::Extraction, e.to_string())
+            } else {
+                CheckError::http(page.status, format!("HTTP {}: {}", page.status, e))
+            };
+            return Ok(Observed::Failed(Box::new(failure(config, &page, error))));
+        }
+    };
+    let version = entry["clientVersion"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    let assets: Vec<String> = parsed
+        .assets
+        .iter()
+        .map(|src| bundle::resolve_url(&page.url, src))
+        .collect();
+    if config.bundle.collect_assets && !assets.is_empty() {
+        log_info(
+            "ASSETS",
+            &format!("Collected {} hashed assets", assets.len()),
+        );
+        entry["assets"] = json!(&assets);
+    }
+
+    let mut diagnostics = Diagnostics {
+        redirects: redirect_chain(&page),
+        ..Diagnostics::default()
+    };
+
+    let bundle_url = parsed
+        .web_player_url
+        .as_deref()
+        .map(|src| bundle::resolve_url(&page.url, src));
+    let mut bundle_js = None;
+    if let Some(url) = &bundle_url {
+        if config.bundle.cross_check || config.bundle.archive {
+            let phase = Instant::now();
+            let permit = pace::wait(&config.http).await;
+            let fetched = bundle::fetch_bundle(&client, url).await;
+            drop(permit);
+            timings.record("bundle", phase);
+            match fetched {
+                Ok(js) => {
+                    timings.add_bytes(js.len());
+                    diagnostics.bundle_bytes = Some(js.len());
+                    if config.bundle.cross_check {
+                        diagnostics.bundle_check = Some(bundle::cross_check(url, &js, &version));
+                    }
+                    bundle_js = Some(js);
+                }
+                Err(e) => {
+                    log_warning("BUNDLE", &format!("Failed to fetch web-player: {}", e));
+                    if config.bundle.cross_check {
+                        diagnostics.bundle_check = Some(json!({
+                            "status": "error",
+                            "url": url,
+                            "error": e.to_string()
+                        }));
+                    }
+                }
+            }
+        }
+    }
+
+    // The token endpoint speaks for the Spotify players only
+    if config.token.enabled && surface.is_spotify() {
+        let phase = Instant::now();
+        let permit = pace::wait(&config.http).await;
+        let fetched = token::fetch_version(&config.token, &client).await;
+        drop(permit);
+        timings.record("token", phase);
+        let check = match fetched {
+            Ok(reported) => {
+                let check = token::cross_check(&config.token.url, reported.as_deref(), &version);
+                // Keep the disagreement with the entry, not only in this run's output
+                if check["status"] == "mismatch" {
+                    entry["tokenClientVersion"] = json!(reported);
+                }
+                check
+            }
+            Err(e) => {
+                log_warning("TOKEN", &format!("Token endpoint failed: {}", e));
+                json!({
+                    "status": "error",
+                    "url": config.token.url,
+                    "error": e.to_string()
+                })
+            }
+        };
+        diagnostics.token_check = Some(check);
+    }
+
+    let phase = Instant::now();
+    let region_versions = regions::scan(config, surface, &user_agent).await;
+    if !config.regions.markets.is_empty() {
+        timings.record("regions", phase);
+    }
+    if !region_versions.is_empty() {
+        diagnostics.regions = Some(regions::to_json(&region_versions));
+    }
+
+    Ok(Observed::Version(Box::new(Observation {
+        key,
+        entry,
+        diagnostics,
+        client,
+        bundle: bundle_url.zip(bundle_js),
+        assets,
+        regions: region_versions,
+    })))
+}
+
+/// Output for a new version that failed the `[anomaly]` checks; it is not
+/// saved, and with `quarantine` it is kept aside for a human to look at.
+fn suspicious(
+    config: &Config,
+    surface: &Surface<'_>,
+    key: &str,
+    entry: Value,
+    reasons: Vec<String>,
+) -> CheckResult {
+    for reason in &reasons {
+        log_warning("ANOMALY", reason);
+    }
+    let error = format!(
+        "Suspicious version {} not saved: {}",
+        key,
+        reasons.join("; ")
+    );
+    let mut output = CheckResult {
+        key: Some(key.to_string()),
+        data: Some(entry),
+        anomalies: reasons,
+        ..CheckResult::failure(CheckError::new(ErrorKind::Anomaly, error))
+            .with_surface(surface.name)
+    };
+
+    if config.anomaly.action == AnomalyAction::Quarantine {
+        let path = &config.anomaly.quarantine_file;
+        let record = json!({
+            "quarantinedAt": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            "surface": surface.name,
+            "key": key,
+            "entry": output.data,
+            "anomalies": output.anomalies
+        });
+        match events::append(path, &record) {
+            Ok(()) => {
+                log_warning("ANOMALY", &format!("Quarantined in {}", path.display()));
+                output.quarantined = Some(path.display().to_string());
+            }
+            Err(e) => log_error("ANOMALY", &format!("Failed to quarantine: {}", e)),
+        }
+    }
+    output
+}
+
+/// Compares an observation with the store and saves it if the version is new.
+async fn record(
+    config: &Config,
+    surface: &Surface<'_>,
+    store: &dyn VersionStore,
+    observation: Observation,
+    timings: &mut Timings,
+) -> Result<CheckResult> {
+    let Observation {
+        mut key,
+        mut entry,
+        diagnostics,
+        client,
+        bundle,
+        assets,
+        regions: region_versions,
+    } = observation;
+
+    log_info("CHECK", "Checking if version is new...");
+    let phase = Instant::now();
+    let loaded = store.load().await;
+    timings.record("store", phase);
+    let mut versions = match loaded {
+        Ok(versions) => versions,
+        Err(e) => {
+            log_error("FILE", &format!("Failed to load versions: {}", e));
+            return Ok(CheckResult::failure(store_error("load", &e)));
+        }
+    };
+
+    let now = Utc::now();
+    if surface.extract.key == EntryKey::FirstSeen {
+        key = first_seen(&versions, &entry, now);
+    }
+
+    let ignored = match ignore::load(surface.versions_file) {
+        Ok(ignored) => ignored,
+        Err(e) => {
+            log_error("FILE", &format!("Failed to load the ignore list: {}", e));
+            return Ok(CheckResult::failure(store_error("load", &e)));
+        }
+    };
+    let client_version = entry["clientVersion"].as_str().unwrap_or_default();
+    if ignored.contains(&key) || ignored.contains(client_version) {
+        log_warning("CHECK", &format!("Version {} is on the ignore list", key));
+        let message = format!("Version {} is ignored", key);
+        return Ok(CheckResult {
+            diagnostics,
+            ..CheckResult::found(surface.name, &key, false, message)
+        });
+    }
+
+    if versions.contains_key(&key) {
+        log_warning("CHECK", &format!("Version {} already exists", key));
+
+        if regions::record(&mut versions, &region_versions) {
+            log_info("REGION", "Updating regions of existing versions");
+            let phase = Instant::now();
+            let saved = store.save(&versions).await;
+            timings.record("store", phase);
+            if let Err(e) = saved {
+                log_error("FILE", &format!("Failed to save versions: {}", e));
+                return Ok(CheckResult::failure(store_error("save", &e)));
+            }
+        }
+
+        let message = format!("Version {} already exists", key);
+        return Ok(CheckResult {
+            diagnostics,
+            ..CheckResult::found(surface.name, &key, false, message)
+        });
+    }
+
+    log_success("CHECK", &format!("Version {} is NEW!", key));
+    // When we noticed it, for detection-lag stats
+    entry["firstSeen"] = json!(now.to_rfc3339_opts(SecondsFormat::Secs, true));
+    if config.is_extra_surface(surface.name) {
+        entry["surface"] = json!(surface.name);
+    }
+
+    if config.anomaly.action != AnomalyAction::Off {
+        let newest = store::sorted_keys(&versions).into_iter().next();
+        let reasons = anomaly::inspect(
+            &config.anomaly,
+            surface,
+            &key,
+            &entry,
+            newest.as_deref(),
+            now,
+        );
+        if !reasons.is_empty() {
+            return Ok(CheckResult {
+                diagnostics,
+                ..suspicious(config, surface, &key, entry, reasons)
+            });
+        }
+    }
+
+    if let Some((url, js)) = &bundle {
+        let phase = Instant::now();
+        archive_bundle(config, surface, &client, &key, url, js, &mut entry).await;
+        timings.record("bundle", phase);
+        if let Some(other) = bundle::same_bundle(&versions, &entry) {
+            log_warning(
+                "BUNDLE",
+                &format!("{} ships the same web-player as {}", key, other),
+            );
+            entry["sameBundleAs"] = json!(other);
+        }
+    }
+    if config.bundle.asset_sizes {
+        let phase = Instant::now();
+        record_sizes(config, &client, bundle.as_ref(), &assets, &mut entry).await;
+        timings.record("asset_sizes", phase);
+    }
+
+    versions.insert(key.clone(), entry);
+    regions::record(&mut versions, &region_versions);
+    let entry = versions[&key].clone();
+
+    let phase = Instant::now();
+    let saved = store.save(&versions).await;
+    timings.record("store", phase);
+    if let Err(e) = saved {
+        log_error("FILE", &format!("Failed to save versions: {}", e));
+        return Ok(CheckResult::failure(store_error("save", &e)));
+    }
+
+    if let Err(e) = store.announce(&key, &entry).await {
+        log_warning("STORE", &format!("Failed to announce {}: {}", key, e));
+    }
+
+    if config.events.enabled {
+        let event = events::new_version(surface, &key, &entry);
+        if let Err(e) = events::append(&config.events.path, &event) {
+            log_warning("EVENTS", &format!("Failed to append event: {}", e));
+        }
+    }
+
+    if config.changelog.enabled {
+        if let Err(e) = changelog::record(&config.changelog.path, &key, &entry) {
+            log_warning("CHANGELOG", &format!("Failed to update changelog: {}", e));
+        }
+    }
+
+    notify::new_version(&config.notify, surface, &key, &entry).await;
+    publish::after_save(&config.publish, &config.http, surface.name, &key, &versions).await;
+
+    let message = format!(
+        "New version {} detected and saved",
+        entry["clientVersion"].as_str().unwrap_or(&key)
+    );
+    Ok(CheckResult {
+        data: Some(entry),
+        diagnostics,
+        ..CheckResult::found(surface.name, &key, true, message)
+    })
+}
+
+async fn archive_bundle(
+    config: &Config,
+    surface: &Surface<'_>,
+    client: &reqwest::Client,
+    key: &str,
+    url: &str,
+    js: &str,
+    entry: &mut Value,
+) {
+    let dir = &config.bundle.archive_dir;
+
+    if config.bundle.archive {
+        entry["bundleSha256"] = json!(integrity::sha256_hex(js.as_bytes()));
+        if let Err(e) = bundle::archive(dir, surface.name, key, js, config.bundle.compression) {
+            log_warning("BUNDLE", &format!("Failed to archive web-player: {}", e));
+        }
+        if !config.bundle.scan.is_empty() {
+            match bundle::archive_scan(dir, surface.name, key, js, &config.bundle.scan) {
+                Ok(counts) => entry["bundleScan"] = json!(counts),
+                Err(e) => log_warning("BUNDLE", &format!("Failed to scan web-player: {}", e)),
+            }
+        }
+    }
+
+    if config.bundle.source_maps {
+        let permit = pace::wait(&config.http).await;
+        let source_map = bundle::fetch_source_map(client, url, js).await;
+        drop(permit);
+        entry["sourceMapAvailable"] = json!(source_map.is_some());
+        if let Some((map_url, map)) = source_map {
+            entry["sourceMap"] = json!(map_url);
+            if config.bundle.archive {
+                if let Err(e) = bundle::archive_source_map(
+                    dir,
+                    surface.name,
+                    key,
+                    &map,
+                    config.bundle.compression,
+                ) {
+                    log_warning("BUNDLE", &format!("Failed to archive source map: {}", e));
+                }
+            }
+        }
+    }
+}
+
+/// `assetBytes` per file name and `bundleBytes` for web-player.js, from HEAD
+/// requests except for a bundle that was already downloaded.
+async fn record_sizes(
+    config: &Config,
+    client: &reqwest::Client,
+    bundle: Option<&(String, String)>,
+    assets: &[String],
+    entry: &mut Value,
+) {
+    let downloaded = bundle.map(|(url, _)| url.as_str());
+    let to_probe: Vec<String> = assets
+        .iter()
+        .filter(|url| Some(url.as_str()) != downloaded)
+        .cloned()
+        .collect();
+    let mut sizes = bundle::asset_sizes(client, &config.http, &to_probe).await;
+    if let Some((url, js)) = bundle {
+        sizes.insert(url.clone(), js.len() as u64);
+    }
+    if sizes.is_empty() {
+        return;
+    }
+
+    let by_name: BTreeMap<&str, u64> = sizes
+        .iter()
+        .map(|(url, bytes)| (bundle::file_name(url), *bytes))
+        .collect();
+    let web_player = entry["webPlayer"].as_str().map(bundle::file_name);
+    if let Some(bytes) = web_player.and_then(|name| by_name.get(name)) {
+        entry["bundleBytes"] = json!(bytes);
+    }
+    entry["totalAssetBytes"] = json!(sizes.values().sum::<u64>());
+    entry["assetBytes"] = json!(by_name);
+}
+
+fn store_error(action: &str, error: &crate::Error) -> CheckError {
+    CheckError::new(
+        ErrorKind::Store,
+        format!("Failed to {} versions: {}", action, error),
+    )
+}
+
+fn failure(config: &Config, page: &FetchedPage, error: CheckError) -> CheckResult {
+    let mut output = CheckResult {
+        blocked: matches!(page.status, 401 | 403 | 429 | 451),
+        ..CheckResult::failure(error)
+    };
+
+    if config.failure_snapshots > 0 {
+        match snapshot::save_failure(&config.failures_dir, page, config.failure_snapshots) {
+            Ok(path) => {
+                log_info("SNAPSHOT", &format!("Page saved to {}", path.display()));
+                output.snapshot = Some(path.display().to_string());
+            }
+            Err(e) => log_warning("SNAPSHOT", &format!("Failed to save page snapshot: {}", e)),
+        }
+    }
+
+    output
+}
+
+fn redirect_chain(page: &FetchedPage) -> Vec<RedirectHop> {
+    page.redirects
+        .iter()
+        .map(|redirect| RedirectHop {
+            status: redirect.status,
+            from: redirect.from.clone(),
+            to: redirect.to.clone(),
+        })
+        .collect()
+}
@@ -0,0 +1,50 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::config::Compression;
+use crate::Result;
+
+impl Compression {
+    /// Picked from the file name: `.gz` is gzip, `.zst` is zstd, anything else is plain.
+    pub fn from_path(path: &Path) -> Compression {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+}
+
+pub fn encode(compression: Compression, bytes: &[u8]) -> Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(bytes.to_vec()),
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(bytes)?;
+            Ok(encoder.finish()?)
+        }
+        Compression::Zstd => Ok(zstd::encode_all(bytes, 0)?),
+    }
+}
+
+pub fn decode(compression: Compression, bytes: &[u8]) -> Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(bytes.to_vec()),
+        Compression::Gzip => {
+            let mut decoded = Vec::new();
+            GzDecoder::new(bytes).read_to_end(&mut decoded)?;
+            Ok(decoded)
+        }
+        Compression::Zstd => Ok(zstd::decode_all(bytes)?),
+    }
+}
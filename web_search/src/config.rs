@@ -0,0 +1,1685 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::log::{log_info, log_success, log_warning, TimeFormat};
+use crate::paths;
+use crate::result::ErrorKind;
+use crate::Result;
+
+pub const SPOTIFY_URL: &str = "https://open.spotify.com";
+pub const USER_AGENT_API: &str = "https://jnrbsn.github.io/user-agents/user-agents.json";
+pub const VERSIONS_FILE: &str = "versions_web.json";
+pub const FAILURES_DIR: &str = "failures";
+pub const FAILURE_SNAPSHOTS: usize = 20;
+pub const BUNDLES_DIR: &str = "bundles";
+pub const SITE_DIR: &str = "site";
+pub const CHANGELOG_FILE: &str = "CHANGELOG.md";
+pub const EVENTS_FILE: &str = "events.jsonl";
+pub const QUARANTINE_FILE: &str = "quarantine.jsonl";
+pub const BACKUPS_DIR: &str = "backups";
+pub const CONFIG_FILE: &str = "web_search.toml";
+pub const EMBED_URL: &str = "https://open.spotify.com/embed/track/4uLU6hMCjMI75M1A2tKUQC";
+pub const EMBED_VERSIONS_FILE: &str = "versions_embed.json";
+pub const YOUTUBE_MUSIC_URL: &str = "https://music.youtube.com/";
+pub const YOUTUBE_MUSIC_VERSIONS_FILE: &str = "versions_youtube_music.json";
+pub const APPLE_MUSIC_URL: &str = "https://music.apple.com/us/browse";
+pub const APPLE_MUSIC_VERSIONS_FILE: &str = "versions_apple_music.json";
+pub const DESKTOP_URL: &str = "https://formulae.brew.sh/api/cask/spotify.json";
+pub const TOKEN_URL: &str =
+    "https://open.spotify.com/get_access_token?reason=transport&productType=web_player";
+pub const SERVICE_NAME: &str = "web_search";
+pub const ENV_PREFIX: &str = "WEB_VERSIONS_";
+
+/// Options that may carry credentials. Each can instead be read from a file
+/// (`<option>_file`) or a command's output (`<option>_cmd`), and `config show`
+/// redacts them.
+pub const SECRET_OPTIONS: [&str; 15] = [
+    "http.proxy",
+    "regions.proxies",
+    "tor.control_password",
+    "healthcheck.url",
+    "results.post_url",
+    "publish.token",
+    "notify.gotify.token",
+    "notify.pushover.user",
+    "notify.pushover.token",
+    "notify.apprise.urls",
+    "serve.read_tokens",
+    "serve.admin_tokens",
+    "serve.webhook_secret",
+    "store.redis.url",
+    "store.postgres.url",
+];
+pub const REDACTED: &str = "<redacted>";
+
+/// Surfaces scraped without a `[[surfaces]]` entry.
+pub const BUILTIN_SURFACES: [&str; 4] = ["web", "embed", "youtube-music", "apple-music"];
+
+/// Short names for the most common environment overrides.
+const ENV_ALIASES: [(&str, &str); 3] = [
+    ("proxy", "http_proxy"),
+    ("user_agent", "http_user_agent"),
+    ("store", "store_backend"),
+];
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Base directory for relative data paths (versions, bundles, failures, site, changelog).
+    pub data_dir: PathBuf,
+    pub spotify_url: String,
+    pub user_agent_api: String,
+    pub versions_file: PathBuf,
+    pub failures_dir: PathBuf,
+    pub failure_snapshots: usize,
+    pub trace_http: bool,
+    /// Budget for a whole check, retries and bundle included; unlimited when unset.
+    pub deadline_secs: Option<u64>,
+    pub http: HttpConfig,
+    pub extract: ExtractConfig,
+    pub bundle: BundleConfig,
+    pub embed: EmbedConfig,
+    pub youtube_music: YouTubeMusicConfig,
+    pub apple_music: AppleMusicConfig,
+    /// More pages to check (`[[surfaces]]`), e.g. the lite or partner web shells.
+    pub surfaces: Vec<SurfaceConfig>,
+    /// Per-surface overrides (`[sources.<surface>]`), see [`Config::for_source`].
+    pub sources: BTreeMap<String, SourceConfig>,
+    pub desktop: DesktopConfig,
+    pub token: TokenConfig,
+    pub regions: RegionsConfig,
+    pub tor: TorConfig,
+    pub robots: RobotsConfig,
+    pub site: SiteConfig,
+    pub changelog: ChangelogConfig,
+    pub events: EventsConfig,
+    pub anomaly: AnomalyConfig,
+    pub backup: BackupConfig,
+    pub integrity: IntegrityConfig,
+    pub watch: WatchConfig,
+    pub runner: RunnerConfig,
+    pub healthcheck: HealthcheckConfig,
+    pub results: ResultsConfig,
+    pub publish: PublishConfig,
+    pub notify: NotifyConfig,
+    pub serve: ServeConfig,
+    pub telemetry: TelemetryConfig,
+    pub log: LogConfig,
+    pub store: StoreConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            data_dir: PathBuf::new(),
+            spotify_url: SPOTIFY_URL.to_string(),
+            user_agent_api: USER_AGENT_API.to_string(),
+            versions_file: PathBuf::from(VERSIONS_FILE),
+            failures_dir: PathBuf::from(FAILURES_DIR),
+            failure_snapshots: FAILURE_SNAPSHOTS,
+            trace_http: false,
+            deadline_secs: None,
+            http: HttpConfig::default(),
+            extract: ExtractConfig::default(),
+            bundle: BundleConfig::default(),
+            embed: EmbedConfig::default(),
+            youtube_music: YouTubeMusicConfig::default(),
+            apple_music: AppleMusicConfig::default(),
+            surfaces: Vec::new(),
+            sources: BTreeMap::new(),
+            desktop: DesktopConfig::default(),
+            token: TokenConfig::default(),
+            regions: RegionsConfig::default(),
+            tor: TorConfig::default(),
+            robots: RobotsConfig::default(),
+            site: SiteConfig::default(),
+            changelog: ChangelogConfig::default(),
+            events: EventsConfig::default(),
+            anomaly: AnomalyConfig::default(),
+            backup: BackupConfig::default(),
+            integrity: IntegrityConfig::default(),
+            watch: WatchConfig::default(),
+            runner: RunnerConfig::default(),
+            healthcheck: HealthcheckConfig::default(),
+            results: ResultsConfig::default(),
+            publish: PublishConfig::default(),
+            notify: NotifyConfig::default(),
+            serve: ServeConfig::default(),
+            telemetry: TelemetryConfig::default(),
+            log: LogConfig::default(),
+            store: StoreConfig::default(),
+        }
+    }
+}
+
+impl Config {
+    /// The config file (`web_search.toml` if present) with `WEB_VERSIONS_*`
+    /// environment variables layered on top.
+    pub fn load(path: Option<&Path>) -> Result<Config> {
+        let mut table = Config::load_file(path)?;
+        let from_env = apply_env(&mut table, std::env::vars());
+        resolve_secrets(&mut table)?;
+        let config: Config = if from_env.is_empty() {
+            serde_json::from_value(table)?
+        } else {
+            serde_json::from_value(table)
+                .map_err(|e| format!("Invalid {}* environment variable: {}", ENV_PREFIX, e))?
+        };
+        config.check_surfaces()?;
+        Ok(config)
+    }
+
+    /// Rejects `[[surfaces]]` entries that are unnamed, reuse a built-in
+    /// surface's name or share a name, since `--source` couldn't tell them apart.
+    pub fn check_surfaces(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for surface in &self.surfaces {
+            let name = surface.name.as_str();
+            if name.is_empty() {
+                return Err("Every [[surfaces]] entry needs a name".into());
+            }
+            if BUILTIN_SURFACES.contains(&name) {
+                return Err(format!(
+                    "[[surfaces]] name '{}' is taken by the built-in surface",
+                    name
+                )
+                .into());
+            }
+            if !seen.insert(name) {
+                return Err(format!("[[surfaces]] name '{}' is used more than once", name).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Only what the config file sets, as a JSON object; empty without a file.
+    pub fn load_file(path: Option<&Path>) -> Result<Value> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(CONFIG_FILE).exists() => Path::new(CONFIG_FILE),
+            None => return Ok(Value::Object(Map::new())),
+        };
+
+        log_info("CONFIG", &format!("Loading {}", path.display()));
+        let content = fs::read_to_string(path)?;
+        // Typed first, for errors that point at the line
+        let _: Config = toml::from_str(&content)?;
+        let table: toml::Value = toml::from_str(&content)?;
+        log_success("CONFIG", &format!("Loaded {}", path.display()));
+        Ok(serde_json::to_value(table)?)
+    }
+
+    /// Anchors every relative data path at `data_dir`; call once overrides are applied.
+    pub fn resolve_paths(&mut self) {
+        let dir = self.data_dir.clone();
+        for path in [
+            &mut self.versions_file,
+            &mut self.failures_dir,
+            &mut self.bundle.archive_dir,
+            &mut self.embed.versions_file,
+            &mut self.youtube_music.versions_file,
+            &mut self.apple_music.versions_file,
+            &mut self.site.out_dir,
+            &mut self.changelog.path,
+            &mut self.events.path,
+            &mut self.anomaly.quarantine_file,
+            &mut self.backup.dir,
+            &mut self.store.sqlite.path,
+        ] {
+            *path = paths::resolve(&dir, path);
+        }
+        for surface in &mut self.surfaces {
+            surface.fill_defaults();
+            surface.versions_file = paths::resolve(&dir, &surface.versions_file);
+        }
+        for path in self
+            .sources
+            .values_mut()
+            .filter_map(|source| source.versions_file.as_mut())
+        {
+            *path = paths::resolve(&dir, path);
+        }
+        for path in [&mut self.results.file, &mut self.results.log]
+            .into_iter()
+            .flatten()
+        {
+            *path = paths::resolve(&dir, path);
+        }
+    }
+
+    pub fn surface(&self, name: &str) -> Option<Surface<'_>> {
+        match name {
+            "web" => Some(Surface {
+                name: "web",
+                url: &self.spotify_url,
+                versions_file: &self.versions_file,
+                extract: &self.extract,
+            }),
+            "embed" => Some(Surface {
+                name: "embed",
+                url: &self.embed.url,
+                versions_file: &self.embed.versions_file,
+                extract: &self.embed.extract,
+            }),
+            "youtube-music" => Some(Surface {
+                name: "youtube-music",
+                url: &self.youtube_music.url,
+                versions_file: &self.youtube_music.versions_file,
+                extract: &self.youtube_music.extract,
+            }),
+            "apple-music" => Some(Surface {
+                name: "apple-music",
+                url: &self.apple_music.url,
+                versions_file: &self.apple_music.versions_file,
+                extract: &self.apple_music.extract,
+            }),
+            _ => self
+                .surfaces
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| Surface {
+                    name: &s.name,
+                    url: &s.url,
+                    versions_file: &s.versions_file,
+                    extract: &s.extract,
+                }),
+        }
+    }
+
+    /// Surfaces under `[sources]` that are not switched off, in name order.
+    pub fn enabled_sources(&self) -> Vec<&str> {
+        self.sources
+            .iter()
+            .filter(|(_, source)| source.enabled)
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// The built-in surfaces and the names of `[[surfaces]]`.
+    pub fn surface_names(&self) -> Vec<&str> {
+        let mut names = BUILTIN_SURFACES.to_vec();
+        names.extend(self.surfaces.iter().map(|s| s.name.as_str()));
+        names
+    }
+
+    /// This config with `[sources.<name>]` applied: the schedule, store and
+    /// notifiers used when checking surface `name`.
+    pub fn for_source(&self, name: &str) -> Config {
+        let mut config = self.clone();
+        let Some(source) = self.sources.get(name) else {
+            return config;
+        };
+        if let Some(interval) = source.interval_secs {
+            config.watch.interval_secs = interval;
+            config.watch.cron = None;
+        }
+        if let Some(cron) = &source.cron {
+            config.watch.cron = Some(cron.clone());
+        }
+        if let Some(backend) = source.store {
+            config.store.backend = backend;
+        }
+        if let Some(path) = &source.versions_file {
+            if let Some(file) = config.versions_file_mut(name) {
+                *file = path.clone();
+            }
+        }
+        if let Some(names) = &source.notify {
+            config.notify.keep_only(names);
+        }
+        config
+    }
+
+    fn versions_file_mut(&mut self, surface: &str) -> Option<&mut PathBuf> {
+        match surface {
+            "web" => Some(&mut self.versions_file),
+            "embed" => Some(&mut self.embed.versions_file),
+            "youtube-music" => Some(&mut self.youtube_music.versions_file),
+            "apple-music" => Some(&mut self.apple_music.versions_file),
+            _ => self
+                .surfaces
+                .iter_mut()
+                .find(|s| s.name == surface)
+                .map(|s| &mut s.versions_file),
+        }
+    }
+
+    /// The extract settings of surface `name`, built in or from `[[surfaces]]`.
+    pub fn extract_mut(&mut self, surface: &str) -> Option<&mut ExtractConfig> {
+        match surface {
+            "web" => Some(&mut self.extract),
+            "embed" => Some(&mut self.embed.extract),
+            "youtube-music" => Some(&mut self.youtube_music.extract),
+            "apple-music" => Some(&mut self.apple_music.extract),
+            _ => self
+                .surfaces
+                .iter_mut()
+                .find(|s| s.name == surface)
+                .map(|s| &mut s.extract),
+        }
+    }
+
+    /// Whether `name` comes from `[[surfaces]]` rather than being built in.
+    pub fn is_extra_surface(&self, name: &str) -> bool {
+        !BUILTIN_SURFACES.contains(&name) && self.surfaces.iter().any(|s| s.name == name)
+    }
+}
+
+/// A scraped page together with the file its versions are stored in.
+#[derive(Debug, Clone, Copy)]
+pub struct Surface<'a> {
+    pub name: &'a str,
+    pub url: &'a str,
+    pub versions_file: &'a Path,
+    pub extract: &'a ExtractConfig,
+}
+
+impl Surface<'_> {
+    /// The Spotify players, whose clientVersion has Spotify's
+    /// `1.2.61.443.g1a2b3c4d` shape and whose tokens Spotify's endpoints vouch for.
+    pub fn is_spotify(&self) -> bool {
+        matches!(self.name, "web" | "embed")
+    }
+}
+
+/// Where the embedded config lives and which of its fields carry the version.
+/// Field names starting with `/` are treated as JSON pointers.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ExtractConfig {
+    pub selectors: Vec<String>,
+    pub regex: String,
+    pub client_version_field: String,
+    /// Empty for pages that carry no build date.
+    pub build_date_field: String,
+    pub build_version_field: String,
+    /// How the tag's text holds the JSON: "base64" (appServerConfig) or "none".
+    pub decode: Decode,
+    /// File name prefix of the main JS bundle among `<script src>` tags
+    /// (`web-player` matches `web-player.ca73afa1.js`); empty to not look for one.
+    pub bundle_marker: String,
+    /// Refuse partial or ambiguous data instead of saving what could be read.
+    pub strict: bool,
+    /// What entries are stored under: the version, or when a build was first
+    /// seen for pages whose builds are named by a hash.
+    pub key: EntryKey,
+}
+
+impl Default for ExtractConfig {
+    fn default() -> Self {
+        ExtractConfig {
+            selectors: vec![
+                r#"script[id="appServerConfig"][type="text/plain"]"#.to_string(),
+                r#"script[id="appServerConfig"]"#.to_string(),
+                r#"script#appServerConfig"#.to_string(),
+            ],
+            regex: r#"<script[^>]*id="appServerConfig"[^>]*>([^<]+)</script>"#.to_string(),
+            client_version_field: "clientVersion".to_string(),
+            build_date_field: "buildDate".to_string(),
+            build_version_field: "buildVersion".to_string(),
+            decode: Decode::Base64,
+            bundle_marker: "web-player".to_string(),
+            strict: false,
+            key: EntryKey::Version,
+        }
+    }
+}
+
+/// Client settings shared by the page, bundle and region requests.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Fractions are kept, e.g. `2.5`.
+    pub timeout_secs: f64,
+    /// Fixed User-Agent; fetched from `user_agent_api` when unset.
+    pub user_agent: Option<String>,
+    /// Used for every request except regions with a proxy of their own.
+    pub proxy: Option<String>,
+    /// Extra attempts after a transport error or a 429/5xx response.
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each one after it.
+    pub retry_backoff_ms: u64,
+    pub http_version: HttpVersion,
+    /// Response encodings to advertise and decode: `gzip`, `brotli`, `deflate`.
+    /// `zstd` is rejected: the HTTP client (reqwest 0.11) can't decode it.
+    pub compression: Vec<String>,
+    /// Lowest TLS version to accept: `"1.0"`, `"1.1"`, `"1.2"` or `"1.3"`.
+    pub min_tls_version: Option<String>,
+    /// PEM file of extra root certificates, e.g. a corporate proxy's CA.
+    pub ca_bundle: Option<PathBuf>,
+    /// DNS-over-HTTPS endpoint speaking the JSON API, used instead of the system resolver.
+    pub doh_url: Option<String>,
+    /// Fixed addresses per host name; these skip DNS entirely.
+    pub hosts: HashMap<String, Vec<IpAddr>>,
+    /// Requests in flight at once across the process; 0 is unlimited.
+    pub max_concurrency: usize,
+    /// Minimum time between two request starts.
+    pub request_delay_ms: u64,
+    /// Cap on request starts per minute; 0 is uncapped.
+    pub requests_per_minute: u32,
+    /// Random pause of up to this long between the User-Agent lookup and the page request.
+    pub request_jitter_ms: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            timeout_secs: 30.0,
+            user_agent: None,
+            proxy: None,
+            retries: 0,
+            retry_backoff_ms: 1000,
+            http_version: HttpVersion::Auto,
+            compression: Vec::new(),
+            min_tls_version: None,
+            ca_bundle: None,
+            doh_url: None,
+            hosts: HashMap::new(),
+            max_concurrency: 0,
+            request_delay_ms: 0,
+            requests_per_minute: 0,
+            request_jitter_ms: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HttpVersion {
+    /// HTTP/2 when the server offers it over TLS, HTTP/1.1 otherwise.
+    #[default]
+    Auto,
+    /// HTTP/1.1 only.
+    Http1,
+    /// HTTP/2 only, without upgrade negotiation.
+    Http2,
+}
+
+impl HttpConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs_f64(self.timeout_secs.max(0.0))
+    }
+
+    /// Wait before retry number `attempt` (0-based).
+    pub fn retry_delay(&self, attempt: u32) -> Duration {
+        Duration::from_millis(self.retry_backoff_ms).saturating_mul(2u32.saturating_pow(attempt))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BundleConfig {
+    /// Fetch web-player.js and compare the version embedded in it.
+    pub cross_check: bool,
+    /// Store every content-hashed asset URL referenced by the page with new versions.
+    pub collect_assets: bool,
+    /// Record the byte size of web-player.js and every other hashed asset of
+    /// new versions (HEAD requests), for `stats --size-chart`.
+    pub asset_sizes: bool,
+    /// Save web-player.js of every new version under `archive_dir/<surface>/<key>.js`.
+    pub archive: bool,
+    pub archive_dir: PathBuf,
+    /// Probe the bundle's sourceMappingURL for new versions and archive the map.
+    pub source_maps: bool,
+    /// Compress archived bundles and source maps: "none", "gzip" or "zstd".
+    pub compression: Compression,
+    /// Named regexes run over every archived bundle; the distinct matches
+    /// (capture group 1 when there is one) go to `<key>.scan.json`.
+    pub scan: BTreeMap<String, String>,
+}
+
+impl Default for BundleConfig {
+    fn default() -> Self {
+        BundleConfig {
+            cross_check: true,
+            collect_assets: false,
+            asset_sizes: false,
+            archive: false,
+            archive_dir: PathBuf::from(BUNDLES_DIR),
+            source_maps: true,
+            compression: Compression::None,
+            scan: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntryKey {
+    /// The clientVersion's first four segments, e.g. `1.2.86.316`.
+    #[default]
+    Version,
+    /// When the build was first seen, in UTC, e.g. `2026.03.15.094512`, so
+    /// hashes still sort by age. The hash stays in `clientVersion`.
+    FirstSeen,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Decode {
+    #[default]
+    Base64,
+    /// The text is JSON as is, e.g. a `<script type="application/json">` tag.
+    None,
+    /// The text is the value itself, read as the field `text`.
+    Text,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EmbedConfig {
+    pub url: String,
+    pub versions_file: PathBuf,
+    pub extract: ExtractConfig,
+}
+
+impl Default for EmbedConfig {
+    fn default() -> Self {
+        EmbedConfig {
+            url: EMBED_URL.to_string(),
+            versions_file: PathBuf::from(EMBED_VERSIONS_FILE),
+            extract: ExtractConfig {
+                bundle_marker: "embed".to_string(),
+                ..ExtractConfig::default()
+            },
+        }
+    }
+}
+
+/// An extra page with its own appServerConfig, checked like `web` under its
+/// own name. The built-in names (`web`, `embed`, `youtube-music`,
+/// `apple-music`) are taken. With `extract` pointed at another
+/// script config this is also how other services' web apps are tracked.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SurfaceConfig {
+    pub name: String,
+    pub url: String,
+    /// Defaults to `versions_<name>.json`.
+    pub versions_file: PathBuf,
+    pub extract: ExtractConfig,
+}
+
+impl SurfaceConfig {
+    /// Sets the default `versions_file` if none was given.
+    pub fn fill_defaults(&mut self) {
+        if self.versions_file.as_os_str().is_empty() {
+            self.versions_file = PathBuf::from(format!("versions_{}.json", self.name));
+        }
+    }
+}
+
+/// music.youtube.com, whose `ytcfg.set({...})` calls carry the web client's
+/// `INNERTUBE_CLIENT_VERSION` (e.g. `1.20260310.01.00`) but no build date.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct YouTubeMusicConfig {
+    pub url: String,
+    pub versions_file: PathBuf,
+    pub extract: ExtractConfig,
+}
+
+impl Default for YouTubeMusicConfig {
+    fn default() -> Self {
+        YouTubeMusicConfig {
+            url: YOUTUBE_MUSIC_URL.to_string(),
+            versions_file: PathBuf::from(YOUTUBE_MUSIC_VERSIONS_FILE),
+            extract: ExtractConfig {
+                selectors: Vec::new(),
+                regex: r"(?s)ytcfg\.set\((\{.*?\})\);".to_string(),
+                decode: Decode::None,
+                client_version_field: "INNERTUBE_CLIENT_VERSION".to_string(),
+                build_date_field: String::new(),
+                build_version_field: "INNERTUBE_CONTEXT_CLIENT_VERSION".to_string(),
+                bundle_marker: String::new(),
+                ..ExtractConfig::default()
+            },
+        }
+    }
+}
+
+/// The Apple Music web app, which carries no version; its build is the content
+/// hash of the `index` script (`/assets/index~8a3c2b1f0e.js`), stored under
+/// when it was first seen.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppleMusicConfig {
+    pub url: String,
+    pub versions_file: PathBuf,
+    pub extract: ExtractConfig,
+}
+
+impl Default for AppleMusicConfig {
+    fn default() -> Self {
+        AppleMusicConfig {
+            url: APPLE_MUSIC_URL.to_string(),
+            versions_file: PathBuf::from(APPLE_MUSIC_VERSIONS_FILE),
+            extract: ExtractConfig {
+                selectors: Vec::new(),
+                regex: r#"<script[^>]+src="[^"]*/assets/index[~.-]([0-9A-Za-z_]+)\.js""#
+                    .to_string(),
+                decode: Decode::Text,
+                client_version_field: "text".to_string(),
+                build_date_field: String::new(),
+                build_version_field: String::new(),
+                bundle_marker: String::new(),
+                key: EntryKey::FirstSeen,
+                ..ExtractConfig::default()
+            },
+        }
+    }
+}
+
+/// What one surface does differently from the rest of the config. Unset
+/// fields keep the top-level value.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SourceConfig {
+    /// Included in `run` and `watch --all`.
+    pub enabled: bool,
+    /// `[watch] interval_secs` for this surface.
+    pub interval_secs: Option<u64>,
+    /// `[watch] cron` for this surface.
+    pub cron: Option<String>,
+    pub versions_file: Option<PathBuf>,
+    pub store: Option<StoreBackend>,
+    /// Notifiers to use, out of those configured in `[notify]`: "desktop",
+    /// "gotify", "pushover", "apprise". An empty list sends none.
+    pub notify: Option<Vec<String>>,
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        SourceConfig {
+            enabled: true,
+            interval_secs: None,
+            cron: None,
+            versions_file: None,
+            store: None,
+            notify: None,
+        }
+    }
+}
+
+/// The `desktop` version source: a JSON document naming the current desktop
+/// client release, by default Homebrew's Spotify cask.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DesktopConfig {
+    pub url: String,
+    /// Field holding the version; a leading `/` makes it a JSON pointer.
+    /// Anything after the first comma (cask build suffixes) is kept apart.
+    pub version_field: String,
+}
+
+impl Default for DesktopConfig {
+    fn default() -> Self {
+        DesktopConfig {
+            url: DESKTOP_URL.to_string(),
+            version_field: "version".to_string(),
+        }
+    }
+}
+
+/// The web player's anonymous token bootstrap, asked for its client build as
+/// a second opinion on the page's `clientVersion`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TokenConfig {
+    pub enabled: bool,
+    pub url: String,
+    /// Field holding the build; a leading `/` makes it a JSON pointer.
+    pub version_field: String,
+}
+
+impl Default for TokenConfig {
+    fn default() -> Self {
+        TokenConfig {
+            enabled: false,
+            url: TOKEN_URL.to_string(),
+            version_field: "clientVersion".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RegionsConfig {
+    /// Markets to re-request the page as, e.g. `["de", "jp", "us", "br"]`.
+    pub markets: Vec<String>,
+    /// Optional proxy URL per market, used for that market's request.
+    pub proxies: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SiteConfig {
+    pub out_dir: PathBuf,
+    pub title: String,
+    /// Public URL the site is served from, used for absolute links in feed.xml.
+    pub base_url: String,
+    pub feed_items: usize,
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        SiteConfig {
+            out_dir: PathBuf::from(SITE_DIR),
+            title: "Spotify Web versions".to_string(),
+            base_url: "https://loaderspot.github.io/web-versions".to_string(),
+            feed_items: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChangelogConfig {
+    pub enabled: bool,
+    pub path: PathBuf,
+}
+
+impl Default for ChangelogConfig {
+    fn default() -> Self {
+        ChangelogConfig {
+            enabled: true,
+            path: PathBuf::from(CHANGELOG_FILE),
+        }
+    }
+}
+
+/// Append-only JSON Lines log of every detected version.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    pub enabled: bool,
+    pub path: PathBuf,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        EventsConfig {
+            enabled: true,
+            path: PathBuf::from(EVENTS_FILE),
+        }
+    }
+}
+
+/// Sanity checks on a new version before it is saved.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    pub action: AnomalyAction,
+    /// Suspicious entries are appended here as JSON lines with `quarantine`.
+    pub quarantine_file: PathBuf,
+    /// A build time further ahead than this is suspicious.
+    pub max_future_hours: i64,
+    /// A build time further back than this is suspicious.
+    pub max_age_days: i64,
+    /// A version this many minor series (third segment) behind the newest stored one is suspicious.
+    pub max_backward_series: u64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            action: AnomalyAction::Quarantine,
+            quarantine_file: PathBuf::from(QUARANTINE_FILE),
+            max_future_hours: 48,
+            max_age_days: 365,
+            max_backward_series: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnomalyAction {
+    /// Don't save it; keep it in `quarantine_file` for a human to look at.
+    #[default]
+    Quarantine,
+    /// Don't save it.
+    Reject,
+    /// Save it anyway.
+    Off,
+}
+
+/// Copies of the versions file taken before every write (file store only).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    pub dir: PathBuf,
+    /// Backups kept per versions file; older ones are deleted.
+    pub keep: usize,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        BackupConfig {
+            enabled: true,
+            dir: PathBuf::from(BACKUPS_DIR),
+            keep: 10,
+        }
+    }
+}
+
+/// Sidecars written next to the versions file for downstream consumers (file store only).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IntegrityConfig {
+    /// Write `<file>.sha256`.
+    pub checksum: bool,
+    /// Base64 ed25519 secret key file; when set, `<file>.sig` is written too.
+    pub signing_key: Option<PathBuf>,
+    /// Base64 ed25519 public key that `verify` checks signatures against.
+    pub public_key: Option<String>,
+}
+
+impl Default for IntegrityConfig {
+    fn default() -> Self {
+        IntegrityConfig {
+            checksum: true,
+            signing_key: None,
+            public_key: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    pub interval_secs: u64,
+    /// Each wait between checks is moved by a random amount up to this, either way.
+    pub jitter_secs: u64,
+    /// Five-field cron expression in local time; replaces the interval when set.
+    pub cron: Option<String>,
+    pub adaptive: AdaptiveConfig,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig {
+            interval_secs: 300,
+            jitter_secs: 0,
+            cron: None,
+            adaptive: AdaptiveConfig::default(),
+        }
+    }
+}
+
+/// When `run` (every source at once) exits non-zero.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RunnerConfig {
+    pub fail_on: FailOn,
+    /// Error kinds that never count as a failure, e.g. `["rate_limited"]`.
+    pub ignore: Vec<ErrorKind>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailOn {
+    /// Any source failed.
+    #[default]
+    Any,
+    /// Every source failed.
+    All,
+    Never,
+}
+
+/// Poll more often in the hours of the week when builds usually land,
+/// learned from the build times already in the versions file.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AdaptiveConfig {
+    pub enabled: bool,
+    pub min_interval_secs: u64,
+    pub max_interval_secs: u64,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        AdaptiveConfig {
+            enabled: false,
+            min_interval_secs: 120,
+            max_interval_secs: 1800,
+        }
+    }
+}
+
+/// New Tor circuits for checks routed through Tor's SOCKS port
+/// (`[http] proxy = "socks5h://127.0.0.1:9050"`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TorConfig {
+    /// Control port address; identity rotation is off while unset.
+    pub control_addr: Option<String>,
+    /// Password for `HashedControlPassword`.
+    pub control_password: Option<String>,
+    /// Auth cookie for `CookieAuthentication`, used instead of the password.
+    pub cookie_file: Option<PathBuf>,
+    /// Consecutive blocked checks after which NEWNYM is sent; 0 never sends it.
+    pub newnym_after: u32,
+}
+
+impl Default for TorConfig {
+    fn default() -> Self {
+        TorConfig {
+            control_addr: None,
+            control_password: None,
+            cookie_file: None,
+            newnym_after: 3,
+        }
+    }
+}
+
+/// Opt-in robots.txt checks before each surface's page is requested.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RobotsConfig {
+    pub enabled: bool,
+    /// How long a site's robots.txt is reused before it is fetched again.
+    pub cache_secs: u64,
+}
+
+impl Default for RobotsConfig {
+    fn default() -> Self {
+        RobotsConfig {
+            enabled: false,
+            cache_secs: 24 * 60 * 60,
+        }
+    }
+}
+
+/// healthchecks.io-style heartbeat, pinged after every check.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HealthcheckConfig {
+    /// Pinged on success; `<url>/fail` is pinged when a check fails.
+    pub url: Option<String>,
+}
+
+/// Where the result JSON of each check goes besides stdout.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ResultsConfig {
+    /// Replaced with the latest result after every check.
+    pub file: Option<PathBuf>,
+    /// Every result appended as one JSON line.
+    pub log: Option<PathBuf>,
+    /// Every result POSTed here as `application/json`.
+    pub post_url: Option<String>,
+}
+
+/// Where new versions are pushed, for a hub that collects many scrapers.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PublishConfig {
+    /// Off when unset. `{surface}` is replaced with the surface name.
+    pub url: Option<String>,
+    pub method: PublishMethod,
+    pub payload: PublishPayload,
+    /// Sent as `Authorization: Bearer <token>`.
+    pub token: Option<String>,
+    pub timeout_secs: u64,
+}
+
+impl Default for PublishConfig {
+    fn default() -> Self {
+        PublishConfig {
+            url: None,
+            method: PublishMethod::default(),
+            payload: PublishPayload::default(),
+            token: None,
+            timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishMethod {
+    #[default]
+    Put,
+    Post,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PublishPayload {
+    /// The whole versions map, as in the versions file. It replaces the hub's
+    /// copy, so only suits a single publisher per surface.
+    File,
+    /// `{"surface", "key", "entry"}` for the new version only.
+    #[default]
+    Entry,
+}
+
+/// Notifications sent when a new version is saved.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    /// Extra attempts per notifier after a failed send.
+    pub retries: u32,
+    /// Wait before the first retry; doubles with each further one.
+    pub retry_backoff_ms: u64,
+    pub desktop: DesktopNotifyConfig,
+    pub gotify: GotifyConfig,
+    pub pushover: PushoverConfig,
+    pub apprise: AppriseConfig,
+}
+
+impl NotifyConfig {
+    /// Turns off every backend not named in `names`.
+    pub fn keep_only(&mut self, names: &[String]) {
+        let keep = |name: &str| names.iter().any(|n| n == name);
+        if !keep("desktop") {
+            self.desktop = DesktopNotifyConfig::default();
+        }
+        if !keep("gotify") {
+            self.gotify = GotifyConfig::default();
+        }
+        if !keep("pushover") {
+            self.pushover = PushoverConfig::default();
+        }
+        if !keep("apprise") {
+            self.apprise = AppriseConfig::default();
+        }
+    }
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        NotifyConfig {
+            retries: 2,
+            retry_backoff_ms: 1000,
+            desktop: DesktopNotifyConfig::default(),
+            gotify: GotifyConfig::default(),
+            pushover: PushoverConfig::default(),
+            apprise: AppriseConfig::default(),
+        }
+    }
+}
+
+/// A native notification on the machine running the check (`notify-desktop` feature).
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DesktopNotifyConfig {
+    pub enabled: bool,
+}
+
+/// A self-hosted Gotify server (`notify-gotify` feature); sends once `url` and `token` are set.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GotifyConfig {
+    pub url: Option<String>,
+    /// Application token.
+    pub token: Option<String>,
+    pub priority: u8,
+}
+
+impl Default for GotifyConfig {
+    fn default() -> Self {
+        GotifyConfig {
+            url: None,
+            token: None,
+            priority: 5,
+        }
+    }
+}
+
+impl GotifyConfig {
+    pub fn is_set(&self) -> bool {
+        self.url.is_some() && self.token.is_some()
+    }
+}
+
+/// Pushover (`notify-pushover` feature); sends once `user` and `token` are set.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PushoverConfig {
+    /// User or group key.
+    pub user: Option<String>,
+    /// Application token.
+    pub token: Option<String>,
+    /// -2 (silent) to 2 (emergency, repeated until acknowledged).
+    pub priority: i8,
+    pub api_url: String,
+}
+
+impl Default for PushoverConfig {
+    fn default() -> Self {
+        PushoverConfig {
+            user: None,
+            token: None,
+            priority: 0,
+            api_url: "https://api.pushover.net/1/messages.json".to_string(),
+        }
+    }
+}
+
+impl PushoverConfig {
+    pub fn is_set(&self) -> bool {
+        self.user.is_some() && self.token.is_some()
+    }
+}
+
+/// Apprise (`notify-apprise` feature): one notifier for every service Apprise
+/// has a URL scheme for. Sends once `urls` or `api_url` is set.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AppriseConfig {
+    /// Apprise URLs, e.g. `tgram://bot-token/chat-id`.
+    pub urls: Vec<String>,
+    /// An Apprise API server's notify endpoint (`http://apprise:8000/notify`,
+    /// or `/notify/<key>` for URLs stored there); the CLI is used when unset.
+    pub api_url: Option<String>,
+    /// The Apprise CLI.
+    pub command: String,
+}
+
+impl Default for AppriseConfig {
+    fn default() -> Self {
+        AppriseConfig {
+            urls: Vec::new(),
+            api_url: None,
+            command: "apprise".to_string(),
+        }
+    }
+}
+
+impl AppriseConfig {
+    pub fn is_set(&self) -> bool {
+        !self.urls.is_empty() || self.api_url.is_some()
+    }
+}
+
+/// `web_search serve`: long-running API over the store.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ServeConfig {
+    pub http_addr: String,
+    /// Listen address of the gRPC service (`serve --grpc`).
+    pub grpc_addr: String,
+    /// Check for new versions in the server process on the `[watch]` schedule.
+    pub scrape: bool,
+    /// Bearer tokens for `/latest`, `/versions` and gRPC; open to all while empty.
+    pub read_tokens: Vec<String>,
+    /// Bearer tokens that may also use `/admin/*`; admin endpoints are off while empty.
+    pub admin_tokens: Vec<String>,
+    /// `Cache-Control: max-age` of `/latest` and `/versions`.
+    pub cache_max_age_secs: u64,
+    /// How often the store is re-read to pick up versions saved by other processes.
+    pub poll_secs: u64,
+    /// Requests per minute per client IP; 0 turns rate limiting off.
+    pub rate_limit_per_min: u32,
+    /// Requests a client may make at once before the per-minute rate applies.
+    pub rate_limit_burst: u32,
+    /// Larger request bodies get 413.
+    pub max_body_bytes: usize,
+    /// Requests whose header names and values add up to more get 431.
+    pub max_header_bytes: usize,
+    /// Accept `POST /hooks/check` to check right away (`serve --webhook-listener`).
+    pub webhook_listener: bool,
+    /// Shared secret `/hooks/check` requests must carry or sign their body with.
+    pub webhook_secret: Option<String>,
+    /// Origins browsers may call the API from (`*` for any); CORS is off while empty.
+    pub cors_origins: Vec<String>,
+    pub cors_methods: Vec<String>,
+    /// How long browsers may cache a preflight answer.
+    pub cors_max_age_secs: u64,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        ServeConfig {
+            http_addr: "127.0.0.1:8080".to_string(),
+            grpc_addr: "127.0.0.1:50051".to_string(),
+            scrape: true,
+            read_tokens: Vec::new(),
+            admin_tokens: Vec::new(),
+            cache_max_age_secs: 60,
+            poll_secs: 30,
+            rate_limit_per_min: 120,
+            rate_limit_burst: 30,
+            max_body_bytes: 16 * 1024,
+            max_header_bytes: 8 * 1024,
+            webhook_listener: false,
+            webhook_secret: None,
+            cors_origins: Vec::new(),
+            cors_methods: vec!["GET".to_string()],
+            cors_max_age_secs: 3600,
+        }
+    }
+}
+
+/// OTLP export of tracing spans and check metrics (needs the `otel` feature).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// Collector gRPC endpoint, e.g. `http://localhost:4317`; export is off when unset.
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        TelemetryConfig {
+            otlp_endpoint: None,
+            service_name: SERVICE_NAME.to_string(),
+        }
+    }
+}
+
+/// Log line timestamps; RFC 3339 with the local offset unless overridden.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// chrono strftime pattern, e.g. `%Y-%m-%d %H:%M:%S%.3f`.
+    pub timestamp_format: Option<String>,
+    /// Use the original `dd.mm.yyyy-h:m:s:cc` stamp.
+    pub legacy_timestamps: bool,
+}
+
+impl LogConfig {
+    pub fn time_format(&self) -> Result<TimeFormat> {
+        if self.legacy_timestamps {
+            return Ok(TimeFormat::Legacy);
+        }
+        match &self.timestamp_format {
+            Some(pattern) => TimeFormat::custom(pattern),
+            None => Ok(TimeFormat::Rfc3339),
+        }
+    }
+}
+
+/// Where the versions map lives. Each surface gets its own file or object.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StoreConfig {
+    pub backend: StoreBackend,
+    /// Recover what parses from a damaged versions file (plus the latest backup)
+    /// instead of failing the run. File store only.
+    pub salvage: bool,
+    /// Saves kept in `<versions_file>.journal.json` for `undo`; 0 disables
+    /// the journal. File store only.
+    pub journal: usize,
+    pub s3: S3Config,
+    pub redis: RedisConfig,
+    pub postgres: PostgresConfig,
+    pub sqlite: SqliteConfig,
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        StoreConfig {
+            backend: StoreBackend::default(),
+            salvage: true,
+            journal: 20,
+            s3: S3Config::default(),
+            redis: RedisConfig::default(),
+            postgres: PostgresConfig::default(),
+            sqlite: SqliteConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreBackend {
+    /// The surface's `versions_file` on local disk.
+    #[default]
+    File,
+    /// An object in an S3-compatible bucket (needs the `s3` feature).
+    S3,
+    /// A Redis hash per surface, with new versions published on a channel (needs the `redis` feature).
+    Redis,
+    /// A `versions` table in PostgreSQL, migrated on connect (needs the `postgres` feature).
+    Postgres,
+    /// A `versions` table in a local SQLite file (needs the `store-sqlite` feature).
+    Sqlite,
+}
+
+/// S3-compatible object storage. Credentials come from the usual AWS environment/profile chain.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct S3Config {
+    pub bucket: String,
+    /// Prepended to the versions file name to form the object key, e.g. `spotify/`.
+    pub prefix: String,
+    pub region: Option<String>,
+    /// Custom endpoint for MinIO, R2 and the like; switches to path-style addressing.
+    pub endpoint: Option<String>,
+}
+
+/// Redis hash `<key_prefix>:<surface>` mapping version keys to entry JSON.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RedisConfig {
+    pub url: String,
+    pub key_prefix: String,
+    /// Pub/sub channel that gets `{"surface", "key", "data"}` for every new version.
+    pub channel: String,
+}
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        RedisConfig {
+            url: "redis://127.0.0.1/".to_string(),
+            key_prefix: "web_search:versions".to_string(),
+            channel: "web_search:new_versions".to_string(),
+        }
+    }
+}
+
+/// PostgreSQL connection; the schema in `migrations/` is applied on connect.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PostgresConfig {
+    pub url: String,
+    pub max_connections: u32,
+}
+
+impl Default for PostgresConfig {
+    fn default() -> Self {
+        PostgresConfig {
+            url: "postgres://localhost/web_search".to_string(),
+            max_connections: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SqliteConfig {
+    /// Created on first use; every surface keeps its rows in the same file.
+    pub path: PathBuf,
+}
+
+impl Default for SqliteConfig {
+    fn default() -> Self {
+        SqliteConfig {
+            path: PathBuf::from("versions.db"),
+        }
+    }
+}
+
+/// Sets every option named by a `WEB_VERSIONS_<SECTION>_<KEY>` variable in
+/// `table`, e.g. `WEB_VERSIONS_HTTP_TIMEOUT_SECS=10`. Lists take comma-separated
+/// values or TOML arrays, maps take TOML inline tables. Returns the options set,
+/// as dotted paths.
+pub fn apply_env(
+    table: &mut Value,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Vec<String> {
+    let schema = serde_json::to_value(Config::default()).unwrap_or_default();
+    let mut applied = Vec::new();
+
+    let mut vars: Vec<(String, String)> = vars
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+        .collect();
+    vars.sort();
+    for (name, raw) in vars {
+        let mut option = name[ENV_PREFIX.len()..].to_ascii_lowercase();
+        if let Some((_, target)) = ENV_ALIASES.iter().find(|(alias, _)| *alias == option) {
+            option = target.to_string();
+        }
+        let Some(path) = env_path(&schema, &option).or_else(|| secret_source_path(&option)) else {
+            log_warning(
+                "CONFIG",
+                &format!("{} does not match any config option", name),
+            );
+            continue;
+        };
+
+        // Map entries, `*_file` and `*_cmd` aren't in the schema and are text
+        let leaf = path
+            .iter()
+            .try_fold(&schema, |value, key| value.get(key))
+            .cloned()
+            .unwrap_or(Value::String(String::new()));
+        let value = env_value(&leaf, &raw);
+        let mut target = &mut *table;
+        for key in &path[..path.len() - 1] {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            target = target
+                .as_object_mut()
+                .expect("just made an object")
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        if let Some(object) = target.as_object_mut() {
+            object.insert(path[path.len() - 1].clone(), value);
+        }
+
+        let dotted = path.join(".");
+        log_info("CONFIG", &format!("{} sets {}", name, dotted));
+        applied.push(dotted);
+    }
+    applied
+}
+
+/// Splits `option` (`http_timeout_secs`) into the keys of `schema` it names
+/// (`["http", "timeout_secs"]`), preferring the longest key at each level.
+fn env_path(schema: &Value, option: &str) -> Option<Vec<String>> {
+    let object = schema.as_object()?;
+    // A map section such as [regions.proxies]: the rest is the key
+    if object.is_empty() {
+        return Some(vec![option.to_string()]);
+    }
+
+    let mut keys: Vec<&String> = object.keys().collect();
+    keys.sort_by_key(|key| std::cmp::Reverse(key.len()));
+    for key in keys {
+        if option == key {
+            return Some(vec![key.clone()]);
+        }
+        let Some(rest) = option
+            .strip_prefix(key.as_str())
+            .and_then(|rest| rest.strip_prefix('_'))
+        else {
+            continue;
+        };
+        if let Some(mut path) = env_path(&object[key], rest) {
+            path.insert(0, key.clone());
+            return Some(path);
+        }
+    }
+    None
+}
+
+/// `raw` as the JSON type of the option's default.
+fn env_value(default: &Value, raw: &str) -> Value {
+    let toml_literal = || {
+        toml::from_str::<toml::Table>(&format!("value = {}", raw))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .and_then(|value| serde_json::to_value(value).ok())
+    };
+    match default {
+        Value::Bool(_) => match raw.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Value::Bool(true),
+            "0" | "false" | "no" | "off" => Value::Bool(false),
+            _ => Value::String(raw.to_string()),
+        },
+        Value::Number(_) => toml_literal()
+            .filter(Value::is_number)
+            .unwrap_or_else(|| Value::String(raw.to_string())),
+        Value::Array(_) if raw.trim_start().starts_with('[') => {
+            toml_literal().unwrap_or_else(|| Value::String(raw.to_string()))
+        }
+        Value::Array(_) => Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        ),
+        Value::Object(_) => toml_literal().unwrap_or_else(|| Value::String(raw.to_string())),
+        Value::String(_) => Value::String(raw.to_string()),
+        // Unset options: numbers and booleans if they look like one, else text
+        Value::Null => toml_literal()
+            .filter(|value| value.is_number() || value.is_boolean() || value.is_array())
+            .unwrap_or_else(|| Value::String(raw.to_string())),
+    }
+}
+
+/// `serve_admin_tokens_file` -> `["serve", "admin_tokens_file"]` for secret options.
+fn secret_source_path(option: &str) -> Option<Vec<String>> {
+    SECRET_OPTIONS.iter().find_map(|secret| {
+        let flat = secret.replace('.', "_");
+        let suffix = option.strip_prefix(flat.as_str())?;
+        if suffix != "_file" && suffix != "_cmd" {
+            return None;
+        }
+        let mut path: Vec<String> = secret.split('.').map(str::to_string).collect();
+        let last = path.pop()?;
+        path.push(format!("{}{}", last, suffix));
+        Some(path)
+    })
+}
+
+/// Replaces `<option>_file` and `<option>_cmd` with the secret they point at.
+pub fn resolve_secrets(table: &mut Value) -> Result<()> {
+    let schema = serde_json::to_value(Config::default()).unwrap_or_default();
+    for secret in SECRET_OPTIONS {
+        let mut path: Vec<&str> = secret.split('.').collect();
+        let key = path.pop().unwrap_or_default();
+        let Some(parent) = path
+            .iter()
+            .try_fold(&mut *table, |value, key| value.get_mut(*key))
+            .and_then(Value::as_object_mut)
+        else {
+            continue;
+        };
+
+        let file = parent.remove(&format!("{}_file", key));
+        let command = parent.remove(&format!("{}_cmd", key));
+        let raw = match (file, command) {
+            (None, None) => continue,
+            (Some(_), Some(_)) => {
+                return Err(format!("Set only one of {0}_file and {0}_cmd", secret).into())
+            }
+            (Some(file), None) => {
+                let file = file.as_str().unwrap_or_default().to_string();
+                log_info("CONFIG", &format!("Reading {} from {}", secret, file));
+                fs::read_to_string(&file)
+                    .map_err(|e| format!("Failed to read {}_file {}: {}", secret, file, e))?
+            }
+            (None, Some(command)) => {
+                let command = command.as_str().unwrap_or_default();
+                log_info("CONFIG", &format!("Reading {} from a command", secret));
+                run_secret_command(command).map_err(|e| format!("{}_cmd failed: {}", secret, e))?
+            }
+        };
+
+        let default = path
+            .iter()
+            .chain([&key])
+            .try_fold(&schema, |value, key| value.get(*key));
+        let value = if default.is_some_and(Value::is_object) {
+            // One name=value per line, e.g. `jp=socks5://127.0.0.1:1080`
+            let mut map = serde_json::Map::new();
+            for line in raw.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                let (name, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| format!("{}: expected name=value, got '{}'", secret, line))?;
+                map.insert(
+                    name.trim().to_string(),
+                    Value::String(value.trim().to_string()),
+                );
+            }
+            Value::Object(map)
+        } else if default.is_some_and(Value::is_array) {
+            Value::Array(
+                raw.split(['\n', ','])
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| Value::String(item.to_string()))
+                    .collect(),
+            )
+        } else {
+            Value::String(raw.trim().to_string())
+        };
+        parent.insert(key.to_string(), value);
+    }
+    Ok(())
+}
+
+fn run_secret_command(command: &str) -> Result<String> {
+    let output = if cfg!(windows) {
+        std::process::Command::new("cmd")
+            .args(["/C", command])
+            .output()?
+    } else {
+        std::process::Command::new("sh")
+            .args(["-c", command])
+            .output()?
+    };
+    if !output.status.success() {
+        return Err(format!(
+            "{}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Replaces every set secret option in a config table with [`REDACTED`].
+pub fn redact(table: &mut Value) {
+    for secret in SECRET_OPTIONS {
+        let Some(value) = secret
+            .split('.')
+            .try_fold(&mut *table, |value, key| value.get_mut(key))
+        else {
+            continue;
+        };
+        match value {
+            Value::Null => {}
+            Value::Array(items) => items
+                .iter_mut()
+                .for_each(|item| *item = Value::String(REDACTED.to_string())),
+            // Keep the names, e.g. which markets have a proxy
+            Value::Object(entries) => entries
+                .values_mut()
+                .for_each(|item| *item = Value::String(REDACTED.to_string())),
+            _ => *value = Value::String(REDACTED.to_string()),
+        }
+    }
+}
+
+/// `scheme://host/<redacted>` for logs: drops credentials, path and query.
+pub fn redact_url(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return REDACTED.to_string();
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    if authority.len() == rest.len() && host.len() == authority.len() {
+        return url.to_string();
+    }
+    format!("{}://{}/{}", scheme, host, REDACTED)
+}
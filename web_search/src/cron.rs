@@ -0,0 +1,139 @@
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike};
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// Search horizon for the next run; covers leap-day-only expressions.
+const MAX_MINUTES: i64 = 4 * 366 * 24 * 60;
+
+/// A standard five-field cron expression: minute, hour, day of month, month, day of week.
+///
+/// Times are matched against local wall-clock time of the zone passed to
+/// [`CronSchedule::next_after`], stepping over real instants, so wall times skipped by a
+/// DST change never fire and a repeated hour fires twice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+fn parse_value(text: &str, min: u32, max: u32, names: &[&str]) -> Result<u32> {
+    let upper = text.to_ascii_uppercase();
+    if let Some(index) = names.iter().position(|name| *name == upper) {
+        return Ok(index as u32 + min);
+    }
+    let value: u32 = text
+        .parse()
+        .map_err(|_| format!("invalid cron value '{}'", text))?;
+    if value < min || value > max {
+        return Err(format!("cron value {} out of range {}-{}", value, min, max).into());
+    }
+    Ok(value)
+}
+
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let mut mask = 0u64;
+
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("invalid cron step '{}'", step))?;
+                if step == 0 {
+                    return Err("cron step must be positive".into());
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (
+                parse_value(a, min, max, names)?,
+                parse_value(b, min, max, names)?,
+            )
+        } else {
+            let start = parse_value(range, min, max, names)?;
+            (start, if step > 1 { max } else { start })
+        };
+        if start > end {
+            return Err(format!("invalid cron range '{}'", range).into());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+impl FromStr for CronSchedule {
+    type Err = Error;
+
+    fn from_str(expression: &str) -> Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(format!(
+                "cron expression needs 5 fields, got {}: '{}'",
+                fields.len(),
+                expression
+            )
+            .into());
+        };
+
+        let mut days_of_week = parse_field(day_of_week, 0, 7, &WEEKDAYS)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(CronSchedule {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days_of_month: parse_field(day_of_month, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, &MONTHS)?,
+            days_of_week,
+            any_day_of_month: day_of_month.starts_with('*'),
+            any_day_of_week: day_of_week.starts_with('*'),
+        })
+    }
+}
+
+impl CronSchedule {
+    pub fn matches<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> bool {
+        let bit = |mask: u64, value: u32| mask & (1 << value) != 0;
+
+        let day_of_month = bit(self.days_of_month, time.day());
+        let day_of_week = bit(self.days_of_week, time.weekday().num_days_from_sunday());
+        // Like cron(8): when both day fields are restricted, either may match.
+        let day = match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        };
+
+        day && bit(self.minutes, time.minute())
+            && bit(self.hours, time.hour())
+            && bit(self.months, time.month())
+    }
+
+    /// The first matching minute strictly after `after`.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        (0..MAX_MINUTES)
+            .map(|offset| start.clone() + Duration::minutes(offset))
+            .find(|time| self.matches(time))
+    }
+}
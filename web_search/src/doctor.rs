@@ -0,0 +1,272 @@
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::config::{redact_url, Config, Surface};
+use crate::fetch;
+use crate::log::{log_error, log_success};
+use crate::notify;
+use crate::paths;
+
+/// One line of the report.
+struct Finding {
+    name: &'static str,
+    ok: bool,
+    detail: String,
+}
+
+/// Runs the checks most support requests come down to and reports each as
+/// pass/fail: DNS, TLS and HTTP to the surface, the User-Agent API, a writable
+/// data directory and whether the configured notifiers answer.
+pub async fn run(config: &Config, surface: &Surface<'_>) -> Value {
+    let mut findings = Vec::new();
+
+    let host = Url::parse(surface.url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string));
+    findings.push(match &host {
+        Some(host) => dns(config, host).await,
+        None => Finding {
+            name: "dns",
+            ok: false,
+            detail: format!("'{}' is not a URL with a host", surface.url),
+        },
+    });
+
+    findings.extend(page(config, surface).await);
+
+    findings.push(
+        match fetch::get_latest_user_agent(&config.user_agent_api).await {
+            Ok(user_agent) => Finding {
+                name: "user_agent_api",
+                ok: true,
+                detail: format!("{} answered with {}", config.user_agent_api, user_agent),
+            },
+            Err(e) => Finding {
+                name: "user_agent_api",
+                ok: false,
+                detail: format!(
+                    "{}: {} (checks fall back to a built-in User-Agent)",
+                    config.user_agent_api, e
+                ),
+            },
+        },
+    );
+
+    let data_dir = surface
+        .versions_file
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    findings.push(writable(data_dir));
+
+    findings.push(notifiers(config).await);
+
+    findings.push(match &config.healthcheck.url {
+        Some(url) => Finding {
+            name: "healthcheck",
+            ok: Url::parse(url).is_ok(),
+            detail: format!(
+                "{} (not pinged, a ping would count as a run)",
+                redact_url(url)
+            ),
+        },
+        None => Finding {
+            name: "healthcheck",
+            ok: true,
+            detail: "not configured".to_string(),
+        },
+    });
+
+    for finding in &findings {
+        let line = format!("{}: {}", finding.name, finding.detail);
+        if finding.ok {
+            log_success("DOCTOR", &line);
+        } else {
+            log_error("DOCTOR", &line);
+        }
+    }
+    let failed = findings.iter().filter(|finding| !finding.ok).count();
+
+    json!({
+        "success": failed == 0,
+        "surface": surface.name,
+        "failed": failed,
+        "checks": findings
+            .iter()
+            .map(|finding| json!({
+                "name": finding.name,
+                "ok": finding.ok,
+                "detail": finding.detail
+            }))
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Probes each configured notifier without sending it anything.
+async fn notifiers(config: &Config) -> Finding {
+    let missing = notify::missing(&config.notify);
+    let notifiers = notify::notifiers(&config.notify);
+    if missing.is_empty() && notifiers.is_empty() {
+        return Finding {
+            name: "notifiers",
+            ok: true,
+            detail: "none configured".to_string(),
+        };
+    }
+
+    let mut ok = missing.is_empty();
+    let mut details: Vec<String> = missing
+        .iter()
+        .map(|name| notify::needs_feature(name))
+        .collect();
+    for notifier in &notifiers {
+        let detail = match notifier.probe().await {
+            Ok(detail) => detail,
+            Err(e) => {
+                ok = false;
+                e.to_string()
+            }
+        };
+        details.push(format!("{}: {}", notifier.name(), detail));
+    }
+    Finding {
+        name: "notifiers",
+        ok,
+        detail: details.join("; "),
+    }
+}
+
+async fn dns(config: &Config, host: &str) -> Finding {
+    if let Some(ips) = config.http.hosts.get(host) {
+        return Finding {
+            name: "dns",
+            ok: !ips.is_empty(),
+            detail: format!("{} is pinned to {:?} in [http] hosts", host, ips),
+        };
+    }
+
+    let started = Instant::now();
+    let via = match &config.http.doh_url {
+        Some(doh) => format!(" (system resolver; requests use {})", doh),
+        None => String::new(),
+    };
+    match tokio::net::lookup_host((host, 443)).await {
+        Ok(addrs) => {
+            let addrs: Vec<String> = addrs.map(|addr| addr.ip().to_string()).collect();
+            Finding {
+                name: "dns",
+                ok: !addrs.is_empty(),
+                detail: format!(
+                    "{} -> {} in {} ms{}",
+                    host,
+                    addrs.join(", "),
+                    started.elapsed().as_millis(),
+                    via
+                ),
+            }
+        }
+        Err(e) => Finding {
+            name: "dns",
+            ok: false,
+            detail: format!("{}: {}{}", host, e, via),
+        },
+    }
+}
+
+/// TLS and HTTP status for the surface page, with the same client a check uses.
+async fn page(config: &Config, surface: &Surface<'_>) -> Vec<Finding> {
+    let user_agent = config
+        .http
+        .user_agent
+        .as_deref()
+        .unwrap_or(fetch::FALLBACK_USER_AGENT);
+    let https = surface.url.starts_with("https://");
+    let result = match fetch::build_page_client(user_agent, &config.http, false) {
+        Ok(client) => fetch::fetch_page(&client, surface.url, false).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(page) => {
+            let mut findings = Vec::new();
+            if https {
+                findings.push(Finding {
+                    name: "tls",
+                    ok: true,
+                    detail: "handshake and certificate verified".to_string(),
+                });
+            }
+            let off_site = fetch::off_site(surface.url, &page.url);
+            findings.push(Finding {
+                name: "http",
+                ok: (200..300).contains(&page.status) && off_site.is_none(),
+                detail: match off_site {
+                    Some(off_site) => format!(
+                        "HTTP {}, redirected to {}: {}",
+                        page.status,
+                        off_site.description(),
+                        page.url
+                    ),
+                    None => format!(
+                        "HTTP {} from {} ({} bytes)",
+                        page.status,
+                        page.url,
+                        page.body.len()
+                    ),
+                },
+            });
+            findings
+        }
+        Err(e) => {
+            let message = error_chain(e.as_ref());
+            let tls_failed = ["certificate", "tls", "ssl", "handshake"]
+                .iter()
+                .any(|word| message.to_ascii_lowercase().contains(word));
+            if https && tls_failed {
+                vec![Finding {
+                    name: "tls",
+                    ok: false,
+                    detail: format!(
+                        "{} (behind a re-signing proxy? see [http] ca_bundle)",
+                        message
+                    ),
+                }]
+            } else {
+                vec![Finding {
+                    name: "http",
+                    ok: false,
+                    detail: format!("{}: {}", surface.url, message),
+                }]
+            }
+        }
+    }
+}
+
+/// reqwest hides the useful part (e.g. the certificate error) in `source()`.
+fn error_chain(error: &(dyn std::error::Error + 'static)) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+fn writable(dir: &Path) -> Finding {
+    let probe = dir.join(".web_search-doctor");
+    let result = fs::create_dir_all(paths::long_path(dir))
+        .and_then(|()| fs::write(paths::long_path(&probe), b"ok"))
+        .and_then(|()| fs::remove_file(paths::long_path(&probe)));
+    Finding {
+        name: "data_dir",
+        ok: result.is_ok(),
+        detail: match result {
+            Ok(()) => format!("{} is writable", dir.display()),
+            Err(e) => format!("{} is not writable: {}", dir.display(), e),
+        },
+    }
+}
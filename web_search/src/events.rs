@@ -0,0 +1,109 @@
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::config::Surface;
+use crate::paths;
+use crate::Result;
+
+/// Identifies this process in the event log: start time plus PID.
+pub fn run_id() -> &'static str {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+    RUN_ID.get_or_init(|| {
+        format!(
+            "{}-{}",
+            Utc::now().format("%Y%m%dT%H%M%SZ"),
+            std::process::id()
+        )
+    })
+}
+
+pub fn new_version(surface: &Surface<'_>, key: &str, entry: &Value) -> Value {
+    json!({
+        "event": "new_version",
+        "key": key,
+        "clientVersion": entry["clientVersion"],
+        "buildDate": entry["buildDate"],
+        "detectedAt": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        "runId": run_id(),
+        "surface": surface.name,
+        "source": surface.url
+    })
+}
+
+/// Appends one event as a single JSON line; existing lines are never rewritten.
+pub fn append(path: &Path, event: &Value) -> Result<()> {
+    let target = paths::long_path(path);
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&target)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    // One write call so concurrent appenders can't interleave within a line
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// What one check found, as delivered to library callbacks and subscribers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionEvent {
+    NewVersion {
+        surface: String,
+        key: String,
+        entry: Value,
+    },
+    Unchanged {
+        surface: String,
+        key: String,
+    },
+    Error {
+        surface: String,
+        error: String,
+    },
+}
+
+impl VersionEvent {
+    /// Classifies the output of `check::run_surface` and friends.
+    pub fn from_check(surface: &str, result: &Result<Value>) -> Self {
+        let surface = surface.to_string();
+        match result {
+            Ok(output) if output["success"] != true => VersionEvent::Error {
+                surface,
+                error: output["error"]["message"]
+                    .as_str()
+                    .unwrap_or("Check failed")
+                    .to_string(),
+            },
+            Ok(output) if output["is_new"] == true => VersionEvent::NewVersion {
+                surface,
+                key: output["key"].as_str().unwrap_or_default().to_string(),
+                entry: output["data"].clone(),
+            },
+            Ok(output) => VersionEvent::Unchanged {
+                surface,
+                key: output["key"].as_str().unwrap_or_default().to_string(),
+            },
+            Err(e) => VersionEvent::Error {
+                surface,
+                error: e.to_string(),
+            },
+        }
+    }
+
+    pub fn surface(&self) -> &str {
+        match self {
+            VersionEvent::NewVersion { surface, .. }
+            | VersionEvent::Unchanged { surface, .. }
+            | VersionEvent::Error { surface, .. } => surface,
+        }
+    }
+}
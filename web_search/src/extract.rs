@@ -0,0 +1,391 @@
+use base64::engine::general_purpose::{self, GeneralPurpose};
+use base64::Engine as _;
+use regex::Regex;
+use scraper::{Html, Selector};
+use serde_json::{json, Value};
+
+use crate::config::{Decode, ExtractConfig};
+use crate::log::{log_error, log_info, log_success, log_warning};
+use crate::stats;
+use crate::version::{version_key, VersionEntry};
+use crate::Result;
+
+const BASE64_ENGINES: [(&str, GeneralPurpose); 4] = [
+    ("STANDARD", general_purpose::STANDARD),
+    ("STANDARD_NO_PAD", general_purpose::STANDARD_NO_PAD),
+    ("URL_SAFE", general_purpose::URL_SAFE),
+    ("URL_SAFE_NO_PAD", general_purpose::URL_SAFE_NO_PAD),
+];
+
+const HASHED_ASSET_REGEX: &str = r"\.[0-9a-f]{8,}\.(?:js|mjs|css)$";
+const SERVICE_WORKER_REGEX: &str = r#"serviceWorker\.register\(\s*["']([^"']+)["']"#;
+
+#[derive(Debug, Default)]
+pub struct Page {
+    pub app_server_config: Option<String>,
+    /// Every distinct appServerConfig payload on the page, in document order.
+    pub candidates: Vec<String>,
+    pub web_player_url: Option<String>,
+    /// Content-hashed script/stylesheet URLs and service worker scripts, as written in the page.
+    pub assets: Vec<String>,
+}
+
+#[tracing::instrument(skip_all, fields(bytes = html_content.len()))]
+pub fn parse_page(html_content: &str, config: &ExtractConfig) -> Result<Page> {
+    log_info("PARSE", "Parsing HTML document...");
+    let document = Html::parse_document(html_content);
+
+    log_info("SEARCH", "Method 1: Scraper...");
+    let mut app_server_config = find_with_selectors(&document, &config.selectors);
+    let mut candidates = find_all_with_selectors(&document, &config.selectors);
+
+    if app_server_config.is_none() {
+        log_info("SEARCH", "Method 2: Regex...");
+        let re = Regex::new(&config.regex)?;
+        if let Some(text) = re.captures(html_content).and_then(|caps| caps.get(1)) {
+            log_success("REGEX", "Tag found via regex");
+            app_server_config = Some(text.as_str().trim().to_string());
+        }
+        candidates = distinct(
+            re.captures_iter(html_content)
+                .filter_map(|caps| caps.get(1))
+                .map(|text| text.as_str().trim().to_string()),
+        );
+    }
+
+    log_info("SEARCH", "Searching for web-player ...");
+    let web_player_url = find_web_player(&document, &config.bundle_marker);
+    let assets = find_assets(&document, html_content)?;
+
+    Ok(Page {
+        app_server_config,
+        candidates,
+        web_player_url,
+        assets,
+    })
+}
+
+/// HTML in, version entry out: selectors, regex fallback, Base64 and JSON, with
+/// no IO. Builds for wasm32-unknown-unknown with `default-features = false,
+/// features = ["scrape"]`, so browser extensions and workers extract exactly
+/// what the scraper does.
+pub fn version_entry(html_content: &str, config: &ExtractConfig) -> Result<VersionEntry> {
+    entry_from_page(&parse_page(html_content, config)?, config)
+}
+
+/// The entry for an already parsed page. `webPlayer` is kept as written in the page.
+pub fn entry_from_page(page: &Page, config: &ExtractConfig) -> Result<VersionEntry> {
+    let Some(first) = page.app_server_config.as_deref() else {
+        log_error("FAIL", "Tag 'appServerConfig' not found in HTML!");
+        return Err("appServerConfig tag not found".into());
+    };
+
+    if first.is_empty() {
+        log_error("ERROR", "Tag found, but content is empty!");
+        return Err("Base64 content is empty".into());
+    }
+
+    let json_object = match pick_candidate(&page.candidates, config) {
+        Some(json_object) => json_object,
+        None => {
+            log_success(
+                "B64",
+                &format!("Base64 string found ({} characters)", first.len()),
+            );
+            decode_payload(first, config.decode).map_err(|e| {
+                log_error(
+                    "DECODE",
+                    &format!("Failed to decode appServerConfig: {}", e),
+                );
+                format!("Failed to decode appServerConfig: {}", e)
+            })?
+        }
+    };
+
+    if config.strict {
+        let problems = strict_problems(page, &json_object, config);
+        if !problems.is_empty() {
+            for problem in &problems {
+                log_error("STRICT", problem);
+            }
+            return Err(format!("Strict mode: {}", problems.join("; ")).into());
+        }
+    }
+
+    let version = field(&json_object, &config.client_version_field);
+    let build_date = field(&json_object, &config.build_date_field);
+    let build_version = field(&json_object, &config.build_version_field);
+
+    let dateless = config.build_date_field.is_empty();
+    let (Some(version), Some(build_date)) = (version, build_date.or(dateless.then_some(""))) else {
+        log_error(
+            "ERROR",
+            "Properties 'clientVersion' or 'buildDate' not found!",
+        );
+        return Err("clientVersion or buildDate not found".into());
+    };
+
+    log_success("", "Version data extracted");
+    log_success("", &format!("clientVersion: {}", version));
+    if !dateless {
+        log_success("", &format!("buildDate: {}", build_date));
+    }
+    if let Some(bv) = build_version {
+        log_success("", &format!("buildVersion: {}", bv));
+    }
+
+    let mut data = json!({ "clientVersion": version });
+    if !dateless {
+        data["buildDate"] = json!(build_date);
+    }
+    if let Some(url) = page.web_player_url.as_deref() {
+        data["webPlayer"] = json!(url);
+    }
+    if let Some(bv) = build_version {
+        data["buildVersion"] = json!(bv);
+    }
+    match stats::build_date_iso(build_date) {
+        Some(iso) => data["buildDateIso"] = json!(iso),
+        None if dateless => {}
+        None => log_warning(
+            "DATE",
+            &format!("buildDate '{}' is not in a known format", build_date),
+        ),
+    }
+
+    Ok(VersionEntry {
+        key: version_key(version),
+        data,
+    })
+}
+
+pub fn decode_base64(input: &str) -> Result<Vec<u8>> {
+    let cleaned: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let mut last_error = None;
+    for (name, engine) in &BASE64_ENGINES {
+        match engine.decode(&cleaned) {
+            Ok(bytes) => {
+                log_info("DECODE", &format!("Decoded with {} engine", name));
+                return Ok(bytes);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(format!(
+        "not valid Base64 in any supported alphabet ({})",
+        last_error.map(|e| e.to_string()).unwrap_or_default()
+    )
+    .into())
+}
+
+/// The JSON in a config tag's text, decoded as `decode` says.
+pub fn decode_payload(payload: &str, decode: Decode) -> Result<Value> {
+    match decode {
+        Decode::Base64 => decode_config(payload),
+        Decode::None => {
+            log_info("JSON", "Parsing JSON data...");
+            Ok(serde_json::from_str(payload)?)
+        }
+        Decode::Text => Ok(json!({ "text": payload })),
+    }
+}
+
+pub fn decode_config(base64_str: &str) -> Result<Value> {
+    log_info("DECODE", "Decoding Base64...");
+    let decoded_bytes = decode_base64(base64_str)?;
+    let decoded_json = String::from_utf8(decoded_bytes)?;
+
+    log_info("JSON", "Parsing JSON data...");
+    Ok(serde_json::from_str(&decoded_json)?)
+}
+
+pub fn field<'a>(json: &'a Value, name: &str) -> Option<&'a str> {
+    lookup(json, name).and_then(|v| v.as_str())
+}
+
+fn lookup<'a>(json: &'a Value, name: &str) -> Option<&'a Value> {
+    if name.starts_with('/') {
+        json.pointer(name)
+    } else {
+        json.get(name)
+    }
+}
+
+/// Everything `strict` refuses: data that may be partial or ambiguous.
+fn strict_problems(page: &Page, json: &Value, config: &ExtractConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+    if page.candidates.len() > 1 {
+        problems.push(format!(
+            "{} appServerConfig tags with different content",
+            page.candidates.len()
+        ));
+    }
+    if !json.is_object() {
+        problems.push(format!(
+            "appServerConfig is a JSON {} instead of an object",
+            kind(json)
+        ));
+    }
+    for name in [
+        &config.client_version_field,
+        &config.build_date_field,
+        &config.build_version_field,
+    ]
+    .into_iter()
+    .filter(|name| !name.is_empty())
+    {
+        match lookup(json, name) {
+            None => problems.push(format!("'{}' not found", name)),
+            Some(value) if !value.is_string() => problems.push(format!(
+                "'{}' is a {} instead of a string",
+                name,
+                kind(value)
+            )),
+            Some(_) => {}
+        }
+    }
+    problems
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn find_with_selectors(document: &Html, selectors: &[String]) -> Option<String> {
+    for selector_str in selectors {
+        match Selector::parse(selector_str) {
+            Ok(selector) => {
+                if let Some(element) = document.select(&selector).next() {
+                    log_success("SCRAPER", &format!("Tag found: {}", selector_str));
+                    return Some(element.text().collect::<String>().trim().to_string());
+                }
+            }
+            Err(e) => log_warning(
+                "SCRAPER",
+                &format!("Invalid selector {}: {:?}", selector_str, e),
+            ),
+        }
+    }
+    None
+}
+
+/// With several distinct appServerConfig payloads, decodes each and keeps the
+/// first (in document order) with a clientVersion, warning about the rest.
+/// `None` when there is at most one payload or none of them qualifies, so the
+/// first tag is used as before.
+fn pick_candidate(candidates: &[String], config: &ExtractConfig) -> Option<Value> {
+    if candidates.len() < 2 {
+        return None;
+    }
+    log_warning(
+        "MULTI",
+        &format!("{} different appServerConfig tags found", candidates.len()),
+    );
+
+    let mut chosen = None;
+    let mut discarded = Vec::new();
+    for (i, candidate) in candidates.iter().enumerate() {
+        let number = i + 1;
+        if chosen.is_some() {
+            discarded.push(format!("#{} (a later tag)", number));
+            continue;
+        }
+        match decode_payload(candidate, config.decode) {
+            Ok(json) if field(&json, &config.client_version_field).is_some() => {
+                log_success(
+                    "MULTI",
+                    &format!("Using tag #{} ({} characters)", number, candidate.len()),
+                );
+                chosen = Some(json);
+            }
+            Ok(_) => discarded.push(format!("#{} (no {})", number, config.client_version_field)),
+            Err(e) => discarded.push(format!("#{} (not decodable: {})", number, e)),
+        }
+    }
+
+    if chosen.is_some() {
+        log_warning("MULTI", &format!("Discarded {}", discarded.join(", ")));
+    }
+    chosen
+}
+
+fn find_all_with_selectors(document: &Html, selectors: &[String]) -> Vec<String> {
+    distinct(
+        selectors
+            .iter()
+            .filter_map(|selector_str| Selector::parse(selector_str).ok())
+            .flat_map(|selector| {
+                document
+                    .select(&selector)
+                    .map(|element| element.text().collect::<String>().trim().to_string())
+                    .collect::<Vec<_>>()
+            }),
+    )
+}
+
+/// Drops empty and repeated payloads, keeping the first occurrence's position.
+fn distinct(payloads: impl Iterator<Item = String>) -> Vec<String> {
+    let mut seen = Vec::new();
+    for payload in payloads {
+        if !payload.is_empty() && !seen.contains(&payload) {
+            seen.push(payload);
+        }
+    }
+    seen
+}
+
+fn find_web_player(document: &Html, marker: &str) -> Option<String> {
+    if marker.is_empty() {
+        return None;
+    }
+    let selector_js = Selector::parse("script[src]").expect("Selector creation error");
+    let prefix = format!("{}.", marker);
+    for element in document.select(&selector_js) {
+        if let Some(src) = element.value().attr("src") {
+            // By file name, so vendor~web-player.<hash>.js isn't taken for the bundle
+            let path = src.split(['?', '#']).next().unwrap_or_default();
+            let file = path.rsplit('/').next().unwrap_or_default();
+            if file.starts_with(&prefix) && file.ends_with(".js") {
+                log_success("FOUND", &format!("Web-player: {}", src));
+                return Some(src.to_string());
+            }
+        }
+    }
+    None
+}
+
+fn find_assets(document: &Html, html_content: &str) -> Result<Vec<String>> {
+    let hashed = Regex::new(HASHED_ASSET_REGEX)?;
+    let selector = Selector::parse("script[src], link[href]").expect("Selector creation error");
+
+    let mut assets: Vec<String> = document
+        .select(&selector)
+        .filter_map(|element| {
+            let value = element.value();
+            value.attr("src").or_else(|| value.attr("href"))
+        })
+        .filter(|url| hashed.is_match(url.split('?').next().unwrap_or("")))
+        .map(str::to_string)
+        .collect();
+
+    let service_worker = Regex::new(SERVICE_WORKER_REGEX)?;
+    assets.extend(
+        service_worker
+            .captures_iter(html_content)
+            .filter_map(|caps| caps.get(1))
+            .map(|m| m.as_str().to_string()),
+    );
+
+    assets.sort();
+    assets.dedup();
+    Ok(assets)
+}
@@ -0,0 +1,503 @@
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::header::{ACCEPT, LOCATION};
+use reqwest::redirect::Policy;
+use reqwest::{tls, Certificate, ClientBuilder, Proxy, Url};
+use serde_json::Value;
+
+use crate::config::{HttpConfig, HttpVersion};
+use crate::log::{log_info, log_warning};
+use crate::pace;
+use crate::record;
+use crate::Result;
+
+pub const FALLBACK_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
+
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_REDIRECTS: usize = 10;
+
+pub async fn get_latest_user_agent(api_url: &str) -> Result<String> {
+    let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+    let request = client.get(api_url).build()?;
+    let body = record::send(&client, request).await?.text()?;
+    let user_agents: Vec<String> = serde_json::from_str(&body)?;
+
+    Ok(user_agents
+        .first()
+        .cloned()
+        .unwrap_or_else(|| FALLBACK_USER_AGENT.to_string()))
+}
+
+pub fn build_client(
+    user_agent: &str,
+    http: &HttpConfig,
+    trace_http: bool,
+) -> Result<reqwest::Client> {
+    let mut builder = builder(user_agent, http, trace_http)?;
+    if trace_http {
+        builder = builder.redirect(tracing_redirect_policy());
+    }
+    Ok(builder.build()?)
+}
+
+/// A client for web_search's own endpoints (result sinks, publishing, sync)
+/// with the `[http]` proxy, timeout, TLS and DNS settings.
+pub fn api_client(http: &HttpConfig) -> Result<reqwest::Client> {
+    let user_agent = http.user_agent.as_deref().unwrap_or(FALLBACK_USER_AGENT);
+    build_client(user_agent, http, false)
+}
+
+/// A client for [`fetch_page`] that leaves redirects to it, so the chain can
+/// be recorded.
+pub fn build_page_client(
+    user_agent: &str,
+    http: &HttpConfig,
+    trace_http: bool,
+) -> Result<reqwest::Client> {
+    Ok(builder(user_agent, http, trace_http)?
+        .redirect(Policy::none())
+        .build()?)
+}
+
+fn builder(user_agent: &str, http: &HttpConfig, trace_http: bool) -> Result<ClientBuilder> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent(user_agent)
+        .timeout(http.timeout());
+
+    if let Some(proxy) = &http.proxy {
+        builder = builder.proxy(Proxy::all(proxy)?);
+    }
+
+    builder = match http.http_version {
+        HttpVersion::Auto => builder,
+        HttpVersion::Http1 => builder.http1_only(),
+        HttpVersion::Http2 => builder.http2_prior_knowledge(),
+    };
+
+    let (mut gzip, mut brotli, mut deflate) = (false, false, false);
+    for encoding in &http.compression {
+        match encoding.to_ascii_lowercase().as_str() {
+            "gzip" => gzip = true,
+            "brotli" | "br" => brotli = true,
+            "deflate" => deflate = true,
+            // reqwest 0.11 can't decode it; advertising it would hand us bodies we can't read
+            "zstd" => {
+                return Err(
+                    "zstd response compression isn't supported by this build's HTTP client; \
+                     use gzip, brotli or deflate"
+                        .into(),
+                )
+            }
+            other => {
+                return Err(format!(
+                    "Unsupported compression '{}' (expected gzip, brotli or deflate)",
+                    other
+                )
+                .into())
+            }
+        }
+    }
+    builder = builder.gzip(gzip).brotli(brotli).deflate(deflate);
+
+    if let Some(version) = &http.min_tls_version {
+        builder = builder.min_tls_version(tls_version(version)?);
+    }
+
+    if let Some(path) = &http.ca_bundle {
+        for certificate in load_certificates(path)? {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    if let Some(endpoint) = &http.doh_url {
+        builder = builder.dns_resolver(Arc::new(DohResolver {
+            endpoint: endpoint.clone(),
+            client: reqwest::Client::builder().timeout(http.timeout()).build()?,
+            trace: trace_http,
+        }));
+    } else if trace_http {
+        builder = builder.dns_resolver(Arc::new(TimedResolver));
+    }
+
+    for (host, ips) in &http.hosts {
+        // Port 0: the URL's port is used
+        let addrs: Vec<SocketAddr> = ips.iter().map(|ip| SocketAddr::new(*ip, 0)).collect();
+        builder = builder.resolve_to_addrs(host, &addrs);
+    }
+
+    Ok(builder)
+}
+
+fn tls_version(version: &str) -> Result<tls::Version> {
+    match version {
+        "1.0" => Ok(tls::Version::TLS_1_0),
+        "1.1" => Ok(tls::Version::TLS_1_1),
+        "1.2" => Ok(tls::Version::TLS_1_2),
+        "1.3" => Ok(tls::Version::TLS_1_3),
+        other => Err(format!(
+            "Unknown TLS version '{}' (expected 1.0, 1.1, 1.2 or 1.3)",
+            other
+        )
+        .into()),
+    }
+}
+
+/// Every certificate in a PEM bundle; `Certificate::from_pem` reads only the first.
+fn load_certificates(path: &Path) -> Result<Vec<Certificate>> {
+    let pem = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read ca_bundle {}: {}", path.display(), e))?;
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let certificates = pem
+        .split_inclusive(END)
+        .filter(|block| block.contains("-----BEGIN CERTIFICATE-----"))
+        .map(|block| Certificate::from_pem(block.trim().as_bytes()))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| format!("Invalid certificate in {}: {}", path.display(), e))?;
+    if certificates.is_empty() {
+        return Err(format!("No certificates found in {}", path.display()).into());
+    }
+    Ok(certificates)
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchedPage {
+    /// Where the page was finally fetched from, after redirects.
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// Redirects followed to get here, in order. Empty when the client
+    /// follows redirects itself.
+    pub redirects: Vec<Redirect>,
+}
+
+/// One followed redirect.
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    pub status: u16,
+    pub from: String,
+    pub to: String,
+}
+
+/// Where a redirected page fetch landed when it isn't the host asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffSite {
+    /// A sign-in page such as accounts.spotify.com.
+    Login,
+    /// A country or language picker in front of the page.
+    Interstitial,
+    /// Any other host.
+    Other,
+}
+
+impl OffSite {
+    pub fn code(self) -> &'static str {
+        match self {
+            OffSite::Login => "login_redirect",
+            OffSite::Interstitial => "region_interstitial",
+            OffSite::Other => "offsite_redirect",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            OffSite::Login => "a login page",
+            OffSite::Interstitial => "a regional interstitial",
+            OffSite::Other => "another site",
+        }
+    }
+}
+
+/// How `final_url` differs from the host of `requested`, `None` when the host is the same.
+pub fn off_site(requested: &str, final_url: &str) -> Option<OffSite> {
+    let (Ok(requested), Ok(landed)) = (Url::parse(requested), Url::parse(final_url)) else {
+        return None;
+    };
+    if landed.host_str() == requested.host_str() {
+        return None;
+    }
+
+    let host = landed.host_str().unwrap_or_default();
+    let first_segment = landed
+        .path_segments()
+        .and_then(|mut segments| segments.next())
+        .unwrap_or_default();
+    Some(
+        if host.starts_with("accounts.") || host.starts_with("login.") {
+            OffSite::Login
+        } else if landed.path().contains("select-your-country")
+            || landed.path().contains("interstitial")
+            || is_locale(first_segment)
+        {
+            OffSite::Interstitial
+        } else {
+            OffSite::Other
+        },
+    )
+}
+
+/// `de`, `en-gb`, `pt_BR`: the first path segment of a localized site.
+fn is_locale(segment: &str) -> bool {
+    let mut parts = segment.split(['-', '_']);
+    let language = parts.next().unwrap_or_default();
+    let region = parts.next();
+    language.len() == 2
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && region.is_none_or(|region| {
+            region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic())
+        })
+        && parts.next().is_none()
+}
+
+#[tracing::instrument(skip(client, trace_http), fields(status))]
+pub async fn fetch_page(
+    client: &reqwest::Client,
+    url: &str,
+    trace_http: bool,
+) -> Result<FetchedPage> {
+    let started = Instant::now();
+    let mut redirects = Vec::new();
+    let mut next = url.to_string();
+    let exchange = loop {
+        let request = client.get(&next).build()?;
+        if trace_http {
+            log_info(
+                "TRACE",
+                &format!("> {} {}", request.method(), request.url()),
+            );
+            for (name, value) in request.headers() {
+                log_info(
+                    "TRACE",
+                    &format!("> {}: {}", name, value.to_str().unwrap_or("<binary>")),
+                );
+            }
+        }
+
+        let exchange = record::send(client, request).await?;
+        let location = exchange
+            .header(LOCATION.as_str())
+            .filter(|_| (300..400).contains(&exchange.status));
+        let Some(location) = location else {
+            break exchange;
+        };
+        if redirects.len() >= MAX_REDIRECTS {
+            return Err(format!("Too many redirects (more than {})", MAX_REDIRECTS).into());
+        }
+
+        let to = Url::parse(&exchange.url)?.join(location)?.to_string();
+        let redirect = Redirect {
+            status: exchange.status,
+            from: exchange.url.clone(),
+            to,
+        };
+        log_info(
+            "HTTP",
+            &format!(
+                "Redirect {} {} -> {}",
+                redirect.status, redirect.from, redirect.to
+            ),
+        );
+        next = redirect.to.clone();
+        redirects.push(redirect);
+    };
+
+    let record::Exchange {
+        url: final_url,
+        status,
+        version,
+        headers,
+        body,
+        ttfb,
+        ..
+    } = exchange;
+    tracing::Span::current().record("status", status);
+
+    if trace_http {
+        log_info(
+            "TRACE",
+            &format!("< {} {} ({})", version, status, final_url),
+        );
+        for (name, value) in &headers {
+            log_info("TRACE", &format!("< {}: {}", name, value));
+        }
+
+        let total = started.elapsed();
+        let ttfb = ttfb.unwrap_or(total);
+        log_info(
+            "TRACE",
+            &format!(
+                "Timing: ttfb {} ms (dns + connect + tls + server), body {} ms, total {} ms, {} bytes",
+                ttfb.as_millis(),
+                (total - ttfb).as_millis(),
+                total.as_millis(),
+                body.len()
+            ),
+        );
+    }
+
+    Ok(FetchedPage {
+        url: final_url,
+        status,
+        headers,
+        body,
+        redirects,
+    })
+}
+
+/// [`fetch_page`] with the retry policy from `[http]`: transport errors and
+/// 429/5xx responses are retried with exponential backoff.
+pub async fn fetch_page_with_retry(
+    client: &reqwest::Client,
+    url: &str,
+    http: &HttpConfig,
+    trace_http: bool,
+) -> Result<FetchedPage> {
+    let mut attempt = 0;
+    loop {
+        let permit = pace::wait(http).await;
+        let result = fetch_page(client, url, trace_http).await;
+        drop(permit);
+        let reason = match &result {
+            Ok(page) if page.status == 429 || page.status >= 500 => format!("HTTP {}", page.status),
+            Ok(_) => return result,
+            Err(e) => e.to_string(),
+        };
+        if attempt >= http.retries {
+            return result;
+        }
+
+        let delay = http.retry_delay(attempt);
+        attempt += 1;
+        log_warning(
+            "HTTP",
+            &format!(
+                "Attempt {} of {} failed ({}), retrying in {} ms",
+                attempt,
+                http.retries + 1,
+                reason,
+                delay.as_millis()
+            ),
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+fn tracing_redirect_policy() -> Policy {
+    Policy::custom(|attempt| {
+        let from = attempt
+            .previous()
+            .last()
+            .map(|url| url.to_string())
+            .unwrap_or_default();
+        log_info(
+            "TRACE",
+            &format!(
+                "Redirect {} {} -> {}",
+                attempt.status(),
+                from,
+                attempt.url()
+            ),
+        );
+
+        if attempt.previous().len() >= MAX_REDIRECTS {
+            attempt.error("too many redirects")
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+struct TimedResolver;
+
+impl Resolve for TimedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let started = Instant::now();
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            log_info(
+                "TRACE",
+                &format!(
+                    "DNS {} -> {:?} in {} ms",
+                    name.as_str(),
+                    addrs,
+                    started.elapsed().as_millis()
+                ),
+            );
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Resolves over DNS-over-HTTPS with the JSON API (`application/dns-json`)
+/// that Cloudflare, Google and most public resolvers offer.
+struct DohResolver {
+    endpoint: String,
+    /// Resolves the endpoint itself with the system resolver.
+    client: reqwest::Client,
+    trace: bool,
+}
+
+impl Resolve for DohResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let endpoint = self.endpoint.clone();
+        let client = self.client.clone();
+        let trace = self.trace;
+        Box::pin(async move {
+            let started = Instant::now();
+            let mut addrs = doh_lookup(&client, &endpoint, name.as_str(), "A").await?;
+            if addrs.is_empty() {
+                addrs = doh_lookup(&client, &endpoint, name.as_str(), "AAAA").await?;
+            }
+            if trace {
+                log_info(
+                    "TRACE",
+                    &format!(
+                        "DoH {} -> {:?} in {} ms",
+                        name.as_str(),
+                        addrs,
+                        started.elapsed().as_millis()
+                    ),
+                );
+            }
+            if addrs.is_empty() {
+                return Err(format!("{} has no addresses at {}", name.as_str(), endpoint).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+async fn doh_lookup(
+    client: &reqwest::Client,
+    endpoint: &str,
+    name: &str,
+    record: &str,
+) -> Result<Vec<SocketAddr>> {
+    let answer: Value = client
+        .get(endpoint)
+        .query(&[("name", name), ("type", record)])
+        .header(ACCEPT, "application/dns-json")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    // Answers may include CNAMEs on the way; only addresses are kept
+    Ok(answer["Answer"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|record| record["data"].as_str()?.parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, 0))
+        .collect())
+}
@@ -0,0 +1,66 @@
+//! C ABI for embedding the scraper in non-Rust programs. Build the library with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib` (or
+//! `staticlib`) and include `include/web_versions.h`.
+
+use serde_json::{json, Value};
+use std::ffi::{c_char, CString};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::Scraper;
+
+/// Fetches the version currently served on the web surface, with the library
+/// defaults of [`Scraper::new`]. Returns a NUL-terminated JSON object,
+/// `{"success":true,"key":..,"data":{..}}` or `{"success":false,"error":".."}`,
+/// that must be released with [`web_versions_string_free`].
+#[no_mangle]
+pub extern "C" fn web_versions_fetch_latest_json() -> *mut c_char {
+    let output = panic::catch_unwind(AssertUnwindSafe(fetch_latest)).unwrap_or_else(|_| {
+        json!({
+            "success": false,
+            "error": "panic while fetching the latest version"
+        })
+    });
+    // serde_json escapes control characters, so the text never holds a NUL
+    CString::new(output.to_string())
+        .map(CString::into_raw)
+        .unwrap_or(std::ptr::null_mut())
+}
+
+/// Frees a string returned by this library. Null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a pointer returned by a `web_versions_*` function that
+/// has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn web_versions_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+fn fetch_latest() -> Value {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            return json!({
+                "success": false,
+                "error": format!("Failed to start runtime: {}", e)
+            })
+        }
+    };
+    match runtime.block_on(Scraper::new().latest()) {
+        Ok(latest) => json!({
+            "success": true,
+            "key": latest.key,
+            "data": latest.entry
+        }),
+        Err(e) => json!({
+            "success": false,
+            "error": e.to_string()
+        }),
+    }
+}
@@ -0,0 +1,92 @@
+use serde_json::{json, Value};
+
+use crate::version::compare_versions;
+
+/// Missed build numbers listed per gap; the count is always exact.
+const MAX_LISTED: u64 = 20;
+
+/// Builds we likely missed between two consecutive stored versions of one
+/// series (same segments but the last).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gap {
+    pub after: String,
+    pub before: String,
+    pub missing: u64,
+}
+
+/// Gaps between `keys`, assuming the last segment grows by `step` from one
+/// build to the next. Keys that aren't all-numeric are ignored, and nothing is
+/// assumed across series boundaries (1.2.85.x to 1.2.86.x).
+pub fn find(keys: &[String], step: u64) -> Vec<Gap> {
+    let step = step.max(1);
+    let mut keys: Vec<(&str, &str, u64)> = keys.iter().filter_map(|key| split(key)).collect();
+    // Oldest first
+    keys.sort_by(|a, b| compare_versions(b.0, a.0));
+
+    keys.windows(2)
+        .filter_map(|pair| {
+            let ((after, series, from), (before, next_series, to)) = (pair[0], pair[1]);
+            let missing = if series == next_series && to > from {
+                (to - from - 1) / step
+            } else {
+                0
+            };
+            (missing > 0).then(|| Gap {
+                after: after.to_string(),
+                before: before.to_string(),
+                missing,
+            })
+        })
+        .collect()
+}
+
+/// `gaps` output: every gap with the build numbers it likely hides, and the
+/// share of builds we have stored within the series we know of.
+pub fn report(keys: &[String], step: u64) -> Value {
+    let step = step.max(1);
+    let gaps = find(keys, step);
+    let stored = keys.iter().filter(|key| split(key).is_some()).count() as u64;
+    let missing: u64 = gaps.iter().map(|gap| gap.missing).sum();
+    let coverage = if stored + missing == 0 {
+        1.0
+    } else {
+        (stored as f64 / (stored + missing) as f64 * 1000.0).round() / 1000.0
+    };
+
+    let gaps: Vec<Value> = gaps
+        .iter()
+        .map(|gap| {
+            let (_, series, from) = split(&gap.after).unwrap_or_default();
+            let likely: Vec<String> = (1..=gap.missing.min(MAX_LISTED))
+                .map(|n| format!("{}.{}", series, from + n * step))
+                .collect();
+            json!({
+                "after": gap.after,
+                "before": gap.before,
+                "missing": gap.missing,
+                "likely": likely
+            })
+        })
+        .collect();
+
+    json!({
+        "success": true,
+        "step": step,
+        "versions": stored,
+        "missing": missing,
+        "coverage": coverage,
+        "gaps": gaps
+    })
+}
+
+/// `1.2.86.316` -> (`1.2.86.316`, `1.2.86`, 316).
+fn split(key: &str) -> Option<(&str, &str, u64)> {
+    let (series, last) = key.rsplit_once('.')?;
+    let all_numeric = key
+        .split('.')
+        .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+    if !all_numeric {
+        return None;
+    }
+    Some((key, series, last.parse().ok()?))
+}
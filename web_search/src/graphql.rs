@@ -0,0 +1,179 @@
+use async_graphql::{
+    Context, EmptyMutation, Error, Json, Object, Result, Schema, SimpleObject, Subscription,
+};
+use chrono::NaiveDate;
+use futures_util::stream::{self, Stream};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::bundle;
+use crate::events::VersionEvent;
+use crate::stats;
+use crate::store::{self, VersionStore};
+use crate::version::has_prefix;
+
+pub type VersionsSchema = Schema<Query, EmptyMutation, Subscription>;
+
+/// The `/graphql` schema over `store`; `events` feeds the `newVersions`
+/// subscription and `archive_dir` answers `archived`.
+pub fn schema(
+    surface: &str,
+    store: Arc<dyn VersionStore>,
+    events: broadcast::Sender<VersionEvent>,
+    archive_dir: PathBuf,
+) -> VersionsSchema {
+    Schema::build(Query, EmptyMutation, Subscription)
+        .data(Arc::new(Shared {
+            surface: surface.to_string(),
+            store,
+            events,
+            archive_dir,
+        }))
+        .finish()
+}
+
+struct Shared {
+    surface: String,
+    store: Arc<dyn VersionStore>,
+    events: broadcast::Sender<VersionEvent>,
+    archive_dir: PathBuf,
+}
+
+impl Shared {
+    fn version(&self, key: String, entry: Value) -> Version {
+        let text = |field: &str| entry.get(field).and_then(Value::as_str).map(str::to_string);
+        Version {
+            surface: self.surface.clone(),
+            archived: bundle::is_archived(&self.archive_dir, &self.surface, &key),
+            client_version: text("clientVersion").unwrap_or_default(),
+            build_date: text("buildDate").unwrap_or_default(),
+            build_version: text("buildVersion"),
+            web_player: text("webPlayer"),
+            key,
+            entry: Json(entry),
+        }
+    }
+}
+
+/// A stored version.
+#[derive(SimpleObject)]
+pub struct Version {
+    surface: String,
+    key: String,
+    client_version: String,
+    build_date: String,
+    build_version: Option<String>,
+    web_player: Option<String>,
+    /// Whether this version's web-player.js is in the bundle archive.
+    archived: bool,
+    /// The stored entry as-is.
+    entry: Json<Value>,
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The newest stored version.
+    async fn latest(&self, ctx: &Context<'_>) -> Result<Option<Version>> {
+        let shared = ctx.data::<Arc<Shared>>()?;
+        let mut versions = shared.store.load().await.map_err(store_error)?;
+        let Some(key) = store::sorted_keys(&versions).into_iter().next() else {
+            return Ok(None);
+        };
+        let entry = versions.remove(&key).unwrap_or_default();
+        Ok(Some(shared.version(key, entry)))
+    }
+
+    /// A stored version by key, e.g. "1.2.86.316".
+    async fn version(&self, ctx: &Context<'_>, key: String) -> Result<Option<Version>> {
+        let shared = ctx.data::<Arc<Shared>>()?;
+        let mut versions = shared.store.load().await.map_err(store_error)?;
+        Ok(versions
+            .remove(&key)
+            .map(|entry| shared.version(key, entry)))
+    }
+
+    /// Stored versions, newest first. `since` and `until` are inclusive build
+    /// dates; `archived` keeps only versions with (or without) an archived bundle.
+    async fn versions(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<NaiveDate>,
+        until: Option<NaiveDate>,
+        prefix: Option<String>,
+        archived: Option<bool>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Version>> {
+        let shared = ctx.data::<Arc<Shared>>()?;
+        let mut versions = shared.store.load().await.map_err(store_error)?;
+
+        let mut found = Vec::new();
+        for key in store::sorted_keys(&versions) {
+            if limit.is_some_and(|limit| found.len() >= limit) {
+                break;
+            }
+            if prefix
+                .as_deref()
+                .is_some_and(|prefix| !has_prefix(&key, prefix))
+            {
+                continue;
+            }
+            let entry = versions.remove(&key).unwrap_or_default();
+            let date = stats::build_time(&entry).map(|time| time.date_naive());
+            if !in_range(date, since, until) {
+                continue;
+            }
+            let version = shared.version(key, entry);
+            if archived.is_some_and(|archived| archived != version.archived) {
+                continue;
+            }
+            found.push(version);
+        }
+        Ok(found)
+    }
+}
+
+pub struct Subscription;
+
+#[Subscription]
+impl Subscription {
+    /// Versions as they are found, by this server's checks or by another process
+    /// writing the store.
+    async fn new_versions(&self, ctx: &Context<'_>) -> Result<impl Stream<Item = Version>> {
+        let shared = ctx.data::<Arc<Shared>>()?.clone();
+        let receiver = shared.events.subscribe();
+        Ok(stream::unfold(
+            (shared, receiver),
+            |(shared, mut receiver)| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(VersionEvent::NewVersion { key, entry, .. }) => {
+                            let version = shared.version(key, entry);
+                            return Some((version, (shared, receiver)));
+                        }
+                        // A slow subscriber skips what it missed
+                        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        ))
+    }
+}
+
+fn store_error(e: crate::Error) -> Error {
+    Error::new(format!("Failed to load versions: {}", e))
+}
+
+fn in_range(date: Option<NaiveDate>, since: Option<NaiveDate>, until: Option<NaiveDate>) -> bool {
+    if since.is_none() && until.is_none() {
+        return true;
+    }
+    let Some(date) = date else {
+        return false;
+    };
+    since.iter().all(|since| date >= *since) && until.iter().all(|until| date <= *until)
+}
@@ -0,0 +1,134 @@
+use serde_json::Value;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::service::Interceptor;
+use tonic::{Request, Response, Status};
+
+use crate::events::VersionEvent;
+use crate::store::{self, VersionStore};
+
+pub mod proto {
+    #![allow(clippy::derive_partial_eq_without_eq)]
+    tonic::include_proto!("web_versions.v1");
+}
+
+use proto::web_versions_server::{WebVersions, WebVersionsServer};
+
+/// `WebVersions` over one surface's store, with new versions fed in through `events`.
+pub struct VersionsService {
+    surface: String,
+    store: Arc<dyn VersionStore>,
+    events: broadcast::Sender<VersionEvent>,
+}
+
+impl VersionsService {
+    pub fn new(
+        surface: &str,
+        store: Arc<dyn VersionStore>,
+        events: broadcast::Sender<VersionEvent>,
+    ) -> Self {
+        VersionsService {
+            surface: surface.to_string(),
+            store,
+            events,
+        }
+    }
+
+    async fn load(&self) -> Result<std::collections::HashMap<String, Value>, Status> {
+        self.store
+            .load()
+            .await
+            .map_err(|e| Status::internal(format!("Failed to load versions: {}", e)))
+    }
+}
+
+fn to_proto(surface: &str, key: &str, entry: &Value) -> proto::Version {
+    let text = |name: &str| entry[name].as_str().map(str::to_string);
+    proto::Version {
+        surface: surface.to_string(),
+        key: key.to_string(),
+        client_version: text("clientVersion").unwrap_or_default(),
+        build_date: text("buildDate").unwrap_or_default(),
+        build_version: text("buildVersion"),
+        web_player: text("webPlayer"),
+        entry_json: entry.to_string(),
+    }
+}
+
+type VersionStream = Pin<Box<dyn Stream<Item = Result<proto::Version, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl WebVersions for VersionsService {
+    async fn get_latest(
+        &self,
+        _request: Request<proto::GetLatestRequest>,
+    ) -> Result<Response<proto::Version>, Status> {
+        let versions = self.load().await?;
+        let key = store::sorted_keys(&versions)
+            .into_iter()
+            .next()
+            .ok_or_else(|| Status::not_found("No versions stored yet"))?;
+        Ok(Response::new(to_proto(
+            &self.surface,
+            &key,
+            &versions[&key],
+        )))
+    }
+
+    async fn list_versions(
+        &self,
+        request: Request<proto::ListVersionsRequest>,
+    ) -> Result<Response<proto::ListVersionsResponse>, Status> {
+        let limit = match request.into_inner().limit {
+            0 => usize::MAX,
+            limit => limit as usize,
+        };
+        let versions = self.load().await?;
+        let list = store::sorted_keys(&versions)
+            .iter()
+            .take(limit)
+            .map(|key| to_proto(&self.surface, key, &versions[key]))
+            .collect();
+        Ok(Response::new(proto::ListVersionsResponse {
+            versions: list,
+        }))
+    }
+
+    type WatchVersionsStream = VersionStream;
+
+    async fn watch_versions(
+        &self,
+        _request: Request<proto::WatchVersionsRequest>,
+    ) -> Result<Response<Self::WatchVersionsStream>, Status> {
+        // A subscriber that falls too far behind skips the missed events rather than failing
+        let stream =
+            BroadcastStream::new(self.events.subscribe()).filter_map(|event| match event {
+                Ok(VersionEvent::NewVersion {
+                    surface,
+                    key,
+                    entry,
+                }) => Some(Ok(to_proto(&surface, &key, &entry))),
+                _ => None,
+            });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+/// Serves `service` on `addr` until `shutdown` resolves.
+pub async fn serve(
+    addr: SocketAddr,
+    service: VersionsService,
+    interceptor: impl Interceptor + Clone + Send + Sync + 'static,
+    shutdown: impl Future<Output = ()>,
+) -> crate::Result<()> {
+    tonic::transport::Server::builder()
+        .add_service(WebVersionsServer::with_interceptor(service, interceptor))
+        .serve_with_shutdown(addr, shutdown)
+        .await?;
+    Ok(())
+}
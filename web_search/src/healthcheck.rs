@@ -0,0 +1,43 @@
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::config::{redact_url, HealthcheckConfig};
+use crate::log::{log_info, log_warning};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn ping_url(base: &str, success: bool) -> String {
+    if success {
+        base.to_string()
+    } else {
+        format!("{}/fail", base.trim_end_matches('/'))
+    }
+}
+
+/// Pings the heartbeat URL (or its `/fail` variant) with the run result as the body.
+pub async fn report(config: &HealthcheckConfig, output: &Value) {
+    let Some(base) = config.url.as_deref() else {
+        return;
+    };
+    let url = ping_url(base, output["success"] == true);
+
+    let result = async {
+        let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+        client
+            .post(&url)
+            .body(output.to_string())
+            .send()
+            .await?
+            .error_for_status()
+    }
+    .await;
+
+    match result {
+        // The URL is the check's credential
+        Ok(_) => log_info("HEALTH", &format!("Pinged {}", redact_url(&url))),
+        Err(e) => log_warning(
+            "HEALTH",
+            &format!("Heartbeat ping failed: {}", e.without_url()),
+        ),
+    }
+}
@@ -0,0 +1,50 @@
+//! Keys that checks must never add, such as leaked test builds. The list lives
+//! next to the versions file (`versions_web.json.ignore.json`) as a sorted
+//! JSON array.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+use crate::Result;
+
+pub fn ignore_path(versions_file: &Path) -> PathBuf {
+    let mut name = versions_file.file_name().unwrap_or_default().to_os_string();
+    name.push(".ignore.json");
+    versions_file.with_file_name(name)
+}
+
+/// The ignored keys; empty when there is no list yet.
+pub fn load(versions_file: &Path) -> Result<BTreeSet<String>> {
+    let path = ignore_path(versions_file);
+    let Some(content) = paths::read_optional(&path)? else {
+        return Ok(BTreeSet::new());
+    };
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e).into())
+}
+
+pub fn save(versions_file: &Path, keys: &BTreeSet<String>) -> Result<()> {
+    let content = serde_json::to_string_pretty(keys)?;
+    paths::write_atomic(&ignore_path(versions_file), content.as_bytes())
+}
+
+/// Adds `key` to the list; false if it was already there.
+pub fn add(versions_file: &Path, key: &str) -> Result<bool> {
+    let mut keys = load(versions_file)?;
+    let added = keys.insert(key.to_string());
+    if added {
+        save(versions_file, &keys)?;
+    }
+    Ok(added)
+}
+
+/// Removes `key` from the list; false if it was not there.
+pub fn remove(versions_file: &Path, key: &str) -> Result<bool> {
+    let mut keys = load(versions_file)?;
+    let removed = keys.remove(key);
+    if removed {
+        save(versions_file, &keys)?;
+    }
+    Ok(removed)
+}
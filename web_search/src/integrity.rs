@@ -0,0 +1,121 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::IntegrityConfig;
+use crate::log::{log_info, log_success};
+use crate::paths;
+use crate::Result;
+
+fn sidecar(file: &Path, extension: &str) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(extension);
+    file.with_file_name(name)
+}
+
+/// `versions_web.json.sha256`, in `sha256sum` format so `sha256sum -c` works on it.
+pub fn checksum_path(file: &Path) -> PathBuf {
+    sidecar(file, ".sha256")
+}
+
+/// `versions_web.json.sig`: Base64 ed25519 signature over the file's bytes.
+pub fn signature_path(file: &Path) -> PathBuf {
+    sidecar(file, ".sig")
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn decode_key<const N: usize>(text: &str, what: &str) -> Result<[u8; N]> {
+    let bytes = STANDARD
+        .decode(text.trim())
+        .map_err(|e| format!("Invalid {}: {}", what, e))?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("Invalid {}: expected {} bytes", what, N).into())
+}
+
+/// Reads a Base64 32-byte ed25519 secret key, e.g. from `openssl rand -base64 32`.
+pub fn load_signing_key(path: &Path) -> Result<SigningKey> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read signing key {}: {}", path.display(), e))?;
+    Ok(SigningKey::from_bytes(&decode_key(&text, "signing key")?))
+}
+
+pub fn public_key(key: &SigningKey) -> String {
+    STANDARD.encode(key.verifying_key().to_bytes())
+}
+
+/// Writes the checksum sidecar, and the signature if a signing key is configured.
+pub fn write_sidecars(config: &IntegrityConfig, file: &Path) -> Result<()> {
+    if !config.checksum && config.signing_key.is_none() {
+        return Ok(());
+    }
+    let content = fs::read(paths::long_path(file))?;
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+
+    if config.checksum {
+        let line = format!("{}  {}\n", sha256_hex(&content), name);
+        paths::write_atomic(&checksum_path(file), line.as_bytes())?;
+    }
+
+    if let Some(key_path) = &config.signing_key {
+        let key = load_signing_key(key_path)?;
+        let signature = STANDARD.encode(key.sign(&content).to_bytes());
+        paths::write_atomic(&signature_path(file), format!("{}\n", signature).as_bytes())?;
+        log_info(
+            "SIGN",
+            &format!("Signed {} (public key {})", name, public_key(&key)),
+        );
+    }
+    Ok(())
+}
+
+/// Checks the file against its sidecars. The signature is checked when a public key
+/// is given; a missing `.sig` then counts as a failure.
+pub fn verify(file: &Path, public_key: Option<&str>) -> Result<Value> {
+    let content = fs::read(paths::long_path(file))
+        .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+    let actual = sha256_hex(&content);
+
+    let checksum = match paths::read_optional(&checksum_path(file))? {
+        None => "missing",
+        Some(line) if line.split_whitespace().next() == Some(actual.as_str()) => "ok",
+        Some(_) => "mismatch",
+    };
+
+    let signature = match public_key {
+        None => "skipped",
+        Some(public_key) => {
+            let key = VerifyingKey::from_bytes(&decode_key(public_key, "public key")?)
+                .map_err(|e| format!("Invalid public key: {}", e))?;
+            match paths::read_optional(&signature_path(file))? {
+                None => "missing",
+                Some(text) => {
+                    let bytes: [u8; 64] = decode_key(&text, "signature")?;
+                    match key.verify(&content, &Signature::from_bytes(&bytes)) {
+                        Ok(()) => "ok",
+                        Err(_) => "invalid",
+                    }
+                }
+            }
+        }
+    };
+
+    let success = checksum != "mismatch" && matches!(signature, "ok" | "skipped");
+    if success {
+        log_success("VERIFY", &format!("{} verified", file.display()));
+    }
+    Ok(json!({
+        "success": success,
+        "file": file.display().to_string(),
+        "sha256": actual,
+        "checksum": checksum,
+        "signature": signature
+    }))
+}
@@ -0,0 +1,102 @@
+#![deny(clippy::print_stdout)]
+
+pub mod anomaly;
+#[cfg(feature = "store-json")]
+pub mod backup;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "net")]
+pub mod bundle;
+pub mod cadence;
+#[cfg(feature = "store-json")]
+pub mod changelog;
+pub mod chart;
+#[cfg(feature = "net")]
+pub mod check;
+#[cfg(feature = "store-json")]
+pub mod compress;
+pub mod config;
+pub mod cron;
+#[cfg(feature = "net")]
+pub mod doctor;
+pub mod events;
+#[cfg(feature = "scrape")]
+pub mod extract;
+#[cfg(feature = "net")]
+pub mod fetch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod gaps;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "net")]
+pub mod healthcheck;
+#[cfg(feature = "store-json")]
+pub mod ignore;
+#[cfg(feature = "store-json")]
+pub mod integrity;
+pub mod lock;
+pub mod log;
+#[cfg(feature = "net")]
+pub mod notify;
+#[cfg(feature = "cli")]
+pub mod output;
+#[cfg(feature = "net")]
+pub mod pace;
+pub mod paths;
+pub mod prettify;
+#[cfg(feature = "net")]
+pub mod publish;
+#[cfg(feature = "store-json")]
+pub mod query;
+#[cfg(feature = "net")]
+pub mod record;
+#[cfg(feature = "net")]
+pub mod regions;
+pub mod result;
+#[cfg(feature = "net")]
+pub mod results;
+#[cfg(feature = "net")]
+pub mod robots;
+#[cfg(feature = "net")]
+pub mod runner;
+#[cfg(feature = "store-json")]
+pub mod salvage;
+#[cfg(feature = "store-json")]
+pub mod schema;
+#[cfg(feature = "net")]
+pub mod scraper;
+#[cfg(feature = "scrape")]
+pub mod selftest;
+#[cfg(feature = "cli")]
+pub mod serve;
+#[cfg(feature = "net")]
+pub mod shutdown;
+pub mod site;
+#[cfg(feature = "net")]
+pub mod snapshot;
+#[cfg(feature = "net")]
+pub mod source;
+pub mod stats;
+#[cfg(feature = "store-json")]
+pub mod store;
+#[cfg(feature = "net")]
+pub mod sync;
+pub mod systemd;
+pub mod telemetry;
+pub mod timing;
+#[cfg(feature = "net")]
+pub mod token;
+#[cfg(feature = "net")]
+pub mod tor;
+pub mod version;
+#[cfg(feature = "cli")]
+pub mod watch;
+
+#[cfg(feature = "net")]
+pub use crate::scraper::Scraper;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+pub type Result<T> = std::result::Result<T, Error>;
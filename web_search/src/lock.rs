@@ -0,0 +1,62 @@
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use crate::log::{log_info, log_warning};
+use crate::Result;
+
+/// PID file guarding a versions file; removed again on drop.
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+}
+
+pub fn lock_path(versions_file: &Path) -> PathBuf {
+    let mut name = versions_file.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+impl InstanceLock {
+    pub fn acquire(path: &Path, force: bool) -> Result<InstanceLock> {
+        let pid = std::process::id();
+
+        match OpenOptions::new().write(true).create_new(true).open(path) {
+            Ok(mut file) => {
+                writeln!(file, "{}", pid)?;
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                let holder = fs::read_to_string(path).unwrap_or_default();
+                let holder = holder.trim();
+                if !force {
+                    return Err(format!(
+                        "{} is held by process {}; stop that instance or pass --force if it is stale",
+                        path.display(),
+                        if holder.is_empty() { "<unknown>" } else { holder }
+                    )
+                    .into());
+                }
+                log_warning(
+                    "LOCK",
+                    &format!("Taking over {} from process {}", path.display(), holder),
+                );
+                fs::write(path, format!("{}\n", pid))?;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        log_info(
+            "LOCK",
+            &format!("Acquired {} (pid {})", path.display(), pid),
+        );
+        Ok(InstanceLock {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
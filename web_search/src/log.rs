@@ -0,0 +1,114 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, Local, SecondsFormat, Timelike};
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::Result;
+
+pub const GREEN: &str = "1;32";
+pub const RED: &str = "1;31";
+pub const YELLOW: &str = "33";
+pub const DIM: &str = "2";
+
+static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
+static TIME_FORMAT: OnceLock<TimeFormat> = OnceLock::new();
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TimeFormat {
+    /// `2026-03-15T14:07:09.123+01:00`
+    #[default]
+    Rfc3339,
+    /// The original `15.03.2026-14:7:9:12` stamp, for log parsers that expect it
+    Legacy,
+    /// A chrono strftime pattern in local time
+    Custom(String),
+}
+
+impl TimeFormat {
+    /// Validates a strftime pattern up front; chrono would panic on it mid-log otherwise.
+    pub fn custom(pattern: &str) -> Result<TimeFormat> {
+        if StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("Invalid timestamp format '{}'", pattern).into());
+        }
+        Ok(TimeFormat::Custom(pattern.to_string()))
+    }
+}
+
+/// Sets the log timestamp format; only the first call takes effect.
+pub fn set_time_format(format: TimeFormat) {
+    let _ = TIME_FORMAT.set(format);
+}
+
+/// Turns ANSI colors off for logs and pretty output (`--no-color`).
+pub fn disable_color() {
+    COLOR_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Colors are used only on a terminal, and never with `--no-color` or a non-empty `NO_COLOR`.
+pub fn use_color(stream: &impl IsTerminal) -> bool {
+    !COLOR_DISABLED.load(Ordering::Relaxed)
+        && std::env::var_os("NO_COLOR").unwrap_or_default().is_empty()
+        && stream.is_terminal()
+}
+
+pub fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn log_time() -> String {
+    let now = Local::now();
+    match TIME_FORMAT.get().unwrap_or(&TimeFormat::Rfc3339) {
+        TimeFormat::Rfc3339 => now.to_rfc3339_opts(SecondsFormat::Millis, false),
+        TimeFormat::Custom(pattern) => now.format(pattern).to_string(),
+        TimeFormat::Legacy => format!(
+            "{:02}.{:02}.{:04}-{}:{}:{}:{:02}",
+            now.day(),
+            now.month(),
+            now.year(),
+            now.hour(),
+            now.minute(),
+            now.second(),
+            now.timestamp_subsec_millis() / 10
+        ),
+    }
+}
+
+/// wasm32-unknown-unknown has no stderr to write to, so logging is skipped there.
+const ENABLED: bool = !cfg!(all(target_arch = "wasm32", target_os = "unknown"));
+
+fn tag(label: &str, code: &str) -> String {
+    paint(label, code, use_color(&io::stderr()))
+}
+
+pub fn log_info(_step: &str, message: &str) {
+    if !ENABLED {
+        return;
+    }
+    eprintln!("[{}]  {}", log_time(), message);
+}
+
+pub fn log_success(_step: &str, message: &str) {
+    if !ENABLED {
+        return;
+    }
+    eprintln!("[{}]  {}  {}", log_time(), tag("[ OK ]", GREEN), message);
+}
+
+pub fn log_warning(_step: &str, message: &str) {
+    if !ENABLED {
+        return;
+    }
+    eprintln!("[{}]  {}  {}", log_time(), tag("[ WARN ]", YELLOW), message);
+}
+
+pub fn log_error(_step: &str, message: &str) {
+    if !ENABLED {
+        return;
+    }
+    eprintln!("[{}]  {}  {}", log_time(), tag("[ ERROR ]", RED), message);
+}
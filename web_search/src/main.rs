@@ -1,316 +1,901 @@
-use base64::{engine::general_purpose, Engine as _};
-use reqwest;
-use scraper::{Html, Selector};
-use serde_json::{json, Value};
-use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
-
-use chrono::{Datelike, Local, Timelike};
-
-fn log_time() -> String {
-    let now = Local::now();
-    format!(
-        "{:02}.{:02}.{:04}-{}:{}:{}:{:02}",
-        now.day(),
-        now.month(),
-        now.year(),
-        now.hour(),
-        now.minute(),
-        now.second(),
-        now.timestamp_subsec_millis() / 10
-    )
+#![deny(clippy::print_stdout)]
+
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use serde_json::json;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use web_search::check;
+use web_search::config::{self, Config, StoreBackend};
+use web_search::log::{self, log_error, log_info, log_success, TimeFormat};
+use web_search::output::{OutputFormat, OutputWriter};
+use web_search::result::CheckResult;
+use web_search::version::{has_prefix, in_range, version_key};
+use web_search::{backup, bundle, record};
+use web_search::{
+    chart, doctor, gaps, healthcheck, ignore, integrity, publish, query, results, runner, selftest,
+    serve, site, stats, store, sync, telemetry, watch,
+};
+
+#[derive(Parser)]
+#[command(version, about = "Detects new Spotify web player versions")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to the TOML config file (defaults to web_search.toml if present)
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Which page to scrape: "web" (the web player), "embed" (the embed player),
+    /// "youtube-music" (music.youtube.com), "apple-music" (music.apple.com) or the
+    /// name of a [[surfaces]] entry
+    #[arg(long, global = true, default_value = "web")]
+    source: String,
+
+    /// Disable colored logs and output (also honoured: NO_COLOR)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Use the original dd.mm.yyyy-h:m:s:cc log timestamps instead of RFC 3339
+    #[arg(long, global = true)]
+    legacy_timestamps: bool,
+
+    /// Fail with a non-zero exit on a missing buildVersion, an unexpected
+    /// appServerConfig shape or conflicting appServerConfig tags, instead of
+    /// saving partial data (also [extract] strict)
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Directory that relative data paths in the config are resolved against
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+
+    /// Output format for stdout
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Json)]
+    output: OutputFormat,
+
+    /// Also request the page as these markets (e.g. de,jp,us,br) and record which saw which version
+    #[arg(long, value_delimiter = ',')]
+    regions: Option<Vec<String>>,
+
+    /// Give up on a check after this long (e.g. 90s, 2m), reporting "timed_out": true
+    /// (also deadline_secs)
+    #[arg(long, global = true, value_parser = parse_deadline)]
+    deadline: Option<u64>,
+
+    /// Save every HTTP exchange to this directory, one JSON file per request
+    #[arg(long, global = true, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Answer HTTP requests from exchanges saved with --record instead of the network
+    #[arg(long, global = true)]
+    replay: Option<PathBuf>,
+
+    /// Also write each check result to this file, replacing it (also [results] file)
+    #[arg(long, global = true)]
+    result_file: Option<PathBuf>,
+
+    /// Also append each check result to this file as one JSON line (also [results] log)
+    #[arg(long, global = true)]
+    results_log: Option<PathBuf>,
+
+    /// Also POST each check result to this URL (also [results] post_url)
+    #[arg(long, global = true)]
+    post_result: Option<String>,
+
+    /// Log request/response headers, redirects, DNS lookups and timings to stderr.
+    /// Connect and TLS time are not exposed by the HTTP client and are included in TTFB.
+    #[arg(long)]
+    trace_http: bool,
 }
 
-fn log_info(_step: &str, message: &str) {
-    eprintln!("[{}]  {}", log_time(), message);
+#[derive(Subcommand)]
+enum Command {
+    /// Check repeatedly, printing one JSON line per check
+    Watch {
+        /// Seconds between checks (defaults to [watch] interval_secs)
+        #[arg(long)]
+        interval: Option<u64>,
+        /// Run on a cron schedule in local time instead, e.g. "*/10 8-20 * * 1-5"
+        #[arg(long, conflicts_with = "interval")]
+        cron: Option<String>,
+        /// Take over the instance lock even if another process holds it
+        #[arg(long)]
+        force: bool,
+        /// Watch every surface under [sources] at once, each on its own schedule
+        #[arg(long)]
+        all: bool,
+    },
+    /// Check every enabled surface under [sources] once, side by side, and print one summary
+    Run,
+    /// Serve the store over HTTP, checking for new versions in the background, until Ctrl-C/SIGTERM
+    Serve {
+        /// Also serve gRPC on [serve] grpc_addr (needs a build with --features grpc)
+        #[arg(long)]
+        grpc: bool,
+        /// Accept POST /hooks/check to check right away (also [serve] webhook_listener)
+        #[arg(long)]
+        webhook_listener: bool,
+    },
+    /// Work with archived web-player.js bundles
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+    /// Compare two archived web-player.js bundles
+    DiffBundle {
+        /// Older version (key or full clientVersion)
+        from: String,
+        /// Newer version (key or full clientVersion)
+        to: String,
+    },
+    /// Release statistics for the versions file
+    Stats {
+        /// Also write an SVG timeline of builds to this path
+        #[arg(long)]
+        chart: Option<PathBuf>,
+        /// Also write an SVG of web-player.js size per version to this path
+        /// (needs [bundle] asset_sizes)
+        #[arg(long)]
+        size_chart: Option<PathBuf>,
+    },
+    /// Known versions, newest first
+    List {
+        /// Only versions from this one on, including its builds (e.g. 1.2.55)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only versions up to this one, including its builds (e.g. 1.2.60)
+        #[arg(long)]
+        until: Option<String>,
+        /// Only versions starting with these whole segments (1.2.5 matches
+        /// 1.2.5.x, not 1.2.55)
+        #[arg(long)]
+        prefix: Option<String>,
+    },
+    /// The newest stored version
+    Latest {
+        #[command(flatten)]
+        pick: Pick,
+    },
+    /// One stored version
+    Show {
+        /// Version (key or full clientVersion)
+        key: String,
+        #[command(flatten)]
+        pick: Pick,
+    },
+    /// Stored versions matching a filter, newest first
+    Query {
+        /// Filter such as 'buildDate >= 2024-01-01 && has(webPlayer)'; fields are
+        /// entry keys, `key` or JSON pointers, operators are == != < <= > >= and ~
+        #[arg(long = "where")]
+        filter: Option<String>,
+        /// Comma-separated fields to keep, e.g. key,buildVersion (defaults to all)
+        #[arg(long, value_delimiter = ',')]
+        select: Vec<String>,
+        /// json (the usual output document) or csv (a header row, then one row per version)
+        #[arg(long, value_enum, default_value_t = QueryFormat::Json)]
+        format: QueryFormat,
+    },
+    /// Build numbers likely missed between consecutive stored versions
+    Gaps {
+        /// How much the last version segment grows per build
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        step: u64,
+    },
+    /// Keys that checks never add again, for leaked test builds
+    Ignore {
+        #[command(subcommand)]
+        action: IgnoreAction,
+    },
+    /// Attach notes or tags to a stored version, kept under x-annotations
+    /// and searchable with query (e.g. --where '/x-annotations/tag == regression')
+    Annotate {
+        /// Version (key or full clientVersion)
+        key: String,
+        /// name=value to set; repeatable
+        #[arg(long, value_parser = parse_annotation)]
+        set: Vec<(String, String)>,
+        /// Name to remove; repeatable
+        #[arg(long)]
+        unset: Vec<String>,
+    },
+    /// Remove all but the newest versions from the store
+    Prune {
+        /// How many of the newest versions to keep
+        #[arg(long)]
+        keep: usize,
+        /// Only report what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Download a published versions file and three-way-merge it into the store
+    Sync {
+        /// URL of the versions file to pull
+        #[arg(long, default_value = sync::CANONICAL_URL)]
+        from: String,
+        /// Only report what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Push the newest stored version to the [publish] endpoint now
+    Publish,
+    /// Revert the most recent save of the versions file recorded in the
+    /// [store] journal
+    Undo,
+    /// Roll the versions file back to a backup; lists backups without an argument
+    Restore {
+        /// Backup file name or path, or "latest"
+        backup: Option<String>,
+    },
+    /// Check the versions file against its .sha256 and, given a public key, its .sig
+    Verify {
+        /// File to check (defaults to the source's versions file)
+        file: Option<PathBuf>,
+        /// Base64 ed25519 public key (defaults to [integrity] public_key)
+        #[arg(long)]
+        public_key: Option<String>,
+    },
+    /// Check DNS, TLS and HTTP to the source, the User-Agent API, the data
+    /// directory and notifiers, and report each as pass/fail
+    Doctor,
+    /// Run extraction on HTML pages built into the binary; needs no network or config
+    SelfTest,
+    /// Print a shell completion script to stdout
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page to stdout, or write one page per command to a directory
+    Mangen {
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Static version history site
+    Site {
+        #[command(subcommand)]
+        action: SiteAction,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
 }
 
-fn log_success(_step: &str, message: &str) {
-    eprintln!("[{}]  [ OK ]  {}", log_time(), message);
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print what the config file and WEB_VERSIONS_* variables set
+    Show {
+        /// Print every option as in effect: defaults, file, environment, flags and resolved paths
+        #[arg(long)]
+        resolved: bool,
+    },
 }
 
-fn log_warning(_step: &str, message: &str) {
-    eprintln!("[{}]  {}", log_time(), message);
+#[derive(Subcommand)]
+enum BundleAction {
+    /// Pretty-print an archived web-player.js to <archive_dir>/<surface>/<key>.pretty.js
+    Prettify {
+        /// Version (key or full clientVersion)
+        key: String,
+    },
 }
 
-fn log_error(_step: &str, message: &str) {
-    eprintln!("[{}]  [ ERROR ]  {}", log_time(), message);
+#[derive(Subcommand)]
+enum IgnoreAction {
+    /// Ignore a version and remove it from the store if it was saved
+    Add {
+        /// Version (key or full clientVersion)
+        key: String,
+    },
+    /// Stop ignoring a version
+    Remove {
+        /// Version (key or full clientVersion)
+        key: String,
+    },
+    /// Print the ignored keys
+    List,
 }
 
-const SPOTIFY_URL: &str = "https://open.spotify.com";
-const USER_AGENT_API: &str = "https://jnrbsn.github.io/user-agents/user-agents.json";
-const VERSIONS_FILE: &str = "versions_web.json";
-
-async fn get_latest_user_agent() -> Result<String, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
-    let response = client.get(USER_AGENT_API).send().await?;
-    let user_agents: Vec<String> = response.json().await?;
-
-    Ok(user_agents
-        .first()
-        .cloned()
-        .unwrap_or_else(|| "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36".to_string()))
+#[derive(Subcommand)]
+enum SiteAction {
+    /// Render index.html, per-version pages and feed.xml from the versions file
+    Build {
+        /// Output directory (defaults to [site] out_dir)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
 }
 
-fn load_existing_versions() -> Result<HashMap<String, Value>, Box<dyn std::error::Error>> {
-    if Path::new(VERSIONS_FILE).exists() {
-        log_info("FILE", &format!("Loading existing {}", VERSIONS_FILE));
-        let content = fs::read_to_string(VERSIONS_FILE)?;
-        let versions: HashMap<String, Value> = serde_json::from_str(&content)?;
-        log_success(
-            "FILE",
-            &format!("Loaded {} existing versions", versions.len()),
-        );
-        Ok(versions)
-    } else {
-        log_warning(
-            "FILE",
-            &format!("{} not found, treating as new", VERSIONS_FILE),
-        );
-        Ok(HashMap::new())
-    }
+#[derive(Args)]
+struct Pick {
+    /// Print only this field of the entry: a jq-style path (.webPlayer,
+    /// .regions[0]) or a JSON pointer (/webPlayer)
+    #[arg(long)]
+    field: Option<String>,
+    /// With --field, print strings without JSON quotes
+    #[arg(long, requires = "field")]
+    raw: bool,
 }
 
-fn compare_versions(a: &str, b: &str) -> Ordering {
-    let parts_a: Vec<u32> = a.split('.').filter_map(|s| s.parse().ok()).collect();
-    let parts_b: Vec<u32> = b.split('.').filter_map(|s| s.parse().ok()).collect();
-
-    parts_b.cmp(&parts_a)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum QueryFormat {
+    Json,
+    Csv,
 }
 
-fn save_versions(versions: &HashMap<String, Value>) -> Result<(), Box<dyn std::error::Error>> {
-    log_info("SORT", "Sorting versions...");
-
-    let mut sorted_keys: Vec<String> = versions.keys().cloned().collect();
-    sorted_keys.sort_by(|a, b| compare_versions(a, b));
-
-    log_success("SORT", "Versions sorted");
-
-    let mut json_parts = Vec::new();
-    json_parts.push("{\n".to_string());
-
-    for (i, key) in sorted_keys.iter().enumerate() {
-        if let Some(value) = versions.get(key) {
-            let value_str = serde_json::to_string_pretty(value)?;
-
-            let indented_value: Vec<String> = value_str
-                .lines()
-                .map(|line| format!("  {}", line))
-                .collect();
-
-            json_parts.push(format!("  \"{}\": {}", key, indented_value.join("\n")));
-
-            if i < sorted_keys.len() - 1 {
-                json_parts.push(",\n".to_string());
-            } else {
-                json_parts.push("\n".to_string());
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if cli.no_color {
+        log::disable_color();
+    }
+    if cli.legacy_timestamps {
+        log::set_time_format(TimeFormat::Legacy);
+    }
+    let out = OutputWriter::new(cli.output);
+
+    match run(cli, &out).await {
+        Ok(code) => code,
+        Err(e) => {
+            log_error("FATAL", &e.to_string());
+            // Keep the one-document contract even when the run itself fails
+            if let Err(e) = out.emit(&CheckResult::from_error(e.as_ref()).to_value()) {
+                log_error("OUTPUT", &format!("Failed to write output: {}", e));
             }
+            ExitCode::FAILURE
         }
     }
-
-    json_parts.push("}".to_string());
-
-    let json_content = json_parts.join("");
-    fs::write(VERSIONS_FILE, json_content)?;
-    log_success("FILE", &format!("Saved {} to disk", VERSIONS_FILE));
-    Ok(())
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn run(cli: Cli, out: &OutputWriter) -> web_search::Result<ExitCode> {
     log_info("INIT", "Starting ...");
 
-    log_info("NET", "Getting actual User-Agent...");
-    let user_agent = get_latest_user_agent().await.unwrap_or_else(|_| {
-        log_warning("NET", "Failed to get UA from API, using fallback");
-        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36".to_string()
-    });
-
-    log_success("NET", &format!("User-Agent set: {}", user_agent));
-
-    log_info("HTTP", &format!("Sending request to {}", SPOTIFY_URL));
-    let client = reqwest::Client::builder()
-        .user_agent(&user_agent)
-        .timeout(std::time::Duration::from_secs(30))
-        .build()?;
-
-    let response = client.get(SPOTIFY_URL).send().await?;
-    let html_content = response.text().await?;
-
-    log_success(
-        "HTTP",
-        &format!("HTML received ({} bytes)", html_content.len()),
-    );
-
-    log_info("PARSE", "Parsing HTML document...");
-    let document = Html::parse_document(&html_content);
-
-    log_info("SEARCH", "Method 1: Scraper...");
-    let selectors = vec![
-        r#"script[id="appServerConfig"][type="text/plain"]"#,
-        r#"script[id="appServerConfig"]"#,
-        r#"script#appServerConfig"#,
-    ];
-
-    let mut base64_string: Option<String> = None;
-
-    for selector_str in selectors {
-        if let Ok(selector) = Selector::parse(selector_str) {
-            if let Some(element) = document.select(&selector).next() {
-                log_success("SCRAPER", &format!("Tag found: {}", selector_str));
-                base64_string = Some(element.text().collect::<String>().trim().to_string());
-                break;
+    // Before the config, which a packaged build may not have
+    match &cli.command {
+        Some(Command::Completions { shell }) => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(*shell, &mut command, name, &mut std::io::stdout());
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Mangen { out }) => {
+            let command = Cli::command();
+            match out {
+                Some(dir) => {
+                    std::fs::create_dir_all(dir)?;
+                    clap_mangen::generate_to(command, dir)?;
+                    log_success("MANGEN", &format!("Man pages written to {}", dir.display()));
+                }
+                None => {
+                    let mut page = Vec::new();
+                    clap_mangen::Man::new(command).render(&mut page)?;
+                    std::io::stdout().write_all(&page)?;
+                }
             }
+            return Ok(ExitCode::SUCCESS);
         }
+        _ => {}
     }
-
-    if base64_string.is_none() {
-        log_info("SEARCH", "Method 2: Regex...");
-        use regex::Regex;
-        let re = Regex::new(r#"<script[^>]*id="appServerConfig"[^>]*>([^<]+)</script>"#)?;
-        if let Some(caps) = re.captures(&html_content) {
-            log_success("REGEX", "Tag found via regex");
-            base64_string = Some(caps.get(1).unwrap().as_str().trim().to_string());
-        }
+    if let Some(Command::SelfTest) = cli.command {
+        let output = selftest::run();
+        out.emit(&output)?;
+        return Ok(if output["success"] == true {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
     }
 
-    log_info("SEARCH", "Searching for web-player ...");
-    let selector_js = Selector::parse("script[src]").expect("Selector creation error");
-    let mut web_player_url: Option<String> = None;
-    for element in document.select(&selector_js) {
-        if let Some(src) = element.value().attr("src") {
-            if src.contains("web-player") && src.ends_with(".js") {
-                web_player_url = Some(src.to_string());
-                log_success("FOUND", &format!("Web-player: {}", src));
-                break;
+    let mut config = Config::load(cli.config.as_deref())?;
+    let watch_all = matches!(
+        cli.command,
+        Some(Command::Watch { all: true, .. } | Command::Run)
+    );
+    if !watch_all {
+        config = config.for_source(&cli.source);
+    }
+    log::set_time_format(config.log.time_format()?);
+    config.trace_http |= cli.trace_http;
+    if let Some(dir) = cli.record {
+        record::set_mode(record::Mode::Record(dir));
+    } else if let Some(dir) = cli.replay {
+        record::set_mode(record::Mode::Replay(dir));
+    }
+    if let Some(deadline) = cli.deadline {
+        config.deadline_secs = Some(deadline);
+    }
+    if let Some(regions) = cli.regions {
+        config.regions.markets = regions;
+    }
+    if cli.strict {
+        // Every surface, so `run` and `watch --all` are strict as well
+        let names: Vec<String> = config
+            .surface_names()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        for name in names {
+            if let Some(extract) = config.extract_mut(&name) {
+                extract.strict = true;
             }
         }
     }
 
-    if let Some(base64_str) = base64_string {
-        if base64_str.is_empty() {
-            log_error("ERROR", "Tag found, but content is empty!");
-
-            let output = json!({
-                "success": false,
-                "error": "Base64 content is empty"
-            });
-            println!("{}", serde_json::to_string(&output)?);
-
-            return Ok(());
+    if let Some(Command::Watch { interval, cron, .. }) = &cli.command {
+        if let Some(interval) = interval {
+            config.watch.interval_secs = *interval;
+            config.watch.cron = None;
         }
+        if let Some(cron) = cron {
+            config.watch.cron = Some(cron.clone());
+        }
+    }
+    if let Some(Command::Serve {
+        webhook_listener: true,
+        ..
+    }) = &cli.command
+    {
+        config.serve.webhook_listener = true;
+    }
 
-        log_success(
-            "B64",
-            &format!("Base64 string found ({} characters)", base64_str.len()),
-        );
-
-        log_info("DECODE", "Decoding Base64...");
-        let decoded_bytes = general_purpose::STANDARD.decode(&base64_str)?;
-        let decoded_json = String::from_utf8(decoded_bytes)?;
+    let _telemetry = telemetry::init(&config.telemetry)?;
 
-        log_info("JSON", "Parsing JSON data...");
-        let json_object: Value = serde_json::from_str(&decoded_json)?;
+    if let Some(data_dir) = cli.data_dir {
+        config.data_dir = data_dir;
+    }
+    config.resolve_paths();
+    // Relative to the working directory, like other path flags
+    if let Some(path) = cli.result_file {
+        config.results.file = Some(path);
+    }
+    if let Some(path) = cli.results_log {
+        config.results.log = Some(path);
+    }
+    if let Some(url) = cli.post_result {
+        config.results.post_url = Some(url);
+    }
 
-        let version = json_object.get("clientVersion").and_then(|v| v.as_str());
-        let build_date = json_object.get("buildDate").and_then(|v| v.as_str());
-        let build_version = json_object.get("buildVersion").and_then(|v| v.as_str());
+    if let Some(Command::Watch {
+        force, all: true, ..
+    }) = cli.command
+    {
+        return watch::run_all(&config, force, out)
+            .await
+            .map(|()| ExitCode::SUCCESS);
+    }
+    if let Some(Command::Run) = cli.command {
+        let summary = runner::run(&config).await?;
+        healthcheck::report(&config.healthcheck, &summary).await;
+        results::deliver(&config.results, &config.http, &summary).await;
+        out.emit(&summary)?;
+        return Ok(if summary["success"] == true {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        });
+    }
+    let Some(surface) = config.surface(&cli.source) else {
+        return Err(format!(
+            "Unknown source '{}', expected one of {}",
+            cli.source,
+            config.surface_names().join(", ")
+        )
+        .into());
+    };
+
+    if let Some(Command::Watch { force, .. }) = cli.command {
+        return watch::run(&config, &surface, force, out)
+            .await
+            .map(|()| ExitCode::SUCCESS);
+    }
+    if let Some(Command::Serve { grpc, .. }) = cli.command {
+        return serve::run(&config, &surface, grpc)
+            .await
+            .map(|()| ExitCode::SUCCESS);
+    }
 
-        if let (Some(version), Some(build_date)) = (version, build_date) {
-            log_success("", "Version data extracted");
-            log_success("", &format!("clientVersion: {}", version));
-            log_success("", &format!("buildDate: {}", build_date));
-            if let Some(bv) = build_version {
-                log_success("", &format!("buildVersion: {}", bv));
+    let doctor = matches!(cli.command, Some(Command::Doctor));
+    let output = match cli.command {
+        Some(Command::Bundle {
+            action: BundleAction::Prettify { key },
+        }) => {
+            let path = bundle::prettify_archived(&config.bundle.archive_dir, surface.name, &key)?;
+            json!({
+                "success": true,
+                "surface": surface.name,
+                "key": key,
+                "path": path.display().to_string()
+            })
+        }
+        Some(Command::DiffBundle { from, to }) => {
+            bundle::diff_archived(&config.bundle.archive_dir, surface.name, &from, &to)?
+        }
+        Some(Command::Stats { chart, size_chart }) => {
+            let versions = store::open(&config, &surface).await?.load().await?;
+            if let Some(path) = chart {
+                let title = format!("{} builds", config.site.title);
+                std::fs::write(
+                    &path,
+                    chart::timeline_svg(&title, &stats::timeline(&versions)),
+                )?;
+                log_success("STATS", &format!("Chart written to {}", path.display()));
             }
-
-            let key = version.split('.').take(4).collect::<Vec<_>>().join(".");
-
-            let mut entry = json!({
-                "buildDate": build_date,
-                "clientVersion": version
-            });
-
-            if let Some(url) = web_player_url.as_deref() {
-                entry["webPlayer"] = json!(url);
+            if let Some(path) = size_chart {
+                let title = format!("{} web-player.js size", config.site.title);
+                std::fs::write(
+                    &path,
+                    chart::size_svg(&title, &stats::bundle_sizes(&versions)),
+                )?;
+                log_success(
+                    "STATS",
+                    &format!("Size chart written to {}", path.display()),
+                );
+            }
+            stats::compute(&versions)
+        }
+        Some(Command::List {
+            since,
+            until,
+            prefix,
+        }) => {
+            let versions = store::open(&config, &surface).await?.load().await?;
+            let list: Vec<_> = store::sorted_keys(&versions)
+                .into_iter()
+                .filter(|key| in_range(key, since.as_deref(), until.as_deref()))
+                .filter(|key| {
+                    prefix
+                        .as_deref()
+                        .is_none_or(|prefix| has_prefix(key, prefix))
+                })
+                .map(|key| {
+                    let mut item = versions[&key].clone();
+                    item["key"] = json!(key);
+                    item
+                })
+                .collect();
+            json!({
+                "success": true,
+                "surface": surface.name,
+                "versions": list
+            })
+        }
+        Some(Command::Latest { pick }) => {
+            let versions = store::open(&config, &surface).await?.load().await?;
+            let key = store::sorted_keys(&versions)
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("No versions stored for {}", surface.name))?;
+            match show(out, surface.name, &key, &versions[&key], &pick)? {
+                Some(output) => output,
+                None => return Ok(ExitCode::SUCCESS),
             }
-            if let Some(bv) = build_version {
-                entry["buildVersion"] = json!(bv);
+        }
+        Some(Command::Show { key, pick }) => {
+            let versions = store::open(&config, &surface).await?.load().await?;
+            let key = version_key(&key);
+            let entry = versions
+                .get(&key)
+                .ok_or_else(|| format!("No version {} stored for {}", key, surface.name))?;
+            match show(out, surface.name, &key, entry, &pick)? {
+                Some(output) => output,
+                None => return Ok(ExitCode::SUCCESS),
             }
-
-            log_info("CHECK", "Checking if version is new...");
-            match load_existing_versions() {
-                Ok(mut versions) => {
-                    if versions.contains_key(&key) {
-                        log_warning("CHECK", &format!("Version {} already exists", key));
-                        let output = json!({
-                            "success": true,
-                            "is_new": false,
-                            "key": key,
-                            "message": format!("Version {} already exists", key)
-                        });
-                        println!("{}", serde_json::to_string(&output)?);
-                    } else {
-                        log_success("CHECK", &format!("Version {} is NEW!", key));
-
-                        versions.insert(key.clone(), entry.clone());
-
-                        if let Err(e) = save_versions(&versions) {
-                            log_error("FILE", &format!("Failed to save versions: {}", e));
-                            let output = json!({
-                                "success": false,
-                                "error": format!("Failed to save versions: {}", e)
-                            });
-                            println!("{}", serde_json::to_string(&output)?);
-                            return Ok(());
-                        }
-
-                        let output = json!({
-                            "success": true,
-                            "is_new": true,
-                            "key": key,
-                            "data": entry,
-                            "message": format!("New version {} detected and saved", version)
-                        });
-                        println!("{}", serde_json::to_string(&output)?);
+        }
+        Some(Command::Query {
+            filter,
+            select,
+            format,
+        }) => {
+            let filter = filter.as_deref().map(query::parse).transpose()?;
+            let versions = store::open(&config, &surface).await?.load().await?;
+            let rows = query::run(&versions, filter.as_ref(), &select);
+            if format == QueryFormat::Csv {
+                let columns = if select.is_empty() {
+                    query::columns(&rows)
+                } else {
+                    select
+                };
+                out.emit_raw(&query::to_csv(&rows, &columns))?;
+                return Ok(ExitCode::SUCCESS);
+            }
+            json!({
+                "success": true,
+                "surface": surface.name,
+                "count": rows.len(),
+                "versions": rows
+            })
+        }
+        Some(Command::Gaps { step }) => {
+            let versions = store::open(&config, &surface).await?.load().await?;
+            let keys: Vec<String> = versions.into_keys().collect();
+            let mut report = gaps::report(&keys, step);
+            report["surface"] = json!(surface.name);
+            report
+        }
+        Some(Command::Ignore { action }) => {
+            let file = surface.versions_file;
+            match action {
+                IgnoreAction::Add { key } => {
+                    let key = version_key(&key);
+                    let added = ignore::add(file, &key)?;
+                    let store = store::open(&config, &surface).await?;
+                    let mut versions = store.load().await?;
+                    // Hash-named builds are stored under when they were first seen
+                    let before = versions.len();
+                    versions.retain(|stored_key, entry| {
+                        *stored_key != key && entry["clientVersion"] != key.as_str()
+                    });
+                    let removed = versions.len() < before;
+                    if removed {
+                        store.save(&versions).await?;
+                        log_success(
+                            "IGNORE",
+                            &format!("Removed {} from {}", key, store.describe()),
+                        );
                     }
+                    json!({
+                        "success": true,
+                        "surface": surface.name,
+                        "key": key,
+                        "added": added,
+                        "removed": removed
+                    })
                 }
-                Err(e) => {
-                    log_error("FILE", &format!("Failed to load versions: {}", e));
-                    let output = json!({
-                        "success": false,
-                        "error": format!("Failed to load versions: {}", e)
-                    });
-                    println!("{}", serde_json::to_string(&output)?);
+                IgnoreAction::Remove { key } => {
+                    let key = version_key(&key);
+                    json!({
+                        "success": true,
+                        "surface": surface.name,
+                        "key": key,
+                        "removed": ignore::remove(file, &key)?
+                    })
                 }
+                IgnoreAction::List => json!({
+                    "success": true,
+                    "surface": surface.name,
+                    "ignored": ignore::load(file)?
+                }),
             }
-
-            log_success("OUTPUT", "JSON output sent to stdout");
-        } else {
-            log_error(
-                "ERROR",
-                "Properties 'clientVersion' or 'buildDate' not found!",
+        }
+        Some(Command::Annotate { key, set, unset }) => {
+            let store = store::open(&config, &surface).await?;
+            let mut versions = store.load().await?;
+            let key = version_key(&key);
+            let annotations = store::annotate(&mut versions, &key, &set, &unset)?;
+            store.save(&versions).await?;
+            log_success(
+                "ANNOTATE",
+                &format!("Annotated {} in {}", key, store.describe()),
             );
-
-            let output = json!({
-                "success": false,
-                "error": "clientVersion or buildDate not found"
-            });
-            println!("{}", serde_json::to_string(&output)?);
+            json!({
+                "success": true,
+                "surface": surface.name,
+                "key": key,
+                "annotations": annotations
+            })
+        }
+        Some(Command::Prune { keep, dry_run }) => {
+            let store = store::open(&config, &surface).await?;
+            let mut versions = store.load().await?;
+            let removed = store::prune(&mut versions, keep);
+            if !dry_run && !removed.is_empty() {
+                store.save(&versions).await?;
+            }
+            log_success(
+                "PRUNE",
+                &format!(
+                    "{} {} versions from {}",
+                    if dry_run { "Would remove" } else { "Removed" },
+                    removed.len(),
+                    store.describe()
+                ),
+            );
+            json!({
+                "success": true,
+                "surface": surface.name,
+                "dry_run": dry_run,
+                "kept": versions.len(),
+                "removed": removed
+            })
+        }
+        Some(Command::Sync { from, dry_run }) => {
+            sync::run(&config, &surface, &from, dry_run).await?
         }
+        Some(Command::Publish) => {
+            let versions = store::open(&config, &surface).await?.load().await?;
+            let key = store::sorted_keys(&versions)
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("No versions stored for {}", surface.name))?;
+            publish::publish(&config.publish, &config.http, surface.name, &key, &versions).await?;
+            json!({
+                "success": true,
+                "surface": surface.name,
+                "key": key
+            })
+        }
+        Some(Command::Undo) => {
+            if config.store.backend != StoreBackend::File {
+                return Err("undo only applies to the file store".into());
+            }
+            match store::open_file(&config, &surface).undo()? {
+                Some(record) => {
+                    let keys: Vec<&str> = record.changes.iter().map(|c| c.key.as_str()).collect();
+                    log_success(
+                        "UNDO",
+                        &format!("Reverted the save of {}: {}", record.at, keys.join(", ")),
+                    );
+                    json!({
+                        "success": true,
+                        "surface": surface.name,
+                        "reverted": record
+                    })
+                }
+                None => return Err("Nothing to undo, the journal is empty".into()),
+            }
+        }
+        Some(Command::Restore { backup: name }) => {
+            if config.store.backend != StoreBackend::File {
+                return Err("restore only applies to the file store".into());
+            }
+            match name {
+                Some(name) => {
+                    let path = backup::find(&config.backup.dir, surface.versions_file, &name)?;
+                    backup::restore(&config.backup, surface.versions_file, &path)?;
+                    json!({
+                        "success": true,
+                        "restored": path.display().to_string(),
+                        "versions_file": surface.versions_file.display().to_string()
+                    })
+                }
+                None => {
+                    let backups: Vec<String> =
+                        backup::list(&config.backup.dir, surface.versions_file)?
+                            .iter()
+                            .map(|path| path.display().to_string())
+                            .collect();
+                    json!({ "success": true, "backups": backups })
+                }
+            }
+        }
+        Some(Command::Verify { file, public_key }) => {
+            let file = file.unwrap_or_else(|| surface.versions_file.to_path_buf());
+            let public_key = public_key.or_else(|| config.integrity.public_key.clone());
+            let output = integrity::verify(&file, public_key.as_deref())?;
+            if output["success"] != true {
+                return Err(format!(
+                    "Verification of {} failed: checksum {}, signature {}",
+                    file.display(),
+                    output["checksum"].as_str().unwrap_or("?"),
+                    output["signature"].as_str().unwrap_or("?")
+                )
+                .into());
+            }
+            output
+        }
+        Some(Command::Doctor) => doctor::run(&config, &surface).await,
+        Some(Command::Config {
+            action: ConfigAction::Show { resolved },
+        }) => {
+            let mut shown = if resolved {
+                serde_json::to_value(&config)?
+            } else {
+                let mut table = Config::load_file(cli.config.as_deref())?;
+                config::apply_env(&mut table, std::env::vars());
+                table
+            };
+            config::redact(&mut shown);
+            json!({ "success": true, "resolved": resolved, "config": shown })
+        }
+        Some(Command::Site {
+            action: SiteAction::Build { out },
+        }) => {
+            let versions = store::open(&config, &surface).await?.load().await?;
+            let out_dir = out.unwrap_or_else(|| config.site.out_dir.clone());
+            site::build(&config.site, &versions, &out_dir)?;
+            json!({
+                "success": true,
+                "site": out_dir.display().to_string(),
+                "versions": versions.len()
+            })
+        }
+        Some(
+            Command::Watch { .. }
+            | Command::Run
+            | Command::Serve { .. }
+            | Command::SelfTest
+            | Command::Completions { .. }
+            | Command::Mangen { .. },
+        ) => unreachable!("watch, run, serve, self-test, completions and mangen return above"),
+        None => {
+            let output = match check::run_surface(&config, &surface).await {
+                Ok(output) => output,
+                Err(e) => {
+                    let failed = CheckResult::from_error(e.as_ref()).to_value();
+                    healthcheck::report(&config.healthcheck, &failed).await;
+                    results::deliver(&config.results, &config.http, &failed).await;
+                    return Err(e);
+                }
+            };
+            healthcheck::report(&config.healthcheck, &output).await;
+            results::deliver(&config.results, &config.http, &output).await;
+            output
+        }
+    };
+    out.emit(&output)?;
+
+    log_success("OUTPUT", "Output sent to stdout");
+    if doctor && output["success"] != true {
+        return Ok(ExitCode::FAILURE);
+    }
+    if surface.extract.strict && output["success"] == false {
+        log_error("STRICT", "Check failed in strict mode");
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The output document for one stored version, or `None` once `--field` has
+/// written its value through `out`.
+fn show(
+    out: &OutputWriter,
+    surface: &str,
+    key: &str,
+    entry: &serde_json::Value,
+    pick: &Pick,
+) -> web_search::Result<Option<serde_json::Value>> {
+    let mut version = entry.clone();
+    version["key"] = json!(key);
+    let Some(field) = &pick.field else {
+        return Ok(Some(json!({
+            "success": true,
+            "surface": surface,
+            "version": version
+        })));
+    };
+    let value = query::lookup(&version, field)
+        .ok_or_else(|| format!("Version {} has no field {}", key, field))?;
+    let text = if pick.raw {
+        query::raw(value)
     } else {
-        log_error("FAIL", "Tag 'appServerConfig' not found in HTML!");
+        value.to_string()
+    };
+    out.emit_raw(&format!("{}\n", text))?;
+    Ok(None)
+}
 
-        let output = json!({
-            "success": false,
-            "error": "appServerConfig tag not found"
-        });
-        println!("{}", serde_json::to_string(&output)?);
+/// `name=value` for `annotate --set`.
+fn parse_annotation(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((name, value)) if !name.is_empty() => Ok((name.to_string(), value.to_string())),
+        _ => Err(format!("expected name=value, got '{}'", value)),
     }
+}
 
-    Ok(())
+/// Seconds in `90`, `90s`, `2m` or `1h`.
+fn parse_deadline(value: &str) -> Result<u64, String> {
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "s"),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("expected a duration like 90s or 2m, got '{}'", value))?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => return Err(format!("unknown unit '{}', expected s, m or h", unit)),
+    };
+    let secs = number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("'{}' is too long a deadline", value))?;
+    if secs == 0 {
+        return Err("the deadline must be at least 1 s".to_string());
+    }
+    Ok(secs)
 }
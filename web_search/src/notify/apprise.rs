@@ -0,0 +1,107 @@
+use async_trait::async_trait;
+use reqwest::Url;
+use serde_json::json;
+
+use super::{client, Message, Notifier};
+use crate::config::AppriseConfig;
+use crate::Result;
+
+/// Hands the message to Apprise: its API server if `api_url` is set, else its CLI.
+pub struct AppriseNotifier {
+    config: AppriseConfig,
+}
+
+impl AppriseNotifier {
+    pub fn new(config: AppriseConfig) -> Self {
+        AppriseNotifier { config }
+    }
+
+    async fn post(&self, api_url: &str, message: &Message) -> Result<()> {
+        let mut body = json!({
+            "title": message.title,
+            "body": message.body
+        });
+        if !self.config.urls.is_empty() {
+            body["urls"] = json!(self.config.urls.join(","));
+        }
+        client()?
+            .post(api_url)
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.without_url())?;
+        Ok(())
+    }
+
+    /// Runs the CLI; with `dry_run` it only parses the URLs.
+    async fn run(&self, message: &Message, dry_run: bool) -> Result<()> {
+        let mut command = tokio::process::Command::new(&self.config.command);
+        if dry_run {
+            command.arg("--dry-run");
+        }
+        // URLs carry credentials; keep them off the process list
+        let output = command
+            .arg("--title")
+            .arg(&message.title)
+            .arg("--body")
+            .arg(&message.body)
+            .env("APPRISE_URLS", self.config.urls.join(" "))
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run {}: {}", self.config.command, e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "{} exited with {}: {}",
+                self.config.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for AppriseNotifier {
+    fn name(&self) -> &'static str {
+        "apprise"
+    }
+
+    async fn send(&self, message: &Message) -> Result<()> {
+        match &self.config.api_url {
+            Some(api_url) => self.post(api_url, message).await,
+            None => self.run(message, false).await,
+        }
+    }
+
+    /// The API server's `/status`, or the CLI with `--dry-run`.
+    async fn probe(&self) -> Result<String> {
+        match &self.config.api_url {
+            Some(api_url) => {
+                let mut url = Url::parse(api_url)?;
+                url.set_path("/status");
+                url.set_query(None);
+                client()?
+                    .get(url)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| e.without_url())?;
+                Ok("API server is up".to_string())
+            }
+            None => {
+                let message = Message {
+                    title: "web_search doctor".to_string(),
+                    body: "dry run".to_string(),
+                };
+                self.run(&message, true).await?;
+                Ok(format!(
+                    "{} --dry-run accepts the URLs",
+                    self.config.command
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+
+use super::{Message, Notifier};
+use crate::Result;
+
+/// A native notification (D-Bus on Linux and BSD, Notification Center on
+/// macOS, toast on Windows).
+pub struct DesktopNotifier;
+
+#[async_trait]
+impl Notifier for DesktopNotifier {
+    fn name(&self) -> &'static str {
+        "desktop"
+    }
+
+    async fn send(&self, message: &Message) -> Result<()> {
+        let mut notification = notify_rust::Notification::new();
+        notification
+            .appname("web_search")
+            .summary(&message.title)
+            .body(&message.body);
+        // D-Bus calls block
+        tokio::task::spawn_blocking(move || notification.show().map(|_| ()))
+            .await?
+            .map_err(|e| e.to_string().into())
+    }
+}
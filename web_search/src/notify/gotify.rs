@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::{client, Message, Notifier};
+use crate::config::GotifyConfig;
+use crate::Result;
+
+/// A message to a Gotify server, posted with an application token.
+pub struct GotifyNotifier {
+    config: GotifyConfig,
+}
+
+impl GotifyNotifier {
+    pub fn new(config: GotifyConfig) -> Self {
+        GotifyNotifier { config }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.config
+                .url
+                .as_deref()
+                .unwrap_or_default()
+                .trim_end_matches('/'),
+            path
+        )
+    }
+}
+
+#[async_trait]
+impl Notifier for GotifyNotifier {
+    fn name(&self) -> &'static str {
+        "gotify"
+    }
+
+    async fn send(&self, message: &Message) -> Result<()> {
+        client()?
+            .post(self.url("message"))
+            .header(
+                "X-Gotify-Key",
+                self.config.token.as_deref().unwrap_or_default(),
+            )
+            .json(&json!({
+                "title": message.title,
+                "message": message.body,
+                "priority": self.config.priority
+            }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.without_url())?;
+        Ok(())
+    }
+
+    /// `GET /version` needs no token, so the token itself goes unchecked.
+    async fn probe(&self) -> Result<String> {
+        let version: Value = client()?
+            .get(self.url("version"))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.without_url())?
+            .json()
+            .await?;
+        Ok(format!(
+            "Gotify {} is up (the app token is only checked when sending)",
+            version["version"].as_str().unwrap_or("(unknown version)")
+        ))
+    }
+}
@@ -0,0 +1,204 @@
+//! Notifications about new versions, sent once a new version is saved.
+
+#[cfg(feature = "notify-apprise")]
+mod apprise;
+#[cfg(feature = "notify-desktop")]
+mod desktop;
+#[cfg(feature = "notify-gotify")]
+mod gotify;
+#[cfg(feature = "notify-pushover")]
+mod pushover;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::config::{NotifyConfig, Surface};
+use crate::log::{log_success, log_warning};
+use crate::Result;
+
+#[cfg(feature = "notify-apprise")]
+pub use apprise::AppriseNotifier;
+#[cfg(feature = "notify-desktop")]
+pub use desktop::DesktopNotifier;
+#[cfg(feature = "notify-gotify")]
+pub use gotify::GotifyNotifier;
+#[cfg(feature = "notify-pushover")]
+pub use pushover::PushoverNotifier;
+
+/// What a notifier tells people about a new version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// "Spotify Web 1.2.61 released"
+    pub title: String,
+    pub body: String,
+}
+
+impl Message {
+    pub fn new_version(surface: &Surface<'_>, key: &str, entry: &Value) -> Self {
+        Message {
+            title: title(surface, key, entry),
+            body: match entry["buildDate"].as_str() {
+                Some(date) => format!("Built {}", date),
+                None => String::new(),
+            },
+        }
+    }
+}
+
+/// A push backend. Each one gets the same retries from `[notify]`.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Name in logs and `doctor`, e.g. `gotify`.
+    fn name(&self) -> &'static str;
+    async fn send(&self, message: &Message) -> Result<()>;
+
+    /// Checks the backend can be reached without sending anything, for
+    /// `doctor`, and says what was checked.
+    async fn probe(&self) -> Result<String> {
+        Ok("not probed, a test message would look like a release".to_string())
+    }
+}
+
+/// "Spotify Web 1.2.61 released"
+pub fn title(surface: &Surface<'_>, key: &str, entry: &Value) -> String {
+    let name = match surface.name {
+        "web" => "Spotify Web",
+        "embed" => "Spotify Embed",
+        "youtube-music" => "YouTube Music",
+        "apple-music" => "Apple Music Web",
+        other => other,
+    };
+    format!(
+        "{} {} released",
+        name,
+        entry["clientVersion"].as_str().unwrap_or(key)
+    )
+}
+
+/// The notifiers set up in `[notify]`. Backends this build lacks are logged
+/// and skipped, so the rest still send.
+pub fn notifiers(config: &NotifyConfig) -> Vec<Box<dyn Notifier>> {
+    #[allow(unused_mut)]
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+
+    #[cfg(feature = "notify-desktop")]
+    if config.desktop.enabled {
+        notifiers.push(Box::new(DesktopNotifier));
+    }
+    #[cfg(feature = "notify-gotify")]
+    if config.gotify.is_set() {
+        notifiers.push(Box::new(GotifyNotifier::new(config.gotify.clone())));
+    }
+    #[cfg(feature = "notify-pushover")]
+    if config.pushover.is_set() {
+        notifiers.push(Box::new(PushoverNotifier::new(config.pushover.clone())));
+    }
+    #[cfg(feature = "notify-apprise")]
+    if config.apprise.is_set() {
+        notifiers.push(Box::new(AppriseNotifier::new(config.apprise.clone())));
+    }
+
+    for name in missing(config) {
+        log_warning("NOTIFY", &format!("{}; skipping it", needs_feature(name)));
+    }
+    notifiers
+}
+
+/// Backends set up in `[notify]` that this build was compiled without.
+pub fn missing(config: &NotifyConfig) -> Vec<&'static str> {
+    [
+        (
+            "desktop",
+            config.desktop.enabled,
+            cfg!(feature = "notify-desktop"),
+        ),
+        (
+            "gotify",
+            config.gotify.is_set(),
+            cfg!(feature = "notify-gotify"),
+        ),
+        (
+            "pushover",
+            config.pushover.is_set(),
+            cfg!(feature = "notify-pushover"),
+        ),
+        (
+            "apprise",
+            config.apprise.is_set(),
+            cfg!(feature = "notify-apprise"),
+        ),
+    ]
+    .into_iter()
+    .filter(|&(_, set, compiled)| set && !compiled)
+    .map(|(name, ..)| name)
+    .collect()
+}
+
+/// "[notify.gotify] needs a build with `--features notify-gotify`"
+pub fn needs_feature(name: &str) -> String {
+    format!(
+        "[notify.{}] needs a build with `--features notify-{}`",
+        name, name
+    )
+}
+
+/// Sends the notifications set up in `[notify]`. Failures are logged and never
+/// fail the check.
+pub async fn new_version(config: &NotifyConfig, surface: &Surface<'_>, key: &str, entry: &Value) {
+    let notifiers = notifiers(config);
+    let message = Message::new_version(surface, key, entry);
+    for notifier in &notifiers {
+        match send_with_retry(config, notifier.as_ref(), &message).await {
+            Ok(()) => log_success("NOTIFY", &format!("{}: {}", notifier.name(), message.title)),
+            Err(e) => log_warning(
+                "NOTIFY",
+                &format!("{} notification failed: {}", notifier.name(), e),
+            ),
+        }
+    }
+}
+
+/// Up to `retries` more attempts, the wait doubling from `retry_backoff_ms`.
+pub async fn send_with_retry(
+    config: &NotifyConfig,
+    notifier: &dyn Notifier,
+    message: &Message,
+) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        let result = notifier.send(message).await;
+        if result.is_ok() || attempt >= config.retries {
+            return result;
+        }
+        let delay = Duration::from_millis(config.retry_backoff_ms)
+            .saturating_mul(2u32.saturating_pow(attempt));
+        attempt += 1;
+        if let Err(e) = &result {
+            log_warning(
+                "NOTIFY",
+                &format!(
+                    "{} attempt {} of {} failed ({}), retrying in {} ms",
+                    notifier.name(),
+                    attempt,
+                    config.retries + 1,
+                    e,
+                    delay.as_millis()
+                ),
+            );
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// The client the push backends post with.
+#[cfg(any(
+    feature = "notify-apprise",
+    feature = "notify-gotify",
+    feature = "notify-pushover"
+))]
+fn client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?)
+}
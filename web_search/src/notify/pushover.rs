@@ -0,0 +1,88 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::{client, Message, Notifier};
+use crate::config::PushoverConfig;
+use crate::Result;
+
+/// A Pushover message to a user or group key, sent as an application.
+pub struct PushoverNotifier {
+    config: PushoverConfig,
+}
+
+impl PushoverNotifier {
+    pub fn new(config: PushoverConfig) -> Self {
+        PushoverNotifier { config }
+    }
+}
+
+#[async_trait]
+impl Notifier for PushoverNotifier {
+    fn name(&self) -> &'static str {
+        "pushover"
+    }
+
+    async fn send(&self, message: &Message) -> Result<()> {
+        let priority = self.config.priority.to_string();
+        // Pushover rejects an empty message
+        let text = if message.body.is_empty() {
+            &message.title
+        } else {
+            &message.body
+        };
+        let mut form = vec![
+            ("token", self.config.token.as_deref().unwrap_or_default()),
+            ("user", self.config.user.as_deref().unwrap_or_default()),
+            ("title", message.title.as_str()),
+            ("message", text.as_str()),
+            ("priority", priority.as_str()),
+        ];
+        // Emergency priority repeats until acknowledged: every minute for an hour
+        if self.config.priority == 2 {
+            form.extend([("retry", "60"), ("expire", "3600")]);
+        }
+        client()?
+            .post(&self.config.api_url)
+            .form(&form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| e.without_url())?;
+        Ok(())
+    }
+
+    /// Asks `users/validate.json`, next to `api_url`, whether the application
+    /// token and user key are good.
+    async fn probe(&self) -> Result<String> {
+        let base = self
+            .config
+            .api_url
+            .strip_suffix("messages.json")
+            .ok_or("[notify.pushover] api_url doesn't end in messages.json")?;
+        let body: Value = client()?
+            .post(format!("{}users/validate.json", base))
+            .form(&[
+                ("token", self.config.token.as_deref().unwrap_or_default()),
+                ("user", self.config.user.as_deref().unwrap_or_default()),
+            ])
+            .send()
+            .await
+            .map_err(|e| e.without_url())?
+            .json()
+            .await?;
+        if body["status"] != 1 {
+            let errors = body["errors"]
+                .as_array()
+                .map(|errors| {
+                    errors
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                })
+                .unwrap_or_default();
+            return Err(format!("Pushover rejected the token or user key: {}", errors).into());
+        }
+        Ok("token and user key are valid".to_string())
+    }
+}
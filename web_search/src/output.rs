@@ -0,0 +1,137 @@
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+use std::io::{self, Write};
+
+use crate::log::{paint, use_color, DIM, GREEN, RED, YELLOW};
+use crate::result::API_VERSION;
+use crate::timing::format_bytes;
+use crate::Result;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Exactly one JSON document on stdout (one per line in watch mode); logs go to stderr
+    #[default]
+    Json,
+    /// A short colored summary for interactive use
+    Pretty,
+}
+
+/// The only writer of stdout. Everything else logs to stderr, and `clippy::print_stdout`
+/// is denied crate-wide so a stray `println!` fails the lint.
+#[derive(Debug, Clone, Copy)]
+pub struct OutputWriter {
+    format: OutputFormat,
+}
+
+impl OutputWriter {
+    pub fn new(format: OutputFormat) -> Self {
+        OutputWriter { format }
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    /// Writes one document. JSON documents that don't carry `apiVersion` yet
+    /// (everything but check results) get the current one.
+    pub fn emit(&self, output: &Value) -> Result<()> {
+        let mut stdout = io::stdout().lock();
+        match self.format {
+            OutputFormat::Json => match output {
+                Value::Object(fields) if !fields.contains_key("apiVersion") => {
+                    let mut versioned = Map::new();
+                    versioned.insert("apiVersion".to_string(), json!(API_VERSION));
+                    versioned.extend(fields.clone());
+                    serde_json::to_writer(&mut stdout, &versioned)?
+                }
+                _ => serde_json::to_writer(&mut stdout, output)?,
+            },
+            OutputFormat::Pretty => {
+                let color = use_color(&stdout);
+                write!(stdout, "{}", pretty(output, color))?;
+            }
+        }
+        writeln!(stdout)?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// Writes `text` as it is, for output that isn't a document (CSV, a
+    /// single field) and so is the same in every format.
+    pub fn emit_raw(&self, text: &str) -> Result<()> {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(text.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// Human summary of a check result; other outputs (stats, site, diffs) are pretty-printed JSON.
+pub fn pretty(output: &Value, color: bool) -> String {
+    let key = output["key"].as_str().unwrap_or("?");
+    let mut lines = Vec::new();
+
+    if output["success"] == false {
+        lines.push(paint(
+            &format!(
+                "FAILED: {}",
+                output["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error")
+            ),
+            RED,
+            color,
+        ));
+        if let Some(snapshot) = output["snapshot"].as_str() {
+            lines.push(format!("  page saved to {}", snapshot));
+        }
+        return lines.join("\n");
+    }
+
+    match output["is_new"].as_bool() {
+        Some(true) => {
+            let data = &output["data"];
+            let mut parts = vec![paint(
+                &format!(
+                    "NEW VERSION {}",
+                    data["clientVersion"].as_str().unwrap_or(key)
+                ),
+                GREEN,
+                color,
+            )];
+            if let Some(date) = data["buildDate"].as_str() {
+                parts.push(format!("built {}", date));
+            }
+            if let Some(bytes) = output["bundle_bytes"].as_u64() {
+                parts.push(format!("bundle {}", format_bytes(bytes)));
+            }
+            lines.push(parts.join(", "));
+        }
+        Some(false) => lines.push(format!("No new version, {} is already known", key)),
+        None => return serde_json::to_string_pretty(output).unwrap_or_default(),
+    }
+
+    if let Some(status) = output["bundle_check"]["status"].as_str() {
+        if status != "match" {
+            lines.push(paint(
+                &format!("  bundle cross-check: {}", status),
+                YELLOW,
+                color,
+            ));
+        }
+    }
+    if let Some(regions) = output["regions"].as_object() {
+        for (region, version) in regions {
+            lines.push(format!(
+                "  {}: {}",
+                region,
+                version.as_str().unwrap_or("no version")
+            ));
+        }
+    }
+    if let Some(total) = output["timings"]["total_ms"].as_u64() {
+        lines.push(paint(&format!("  took {} ms", total), DIM, color));
+    }
+
+    lines.join("\n")
+}
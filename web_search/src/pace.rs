@@ -0,0 +1,85 @@
+use rand::Rng;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+use crate::config::HttpConfig;
+use crate::log::log_info;
+
+/// The politeness limits every request of this process goes through.
+struct Pacer {
+    /// `None` when concurrency is unlimited.
+    permits: Option<Semaphore>,
+    /// Minimum time between two request starts.
+    gap: Duration,
+    next_start: Mutex<Instant>,
+}
+
+static PACER: OnceLock<Pacer> = OnceLock::new();
+
+impl Pacer {
+    fn new(http: &HttpConfig) -> Self {
+        let per_minute = match http.requests_per_minute {
+            0 => Duration::ZERO,
+            rpm => Duration::from_secs(60) / rpm,
+        };
+        Pacer {
+            permits: (http.max_concurrency > 0).then(|| Semaphore::new(http.max_concurrency)),
+            gap: per_minute.max(Duration::from_millis(http.request_delay_ms)),
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+/// Waits for a concurrency slot and this request's turn, then returns the slot
+/// to hold until the response is read. The limits are read from the first
+/// config that asks and shared by every request after it.
+pub async fn wait(http: &HttpConfig) -> Option<SemaphorePermit<'static>> {
+    if http.max_concurrency == 0 && http.request_delay_ms == 0 && http.requests_per_minute == 0 {
+        return None;
+    }
+    let pacer = PACER.get_or_init(|| Pacer::new(http));
+
+    let permit = match &pacer.permits {
+        Some(permits) => permits.acquire().await.ok(),
+        None => None,
+    };
+
+    let start = {
+        let mut next_start = pacer.next_start.lock().unwrap();
+        let start = (*next_start).max(Instant::now());
+        *next_start = start + pacer.gap;
+        start
+    };
+    let wait = start.saturating_duration_since(Instant::now());
+    if wait >= Duration::from_secs(1) {
+        log_info(
+            "PACE",
+            &format!("Waiting {} ms before the next request", wait.as_millis()),
+        );
+    }
+    tokio::time::sleep_until(start).await;
+
+    permit
+}
+
+/// `base` moved by a random amount of up to `spread` either way, but never
+/// below half of `base`, so a spread as wide as the interval can't make a loop
+/// spin with no wait at all.
+pub fn jitter(base: Duration, spread: Duration) -> Duration {
+    if spread.is_zero() {
+        return base;
+    }
+    let offset = rand::thread_rng().gen_range(Duration::ZERO..=spread.saturating_mul(2));
+    (base + offset).saturating_sub(spread).max(base / 2)
+}
+
+/// Sleeps for a random time of up to `max`.
+pub async fn pause(max: Duration) {
+    if max.is_zero() {
+        return;
+    }
+    let pause = rand::thread_rng().gen_range(Duration::ZERO..=max);
+    tokio::time::sleep(pause).await;
+}
@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+#[cfg(feature = "store-json")]
+use std::{fs, io};
+
+#[cfg(feature = "store-json")]
+use crate::compress;
+#[cfg(feature = "store-json")]
+use crate::config::Compression;
+#[cfg(feature = "store-json")]
+use crate::Result;
+
+/// Relative paths live under the data dir; absolute ones are used as given.
+pub fn resolve(data_dir: &Path, path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        data_dir.join(path)
+    }
+}
+
+/// On Windows, paths past MAX_PATH get the `\\?\` verbatim prefix so they can still be opened.
+#[cfg(windows)]
+pub fn long_path(path: &Path) -> PathBuf {
+    const MAX_PATH: usize = 260;
+    if path.as_os_str().len() < MAX_PATH {
+        return path.to_path_buf();
+    }
+    // absolute() also normalizes separators and `.`/`..`, which verbatim paths don't allow
+    let Ok(absolute) = std::path::absolute(path) else {
+        return path.to_path_buf();
+    };
+    let text = absolute.to_string_lossy();
+    if text.starts_with(r"\\?\") {
+        absolute
+    } else if let Some(unc) = text.strip_prefix(r"\\") {
+        PathBuf::from(format!(r"\\?\UNC\{}", unc))
+    } else {
+        PathBuf::from(format!(r"\\?\{}", text))
+    }
+}
+
+#[cfg(not(windows))]
+pub fn long_path(path: &Path) -> PathBuf {
+    path.to_path_buf()
+}
+
+/// Reads a file, or `None` if it doesn't exist. `.gz`/`.zst` files are decompressed.
+#[cfg(feature = "store-json")]
+pub fn read_optional(path: &Path) -> Result<Option<String>> {
+    let bytes = match fs::read(long_path(path)) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+    };
+    let decoded = compress::decode(Compression::from_path(path), &bytes)
+        .map_err(|e| format!("Failed to decompress {}: {}", path.display(), e))?;
+    String::from_utf8(decoded)
+        .map(Some)
+        .map_err(|e| format!("{} is not UTF-8: {}", path.display(), e).into())
+}
+
+/// Writes through a temp file and a rename, creating parent directories as needed.
+/// A read-only target is left alone and reported rather than replaced.
+/// `.gz`/`.zst` files are compressed.
+#[cfg(feature = "store-json")]
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    let target = long_path(path);
+    let contents = compress::encode(Compression::from_path(path), contents)?;
+
+    if let Ok(metadata) = fs::metadata(&target) {
+        if metadata.permissions().readonly() {
+            return Err(format!("{} is read-only, not overwriting it", path.display()).into());
+        }
+    }
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    let mut temp_name = target.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp = target.with_file_name(temp_name);
+
+    let written = fs::write(&temp, &contents).and_then(|()| fs::rename(&temp, &target));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {}", path.display(), e).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,196 @@
+//! A small JavaScript pretty-printer for reading and diffing minified bundles.
+//! It only re-indents and breaks lines at braces and statement ends; string,
+//! template, regex literals and comments are copied untouched. The output is
+//! for people, not for running.
+
+const INDENT: &str = "  ";
+
+/// Characters after which a `/` starts a regex literal rather than a division.
+const BEFORE_REGEX: &str = "(,=:[!&|?{};~+-*%<>^";
+
+struct Printer {
+    out: String,
+    indent: usize,
+    line_start: bool,
+}
+
+impl Printer {
+    fn newline(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.line_start = true;
+    }
+
+    fn push(&mut self, text: &str) {
+        if self.line_start {
+            for _ in 0..self.indent {
+                self.out.push_str(INDENT);
+            }
+            self.line_start = false;
+        }
+        self.out.push_str(text);
+    }
+
+    fn space(&mut self) {
+        if !self.line_start && !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+    }
+}
+
+pub fn prettify(js: &str) -> String {
+    let chars: Vec<char> = js.chars().collect();
+    let mut printer = Printer {
+        out: String::with_capacity(js.len() * 5 / 4),
+        indent: 0,
+        line_start: true,
+    };
+    // Last significant character outside literals, to tell regexes from division
+    let mut last: Option<char> = None;
+    let mut parens = 0usize;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '"' | '\'' | '`' => {
+                let end = literal_end(&chars, i, c);
+                printer.push(&collect(&chars[i..end]));
+                last = Some(c);
+                i = end;
+                continue;
+            }
+            '/' if next == Some('/') => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map_or(chars.len(), |n| i + n);
+                printer.push(&collect(&chars[i..end]));
+                printer.newline();
+                i = end;
+                continue;
+            }
+            '/' if next == Some('*') => {
+                let end = find(&chars, i + 2, &['*', '/']).map_or(chars.len(), |n| n + 2);
+                printer.push(&collect(&chars[i..end]));
+                i = end;
+                continue;
+            }
+            '/' if starts_regex(last) => {
+                let end = regex_end(&chars, i);
+                printer.push(&collect(&chars[i..end]));
+                last = Some('/');
+                i = end;
+                continue;
+            }
+            c if c.is_whitespace() => printer.space(),
+            '{' => {
+                let close = chars[i + 1..]
+                    .iter()
+                    .position(|c| !c.is_whitespace())
+                    .map(|n| i + 1 + n)
+                    .filter(|&n| chars[n] == '}');
+                if let Some(close) = close {
+                    printer.push("{}");
+                    last = Some('}');
+                    i = close + 1;
+                    continue;
+                }
+                printer.push("{");
+                printer.indent += 1;
+                printer.newline();
+            }
+            '}' => {
+                printer.indent = printer.indent.saturating_sub(1);
+                printer.newline();
+                printer.push("}");
+                if !matches!(next, Some(';' | ',' | ')' | '.')) {
+                    printer.newline();
+                }
+            }
+            ';' => {
+                printer.push(";");
+                if parens == 0 {
+                    printer.newline();
+                }
+            }
+            '(' => {
+                parens += 1;
+                printer.push("(");
+            }
+            ')' => {
+                parens = parens.saturating_sub(1);
+                printer.push(")");
+            }
+            _ => printer.push(c.encode_utf8(&mut [0; 4])),
+        }
+        if !c.is_whitespace() {
+            last = Some(c);
+        }
+        i += 1;
+    }
+
+    printer.newline();
+    printer.out
+}
+
+fn starts_regex(last: Option<char>) -> bool {
+    match last {
+        Some(c) => BEFORE_REGEX.contains(c),
+        None => true,
+    }
+}
+
+fn collect(chars: &[char]) -> String {
+    chars.iter().collect()
+}
+
+/// Index just past the string or template literal opened at `start`.
+fn literal_end(chars: &[char], start: usize, quote: char) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == quote => return i + 1,
+            '\n' if quote != '`' => return i,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+/// Index just past the regex literal opened at `start`, flags included.
+fn regex_end(chars: &[char], start: usize) -> usize {
+    let mut i = start + 1;
+    let mut in_class = false;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                return i;
+            }
+            '\n' => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+fn find(chars: &[char], from: usize, needle: &[char]) -> Option<usize> {
+    chars
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|n| from + n)
+}
@@ -0,0 +1,85 @@
+//! Pushes new versions to a central server (`[publish]`), the other half of
+//! `sync`: many scrapers publish, one hub serves the canonical file.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::config::{redact_url, HttpConfig, PublishConfig, PublishMethod, PublishPayload};
+use crate::fetch;
+use crate::log::{log_info, log_warning};
+use crate::record;
+use crate::store;
+use crate::Result;
+
+/// Publishes after a check saved `key`. A failure is logged and never fails
+/// the check.
+pub async fn after_save(
+    config: &PublishConfig,
+    http: &HttpConfig,
+    surface: &str,
+    key: &str,
+    versions: &HashMap<String, Value>,
+) {
+    let Some(url) = &config.url else {
+        return;
+    };
+    if let Err(e) = publish(config, http, surface, key, versions).await {
+        log_warning(
+            "PUBLISH",
+            &format!("Failed to publish to {}: {}", redact_url(url), e),
+        );
+    }
+}
+
+/// Sends the configured payload for `key` of `surface`'s `versions`, through
+/// the `[http]` proxy and settings.
+pub async fn publish(
+    config: &PublishConfig,
+    http: &HttpConfig,
+    surface: &str,
+    key: &str,
+    versions: &HashMap<String, Value>,
+) -> Result<()> {
+    let url = config
+        .url
+        .as_deref()
+        .ok_or("[publish] url is not set")?
+        .replace("{surface}", surface);
+    let body = match config.payload {
+        PublishPayload::File => store::to_json(versions)?,
+        PublishPayload::Entry => {
+            let entry = versions
+                .get(key)
+                .ok_or_else(|| format!("No version {} to publish", key))?;
+            json!({ "surface": surface, "key": key, "entry": entry }).to_string()
+        }
+    };
+
+    let client = fetch::api_client(http)?;
+    let mut request = match config.method {
+        PublishMethod::Put => client.put(&url),
+        PublishMethod::Post => client.post(&url),
+    }
+    .timeout(Duration::from_secs(config.timeout_secs))
+    .header(reqwest::header::CONTENT_TYPE, "application/json")
+    .body(body);
+    if let Some(token) = &config.token {
+        request = request.bearer_auth(token);
+    }
+    let exchange = record::send(&client, request.build()?)
+        .await
+        .map_err(|e| match e.downcast::<reqwest::Error>() {
+            // The url may carry a token
+            Ok(e) => e.without_url().into(),
+            Err(e) => e,
+        })?;
+    if !exchange.is_success() {
+        return Err(format!("HTTP {} from {}", exchange.status, redact_url(&url)).into());
+    }
+    log_info(
+        "PUBLISH",
+        &format!("Published {} to {}", key, redact_url(&url)),
+    );
+    Ok(())
+}
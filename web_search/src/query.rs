@@ -0,0 +1,345 @@
+//! Filter expressions over version entries, for `query --where`.
+//!
+//! ```text
+//! buildDate >= 2024-01-01 && has(webPlayer)
+//! key > 1.2.80 || !(clientVersion ~ ".g1a2b")
+//! ```
+//!
+//! Fields are entry keys, `key` for the version key, or JSON pointers
+//! (`/regions/0`). Operators are `== != < <= > >=` and `~` (contains).
+//! Dotted numbers compare as versions, other numbers numerically, the rest as
+//! strings, so ISO dates order correctly.
+
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::store;
+use crate::version::WebVersion;
+use crate::Result;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Has(String),
+    Compare(String, Op, String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+pub fn parse(input: &str) -> Result<Expr> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.or()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(expr),
+        Some(token) => Err(format!("Unexpected {:?} in --where", token).into()),
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let (token, width) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Op(Op::Eq), 2),
+            ('!', Some('=')) => (Token::Op(Op::Ne), 2),
+            ('<', Some('=')) => (Token::Op(Op::Le), 2),
+            ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+            ('<', _) => (Token::Op(Op::Lt), 1),
+            ('>', _) => (Token::Op(Op::Gt), 1),
+            ('~', _) => (Token::Op(Op::Contains), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            ('"' | '\'', _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&q| q == c)
+                    .ok_or("Unterminated string in --where")?;
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                (Token::Quoted(text), end + 2)
+            }
+            _ => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| c.is_whitespace() || "&|=!<>~()\"'".contains(*c))
+                    .unwrap_or(chars.len() - i);
+                if end == 0 {
+                    return Err(format!("Unexpected '{}' in --where", c).into());
+                }
+                (Token::Word(chars[i..i + end].iter().collect()), end)
+            }
+        };
+        tokens.push(token);
+        i += width;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("Missing ')' in --where".into()),
+                }
+            }
+            Some(Token::Word(word)) if word == "has" && self.peek() == Some(&Token::Open) => {
+                self.pos += 1;
+                let field = self.value()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(Expr::Has(field)),
+                    _ => Err("Missing ')' after has(field in --where".into()),
+                }
+            }
+            Some(Token::Word(field)) => match self.next() {
+                Some(Token::Op(op)) => Ok(Expr::Compare(field, op, self.value()?)),
+                _ => Err(format!("Expected an operator after '{}' in --where", field).into()),
+            },
+            other => Err(format!("Expected a condition in --where, got {:?}", other).into()),
+        }
+    }
+
+    fn value(&mut self) -> Result<String> {
+        match self.next() {
+            Some(Token::Word(value) | Token::Quoted(value)) => Ok(value),
+            other => Err(format!("Expected a value in --where, got {:?}", other).into()),
+        }
+    }
+}
+
+/// A field of an entry stored under `key`, as text; `key` is the key itself.
+pub fn field(key: &str, entry: &Value, name: &str) -> Option<String> {
+    let value = match name {
+        "key" => return Some(key.to_string()),
+        pointer if pointer.starts_with('/') => entry.pointer(pointer)?,
+        name => entry.get(name)?,
+    };
+    match value {
+        Value::Null => None,
+        Value::String(text) => Some(text.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// The value at a jq-style path (`.webPlayer`, `.regions[0]`), a JSON pointer
+/// (`/regions/0`) or a bare top-level field name.
+pub fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.starts_with('/') || path.is_empty() {
+        return value.pointer(path);
+    }
+    if path == "." {
+        return Some(value);
+    }
+    let pointer: String = path
+        .strip_prefix('.')
+        .unwrap_or(path)
+        .split('.')
+        .flat_map(|part| part.split('['))
+        .map(|part| {
+            let part = part.trim_end_matches(']');
+            format!("/{}", part.replace('~', "~0").replace('/', "~1"))
+        })
+        .collect();
+    value.pointer(&pointer)
+}
+
+/// `value` for a shell: strings without quotes, everything else as JSON.
+pub fn raw(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+impl Expr {
+    pub fn matches(&self, key: &str, entry: &Value) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(key, entry) && b.matches(key, entry),
+            Expr::Or(a, b) => a.matches(key, entry) || b.matches(key, entry),
+            Expr::Not(expr) => !expr.matches(key, entry),
+            Expr::Has(name) => field(key, entry, name).is_some(),
+            Expr::Compare(name, op, expected) => {
+                let Some(actual) = field(key, entry, name) else {
+                    return false;
+                };
+                let ordering = compare(&actual, expected);
+                match op {
+                    Op::Eq => ordering == Ordering::Equal,
+                    Op::Ne => ordering != Ordering::Equal,
+                    Op::Lt => ordering == Ordering::Less,
+                    Op::Le => ordering != Ordering::Greater,
+                    Op::Gt => ordering == Ordering::Greater,
+                    Op::Ge => ordering != Ordering::Less,
+                    Op::Contains => actual.contains(expected.as_str()),
+                }
+            }
+        }
+    }
+}
+
+fn compare(actual: &str, expected: &str) -> Ordering {
+    let dotted =
+        |text: &str| text.matches('.').count() >= 2 && text.split('.').all(|part| !part.is_empty());
+    if dotted(actual) && dotted(expected) {
+        return WebVersion::new(actual).cmp(&WebVersion::new(expected));
+    }
+    if let (Ok(a), Ok(b)) = (actual.parse::<f64>(), expected.parse::<f64>()) {
+        return a.total_cmp(&b);
+    }
+    actual.cmp(expected)
+}
+
+/// Matching entries, newest first, each with `key` and only the `select`ed
+/// fields (all of them when `select` is empty).
+pub fn run(
+    versions: &HashMap<String, Value>,
+    filter: Option<&Expr>,
+    select: &[String],
+) -> Vec<Value> {
+    store::sorted_keys(versions)
+        .into_iter()
+        .filter(|key| filter.is_none_or(|filter| filter.matches(key, &versions[key])))
+        .map(|key| {
+            let entry = &versions[&key];
+            if select.is_empty() {
+                // A hand-edited file may hold something other than an object
+                let Some(fields) = entry.as_object() else {
+                    return json!({ "key": key, "value": entry });
+                };
+                let mut item = Value::Object(fields.clone());
+                item["key"] = json!(key);
+                return item;
+            }
+            let fields: Map<String, Value> = select
+                .iter()
+                .map(|name| {
+                    let value = match name.as_str() {
+                        "key" => json!(key),
+                        pointer if pointer.starts_with('/') => {
+                            entry.pointer(pointer).cloned().unwrap_or(Value::Null)
+                        }
+                        name => entry.get(name).cloned().unwrap_or(Value::Null),
+                    };
+                    (name.clone(), value)
+                })
+                .collect();
+            Value::Object(fields)
+        })
+        .collect()
+}
+
+/// CSV columns for unselected rows: `key`, then every other field by name.
+pub fn columns(rows: &[Value]) -> Vec<String> {
+    let mut names: Vec<String> = rows
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(|fields| fields.keys().cloned())
+        .filter(|name| name != "key")
+        .collect();
+    names.sort();
+    names.dedup();
+    names.insert(0, "key".to_string());
+    names
+}
+
+/// `rows` as CSV with a header of `columns`; nested values are written as JSON
+/// and missing ones left empty.
+pub fn to_csv(rows: &[Value], columns: &[String]) -> String {
+    let cell = |value: &Value| {
+        let text = match value {
+            Value::Null => String::new(),
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        if text.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        } else {
+            text
+        }
+    };
+    let mut csv = columns
+        .iter()
+        .map(|column| cell(&json!(column)))
+        .collect::<Vec<_>>()
+        .join(",");
+    csv.push('\n');
+    for row in rows {
+        let line: Vec<String> = columns
+            .iter()
+            .map(|column| cell(row.get(column).unwrap_or(&Value::Null)))
+            .collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv
+}
@@ -0,0 +1,157 @@
+use reqwest::header::ACCEPT_LANGUAGE;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use crate::integrity::sha256_hex;
+use crate::log::{log_info, log_success};
+use crate::paths;
+use crate::Result;
+
+/// Whether HTTP exchanges go to the network, are also written to a fixtures
+/// directory, or are answered from one.
+#[derive(Debug, Clone)]
+pub enum Mode {
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+static MODE: RwLock<Mode> = RwLock::new(Mode::Live);
+
+/// Sets the mode for every request of the process from now on.
+pub fn set_mode(mode: Mode) {
+    match &mode {
+        Mode::Live => {}
+        Mode::Record(dir) => log_info("RECORD", &format!("Recording HTTP to {}", dir.display())),
+        Mode::Replay(dir) => log_info("REPLAY", &format!("Answering HTTP from {}", dir.display())),
+    }
+    *MODE.write().unwrap() = mode;
+}
+
+fn mode() -> Mode {
+    MODE.read().unwrap().clone()
+}
+
+/// One request and its response, as stored in a fixture file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    /// Request headers that select the response, e.g. Accept-Language for regions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vary: Vec<(String, String)>,
+    pub status: u16,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// Time to the response headers, when it came from the network.
+    #[serde(skip)]
+    pub ttfb: Option<Duration>,
+}
+
+impl Exchange {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The body of a 2xx response, an error otherwise.
+    pub fn text(self) -> Result<String> {
+        if !self.is_success() {
+            return Err(format!("HTTP {} from {}", self.status, self.url).into());
+        }
+        Ok(self.body)
+    }
+}
+
+/// Sends `request`, or answers it from the fixtures directory in replay mode.
+pub async fn send(client: &reqwest::Client, request: reqwest::Request) -> Result<Exchange> {
+    let method = request.method().to_string();
+    let url = request.url().to_string();
+    let vary: Vec<(String, String)> = request
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| vec![(ACCEPT_LANGUAGE.to_string(), value.to_string())])
+        .unwrap_or_default();
+
+    if let Mode::Replay(dir) = mode() {
+        let path = fixture_path(&dir, &method, &url, &vary);
+        let content = fs::read_to_string(paths::long_path(&path)).map_err(|_| {
+            format!(
+                "No recorded response for {} {} in {}",
+                method,
+                url,
+                dir.display()
+            )
+        })?;
+        log_info("REPLAY", &format!("{} {}", method, url));
+        return Ok(serde_json::from_str(&content)?);
+    }
+
+    let started = Instant::now();
+    let response = client.execute(request).await?;
+    let ttfb = started.elapsed();
+    let exchange = Exchange {
+        method,
+        url,
+        vary,
+        status: response.status().as_u16(),
+        version: format!("{:?}", response.version()),
+        headers: response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    value.to_str().unwrap_or("<binary>").to_string(),
+                )
+            })
+            .collect(),
+        body: response.text().await?,
+        ttfb: Some(ttfb),
+    };
+
+    if let Mode::Record(dir) = mode() {
+        let path = fixture_path(&dir, &exchange.method, &exchange.url, &exchange.vary);
+        fs::create_dir_all(paths::long_path(&dir))?;
+        fs::write(
+            paths::long_path(&path),
+            serde_json::to_string_pretty(&exchange)?,
+        )?;
+        log_success(
+            "RECORD",
+            &format!("{} {} -> {}", exchange.method, exchange.url, path.display()),
+        );
+    }
+    Ok(exchange)
+}
+
+/// `<host>-<hash>.json`, the hash covering everything that selects the response.
+fn fixture_path(dir: &Path, method: &str, url: &str, vary: &[(String, String)]) -> PathBuf {
+    let mut key = format!("{} {}", method, url);
+    for (name, value) in vary {
+        key.push_str(&format!("\n{}: {}", name, value));
+    }
+    let host: String = reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dir.join(format!(
+        "{}-{}.json",
+        host,
+        &sha256_hex(key.as_bytes())[..16]
+    ))
+}
@@ -0,0 +1,131 @@
+use reqwest::header::ACCEPT_LANGUAGE;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::config::{Config, HttpConfig, Surface};
+use crate::extract;
+use crate::fetch;
+use crate::log::{log_info, log_success, log_warning};
+use crate::pace;
+use crate::record;
+use crate::version::version_key;
+use crate::Result;
+
+/// clientVersion seen by each region, `None` when the page could not be read.
+pub type RegionVersions = Vec<(String, Option<String>)>;
+
+pub fn accept_language(region: &str) -> String {
+    let tag = match region.to_ascii_lowercase().as_str() {
+        "us" => "en-US",
+        "gb" | "uk" => "en-GB",
+        "de" => "de-DE",
+        "fr" => "fr-FR",
+        "es" => "es-ES",
+        "it" => "it-IT",
+        "jp" => "ja-JP",
+        "kr" => "ko-KR",
+        "br" => "pt-BR",
+        "mx" => "es-MX",
+        "se" => "sv-SE",
+        "in" => "hi-IN",
+        "ru" => "ru-RU",
+        _ => return region.to_string(),
+    };
+    let language = tag.split('-').next().unwrap_or(tag);
+    format!("{},{};q=0.9", tag, language)
+}
+
+pub async fn scan(config: &Config, surface: &Surface<'_>, user_agent: &str) -> RegionVersions {
+    let mut seen = Vec::new();
+
+    for region in &config.regions.markets {
+        log_info("REGION", &format!("Checking {} as {}", surface.url, region));
+        let version = match region_version(config, surface, user_agent, region).await {
+            Ok(Some(version)) => {
+                log_success("REGION", &format!("{}: {}", region, version));
+                Some(version)
+            }
+            Ok(None) => {
+                log_warning("REGION", &format!("{}: no version found", region));
+                None
+            }
+            Err(e) => {
+                log_warning("REGION", &format!("{}: {}", region, e));
+                None
+            }
+        };
+        seen.push((region.clone(), version));
+    }
+
+    seen
+}
+
+async fn region_version(
+    config: &Config,
+    surface: &Surface<'_>,
+    user_agent: &str,
+    region: &str,
+) -> Result<Option<String>> {
+    let client = match config.regions.proxies.get(region) {
+        Some(proxy) => {
+            let http = HttpConfig {
+                proxy: Some(proxy.clone()),
+                ..config.http.clone()
+            };
+            fetch::build_client(user_agent, &http, false)?
+        }
+        None => fetch::build_client(user_agent, &config.http, config.trace_http)?,
+    };
+
+    let permit = pace::wait(&config.http).await;
+    let request = client
+        .get(surface.url)
+        .header(ACCEPT_LANGUAGE, accept_language(region))
+        .build()?;
+    let body = record::send(&client, request).await?.body;
+    drop(permit);
+
+    let parsed = extract::parse_page(&body, surface.extract)?;
+    let Some(base64_str) = parsed.app_server_config.filter(|s| !s.is_empty()) else {
+        return Ok(None);
+    };
+    let json_object = extract::decode_payload(&base64_str, surface.extract.decode)?;
+
+    Ok(extract::field(&json_object, &surface.extract.client_version_field).map(str::to_string))
+}
+
+/// Adds each region to the `regions` array of the entry it saw. Returns true if
+/// anything changed.
+pub fn record(versions: &mut HashMap<String, Value>, seen: &RegionVersions) -> bool {
+    let mut changed = false;
+
+    for (region, client_version) in seen {
+        let Some(client_version) = client_version else {
+            continue;
+        };
+        let Some(entry) = versions.get_mut(&version_key(client_version)) else {
+            continue;
+        };
+        let Some(object) = entry.as_object_mut() else {
+            continue;
+        };
+
+        let regions = object.entry("regions").or_insert_with(|| json!([]));
+        if let Some(list) = regions.as_array_mut() {
+            if !list.iter().any(|r| r == region) {
+                list.push(json!(region));
+                changed = true;
+            }
+        }
+    }
+
+    changed
+}
+
+pub fn to_json(seen: &RegionVersions) -> Value {
+    let map: serde_json::Map<String, Value> = seen
+        .iter()
+        .map(|(region, version)| (region.clone(), json!(version)))
+        .collect();
+    Value::Object(map)
+}
@@ -0,0 +1,256 @@
+//! The result document of a check, as printed on stdout and handed to the
+//! result sinks and the healthcheck.
+//!
+//! Field names are frozen. Changes are additive only: new fields may appear,
+//! existing ones keep their name, type and meaning, and `apiVersion` goes up
+//! only for a change that breaks that promise. Optional fields are left out
+//! rather than set to null, so parsers should treat a missing field as unset.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// `apiVersion` of the documents this build writes. 2: `error` is an object
+/// ([`CheckError`]) instead of a string.
+pub const API_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckResult {
+    #[serde(rename = "apiVersion")]
+    pub api_version: u32,
+    pub success: bool,
+    /// Whether the version was not in the store before; unset when the check failed
+    /// before the store was read.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_new: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub surface: Option<String>,
+    /// Store key of the version, e.g. `1.2.86.316`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// The stored entry (`clientVersion`, `buildDate`, ...) of a new version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<CheckError>,
+    /// The check was cancelled at `deadline_secs`.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub timed_out: bool,
+    /// The site refused us (401/403/429/451 or an off-site redirect).
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub blocked: bool,
+    /// `login_redirect`, `region_interstitial` or `offsite_redirect`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect: Option<String>,
+    /// `"disallowed"` when robots.txt kept the check from fetching the page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub robots: Option<String>,
+    /// Where the page of a failed check was saved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+    /// Why a new version was held back by the `[anomaly]` checks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anomalies: Vec<String>,
+    /// The file a held-back version was quarantined in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quarantined: Option<String>,
+    #[serde(flatten)]
+    pub diagnostics: Diagnostics,
+    /// `phases_ms`, `total_ms` and `bytes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Value>,
+}
+
+/// What a check saw on the way, reported whether or not the version is new.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostics {
+    /// Redirects followed to the page, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<RedirectHop>,
+    /// Size of the web-player bundle, when it was fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_bytes: Option<usize>,
+    /// `status` (`match`, `mismatch`, `not_found`, `error`) and details.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_check: Option<Value>,
+    /// Same as `bundle_check`, against the token endpoint (`[token] enabled`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_check: Option<Value>,
+    /// Version seen per market, `null` where none was found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regions: Option<Value>,
+}
+
+/// Why a check failed, in a form automation can act on without parsing `message`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Whether the same check may succeed if simply run again later.
+    pub retryable: bool,
+    /// Status of the response the error is about, if there was one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// DNS, connect, TLS or a dropped connection.
+    Network,
+    /// A request or the whole check (`deadline_secs`) ran out of time.
+    Timeout,
+    /// The page answered with an unexpected status.
+    HttpStatus,
+    /// 401/403/451 or a redirect to a login or interstitial page.
+    Blocked,
+    /// 429 Too Many Requests.
+    RateLimited,
+    /// robots.txt disallows the page (`[robots] enabled`).
+    RobotsDisallowed,
+    /// The page came back but held no usable version; the page may have changed.
+    Extraction,
+    /// A new version failed the `[anomaly]` checks and was not saved.
+    Anomaly,
+    /// Reading or writing the versions store failed.
+    Store,
+    /// A version source other than a page failed.
+    Source,
+    Internal,
+}
+
+impl ErrorKind {
+    pub fn retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::Network
+                | ErrorKind::Timeout
+                | ErrorKind::RateLimited
+                | ErrorKind::Source
+                | ErrorKind::Store
+        )
+    }
+}
+
+impl CheckError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        CheckError {
+            kind,
+            message: message.into(),
+            retryable: kind.retryable(),
+            http_status: None,
+        }
+    }
+
+    /// An error about a response with `status`; 5xx and 429 are worth retrying.
+    pub fn http(status: u16, message: impl Into<String>) -> Self {
+        let kind = match status {
+            401 | 403 | 451 => ErrorKind::Blocked,
+            429 => ErrorKind::RateLimited,
+            _ => ErrorKind::HttpStatus,
+        };
+        CheckError {
+            retryable: status == 429 || status >= 500,
+            http_status: Some(status),
+            ..CheckError::new(kind, message)
+        }
+    }
+
+    /// Classifies an error that ended a check, by the HTTP client error in its
+    /// source chain if there is one, else as `fallback`.
+    pub fn from_error(error: &(dyn std::error::Error + 'static), fallback: ErrorKind) -> Self {
+        let message = error.to_string();
+        #[cfg(feature = "net")]
+        {
+            let mut source = Some(error);
+            while let Some(cause) = source {
+                if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+                    return match e.status() {
+                        _ if e.is_timeout() => CheckError::new(ErrorKind::Timeout, message),
+                        Some(status) => CheckError::http(status.as_u16(), message),
+                        None => CheckError::new(ErrorKind::Network, message),
+                    };
+                }
+                source = cause.source();
+            }
+        }
+        CheckError::new(fallback, message)
+    }
+}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedirectHop {
+    pub status: u16,
+    pub from: String,
+    pub to: String,
+}
+
+impl Default for CheckResult {
+    fn default() -> Self {
+        CheckResult {
+            api_version: API_VERSION,
+            success: false,
+            is_new: None,
+            surface: None,
+            key: None,
+            data: None,
+            message: None,
+            error: None,
+            timed_out: false,
+            blocked: false,
+            redirect: None,
+            robots: None,
+            snapshot: None,
+            anomalies: Vec::new(),
+            quarantined: None,
+            diagnostics: Diagnostics::default(),
+            timings: None,
+        }
+    }
+}
+
+impl CheckResult {
+    pub fn failure(error: CheckError) -> Self {
+        CheckResult {
+            error: Some(error),
+            ..CheckResult::default()
+        }
+    }
+
+    /// The output for a check that ended in `Err`.
+    pub fn from_error(error: &(dyn std::error::Error + 'static)) -> Self {
+        CheckResult::failure(CheckError::from_error(error, ErrorKind::Internal))
+    }
+
+    /// A successful check of `key`, new or already stored.
+    pub fn found(surface: &str, key: &str, is_new: bool, message: String) -> Self {
+        CheckResult {
+            success: true,
+            is_new: Some(is_new),
+            surface: Some(surface.to_string()),
+            key: Some(key.to_string()),
+            message: Some(message),
+            ..CheckResult::default()
+        }
+    }
+
+    pub fn with_surface(mut self, surface: &str) -> Self {
+        self.surface = Some(surface.to_string());
+        self
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).expect("a check result always serializes")
+    }
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
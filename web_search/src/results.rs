@@ -0,0 +1,67 @@
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use crate::config::{redact_url, HttpConfig, ResultsConfig};
+use crate::fetch;
+use crate::log::{log_info, log_warning};
+use crate::paths;
+use crate::Result;
+
+/// Hands a check result to every configured sink, posting through a client
+/// built from `http`. A failing sink is logged and never fails the check.
+pub async fn deliver(config: &ResultsConfig, http: &HttpConfig, output: &Value) {
+    if let Some(path) = &config.file {
+        match write_file(path, output) {
+            Ok(()) => log_info("RESULTS", &format!("Result written to {}", path.display())),
+            Err(e) => log_warning("RESULTS", &format!("Failed to write result file: {}", e)),
+        }
+    }
+    if let Some(path) = &config.log {
+        if let Err(e) = append_line(path, output) {
+            log_warning(
+                "RESULTS",
+                &format!("Failed to append to results log: {}", e),
+            );
+        }
+    }
+    if let Some(url) = &config.post_url {
+        match post(http, url, output).await {
+            Ok(()) => log_info("RESULTS", &format!("Result posted to {}", redact_url(url))),
+            Err(e) => log_warning("RESULTS", &format!("Failed to post result: {}", e)),
+        }
+    }
+}
+
+fn write_file(path: &Path, output: &Value) -> Result<()> {
+    let mut content = serde_json::to_string_pretty(output)?;
+    content.push('\n');
+    paths::write_atomic(path, content.as_bytes())
+}
+
+/// One line per result, written with a single call so concurrent writers don't interleave.
+pub fn append_line(path: &Path, output: &Value) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(paths::long_path(parent))?;
+    }
+    let mut line = serde_json::to_string(output)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(paths::long_path(path))
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+async fn post(http: &HttpConfig, url: &str, output: &Value) -> Result<()> {
+    fetch::api_client(http)?
+        .post(url)
+        .json(output)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.without_url())?;
+    Ok(())
+}
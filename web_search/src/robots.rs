@@ -0,0 +1,213 @@
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::RobotsConfig;
+use crate::log::{log_info, log_warning};
+use crate::record;
+use crate::Result;
+
+/// robots.txt per origin, with when it was fetched.
+static CACHE: Mutex<Option<HashMap<String, (Instant, Robots)>>> = Mutex::new(None);
+
+/// The rules of one robots.txt (RFC 9309).
+#[derive(Debug, Clone, Default)]
+pub struct Robots {
+    groups: Vec<Group>,
+    /// Set when robots.txt could not be read and everything counts as disallowed.
+    disallow_all: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    agents: Vec<String>,
+    /// `(allow, pattern)` in file order.
+    rules: Vec<(bool, String)>,
+}
+
+impl Robots {
+    pub fn parse(text: &str) -> Robots {
+        let mut groups: Vec<Group> = Vec::new();
+        // A user-agent line after rules starts a new group
+        let mut in_rules = true;
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        groups.push(Group::default());
+                        in_rules = false;
+                    }
+                    if let Some(group) = groups.last_mut() {
+                        group.agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                rule @ ("allow" | "disallow") => {
+                    in_rules = true;
+                    // An empty Disallow allows everything
+                    if let (Some(group), false) = (groups.last_mut(), value.is_empty()) {
+                        group.rules.push((rule == "allow", value.to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Robots {
+            groups,
+            disallow_all: false,
+        }
+    }
+
+    /// Whether `agent` (a User-Agent header) may fetch `path` (with query).
+    pub fn allows(&self, agent: &str, path: &str) -> bool {
+        if self.disallow_all {
+            return false;
+        }
+        let agent = agent.to_ascii_lowercase();
+        let product = agent.split(['/', ' ']).next().unwrap_or_default();
+        let matching: Vec<&Group> = self
+            .groups
+            .iter()
+            .filter(|group| {
+                group
+                    .agents
+                    .iter()
+                    .any(|name| name != "*" && name == product)
+            })
+            .collect();
+        let matching = if matching.is_empty() {
+            self.groups
+                .iter()
+                .filter(|group| group.agents.iter().any(|name| name == "*"))
+                .collect()
+        } else {
+            matching
+        };
+
+        // Longest match wins; on a tie, allow
+        matching
+            .iter()
+            .flat_map(|group| &group.rules)
+            .filter(|(_, pattern)| matches(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+/// Robots pattern match: a prefix match where `*` is any run of characters and
+/// a trailing `$` anchors the end.
+fn matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        // The last part of an anchored pattern has to end the path
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Whether robots.txt of `url`'s site lets `user_agent` fetch it. Always true
+/// unless `[robots] enabled`.
+pub async fn allowed(
+    config: &RobotsConfig,
+    client: &reqwest::Client,
+    url: &str,
+    user_agent: &str,
+) -> Result<bool> {
+    if !config.enabled {
+        return Ok(true);
+    }
+    let url = Url::parse(url)?;
+    let origin = url.origin().ascii_serialization();
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    let ttl = Duration::from_secs(config.cache_secs);
+    let cached = CACHE
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .get(&origin)
+        .filter(|(fetched, _)| fetched.elapsed() < ttl)
+        .map(|(_, robots)| robots.clone());
+    let robots = match cached {
+        Some(robots) => robots,
+        None => {
+            let robots = fetch(client, &origin).await;
+            CACHE
+                .lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(origin.clone(), (Instant::now(), robots.clone()));
+            robots
+        }
+    };
+
+    let allowed = robots.allows(user_agent, &path);
+    if !allowed {
+        log_warning(
+            "ROBOTS",
+            &format!("{}/robots.txt disallows {}", origin, path),
+        );
+    }
+    Ok(allowed)
+}
+
+/// 4xx means no rules; an unreachable robots.txt disallows everything, as RFC 9309 asks.
+async fn fetch(client: &reqwest::Client, origin: &str) -> Robots {
+    let url = format!("{}/robots.txt", origin);
+    log_info("ROBOTS", &format!("Fetching {}", url));
+    let exchange = match async { record::send(client, client.get(&url).build()?).await }.await {
+        Ok(exchange) => exchange,
+        Err(e) => {
+            log_warning(
+                "ROBOTS",
+                &format!("{} unreachable ({}), treating site as disallowed", url, e),
+            );
+            return Robots {
+                disallow_all: true,
+                ..Robots::default()
+            };
+        }
+    };
+    let status = exchange.status;
+    if (400..500).contains(&status) {
+        return Robots::default();
+    }
+    match exchange.text() {
+        Ok(text) => Robots::parse(&text),
+        Err(_) => {
+            log_warning(
+                "ROBOTS",
+                &format!("{} answered {}, treating site as disallowed", url, status),
+            );
+            Robots {
+                disallow_all: true,
+                ..Robots::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! One check of every enabled surface in `[sources]`, run side by side. A
+//! surface that errors or panics only fails its own entry in the summary.
+
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+use crate::check;
+use crate::config::{Config, FailOn, RunnerConfig};
+use crate::log::{log_error, log_info, log_success, log_warning};
+use crate::result::{CheckError, CheckResult, ErrorKind, API_VERSION};
+use crate::Result;
+
+/// Checks every enabled source concurrently and summarizes the results.
+pub async fn run(config: &Config) -> Result<Value> {
+    let names = config.enabled_sources();
+    if names.is_empty() {
+        return Err("run needs at least one enabled [sources.<surface>] table".into());
+    }
+    if let Some(unknown) = names.iter().find(|name| config.surface(name).is_none()) {
+        return Err(format!(
+            "Unknown surface '{}' in [sources], expected one of {}",
+            unknown,
+            config.surface_names().join(", ")
+        )
+        .into());
+    }
+    log_info(
+        "RUN",
+        &format!("Checking {} sources: {}", names.len(), names.join(", ")),
+    );
+
+    let mut checks = tokio::task::JoinSet::new();
+    let mut tasks = HashMap::new();
+    for &surface_name in &names {
+        let config = config.for_source(surface_name);
+        let name = surface_name.to_string();
+        let task = checks.spawn(async move {
+            let surface = config.surface(&name).expect("checked above");
+            let output = check::run_surface(&config, &surface)
+                .await
+                .unwrap_or_else(|e| {
+                    log_error("RUN", &format!("{}: {}", name, e));
+                    CheckResult::from_error(e.as_ref())
+                        .with_surface(&name)
+                        .to_value()
+                });
+            (name, output)
+        });
+        tasks.insert(task.id(), surface_name.to_string());
+    }
+
+    let mut outputs = Vec::new();
+    while let Some(joined) = checks.join_next().await {
+        match joined {
+            Ok(output) => outputs.push(output),
+            Err(e) => {
+                let name = tasks.remove(&e.id()).unwrap_or_default();
+                log_error("RUN", &format!("{}: check panicked: {}", name, e));
+                let output = CheckResult::failure(CheckError::new(
+                    ErrorKind::Internal,
+                    format!("Check panicked: {}", e),
+                ))
+                .with_surface(&name);
+                outputs.push((name, output.to_value()));
+            }
+        }
+    }
+
+    let summary = summarize(&config.runner, outputs);
+    if summary["success"] == true {
+        log_success("RUN", "All sources checked");
+    } else {
+        log_warning("RUN", "Some sources failed");
+    }
+    Ok(summary)
+}
+
+/// The summary document: per-source results under `results`, counts, and
+/// `success` decided by `[runner] fail_on` and `ignore`.
+pub fn summarize(rules: &RunnerConfig, outputs: Vec<(String, Value)>) -> Value {
+    let mut new_versions = 0;
+    let mut failures = Vec::new();
+    let mut results = Map::new();
+    for (name, output) in outputs {
+        if output["success"] == true {
+            if output["is_new"] == true {
+                new_versions += 1;
+            }
+        } else if !ignored(rules, &output) {
+            failures.push(name.clone());
+        }
+        results.insert(name, output);
+    }
+    failures.sort();
+
+    let failed = match rules.fail_on {
+        FailOn::Any => !failures.is_empty(),
+        FailOn::All => !results.is_empty() && failures.len() == results.len(),
+        FailOn::Never => false,
+    };
+    json!({
+        "apiVersion": API_VERSION,
+        "success": !failed,
+        "checks": results.len(),
+        "new_versions": new_versions,
+        "failed": failures,
+        "results": results
+    })
+}
+
+fn ignored(rules: &RunnerConfig, output: &Value) -> bool {
+    let kind = output["error"]["kind"].clone();
+    serde_json::from_value::<ErrorKind>(kind).is_ok_and(|kind| rules.ignore.contains(&kind))
+}
@@ -0,0 +1,155 @@
+use chrono::Local;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backup;
+use crate::config::BackupConfig;
+use crate::log::{log_error, log_info, log_warning};
+use crate::paths;
+use crate::Result;
+
+/// What `recover` managed to put back together.
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Entries parsed out of the damaged file.
+    pub salvaged: usize,
+    /// Keys that only the latest readable backup still had.
+    pub from_backup: Vec<String>,
+    /// Keys whose entries were damaged and that no backup had either.
+    pub lost: Vec<String>,
+    /// Copy of the damaged file, kept for manual inspection.
+    pub corrupt_copy: Option<PathBuf>,
+}
+
+fn is_entry(value: &Value) -> bool {
+    value.get("clientVersion").is_some() || value.get("buildDate").is_some()
+}
+
+/// Scans for `"key": {` and parses each object independently, so one truncated or
+/// mangled entry doesn't take the rest of the file with it. Returns the entries
+/// that parsed and the keys of those that didn't.
+pub fn salvage_entries(content: &str) -> (HashMap<String, Value>, Vec<String>) {
+    let start = Regex::new(r#""((?:[^"\\]|\\.)+)"\s*:\s*\{"#).unwrap();
+    let mut recovered = HashMap::new();
+    let mut broken = Vec::new();
+    let mut cursor = 0;
+
+    for captures in start.captures_iter(content) {
+        let whole = captures.get(0).unwrap();
+        // Matches inside an entry that already parsed are its own fields
+        if whole.start() < cursor {
+            continue;
+        }
+        let key = captures[1].to_string();
+        let object_start = whole.end() - 1;
+
+        let mut stream =
+            serde_json::Deserializer::from_str(&content[object_start..]).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(value)) if is_entry(&value) => {
+                cursor = object_start + stream.byte_offset();
+                recovered.insert(key, value);
+            }
+            _ if key.starts_with(|c: char| c.is_ascii_digit()) => broken.push(key),
+            _ => {}
+        }
+    }
+
+    broken.retain(|key| !recovered.contains_key(key));
+    (recovered, broken)
+}
+
+fn latest_readable_backup(
+    config: &BackupConfig,
+    file: &Path,
+) -> Option<(PathBuf, HashMap<String, Value>)> {
+    let backups = backup::list(&config.dir, file).ok()?;
+    backups.into_iter().find_map(|path| {
+        let content = paths::read_optional(&path).ok()??;
+        let versions = serde_json::from_str(&content).ok()?;
+        Some((path, versions))
+    })
+}
+
+/// Rebuilds the versions map from a file that no longer parses: salvaged entries
+/// first, then anything only the latest readable backup has. The damaged file is
+/// copied aside before the next save overwrites it. Fails if nothing is recoverable,
+/// so an empty map never replaces real history.
+pub fn recover(
+    path: &Path,
+    content: &str,
+    backups: Option<&BackupConfig>,
+) -> Result<(HashMap<String, Value>, Report)> {
+    log_error(
+        "FILE",
+        &format!("{} is corrupt, attempting to salvage it", path.display()),
+    );
+
+    let (mut versions, broken) = salvage_entries(content);
+    let mut report = Report {
+        salvaged: versions.len(),
+        ..Report::default()
+    };
+
+    if let Some((backup_path, backed_up)) = backups.and_then(|c| latest_readable_backup(c, path)) {
+        for (key, entry) in backed_up {
+            if let Entry::Vacant(slot) = versions.entry(key) {
+                report.from_backup.push(slot.key().clone());
+                slot.insert(entry);
+            }
+        }
+        log_info(
+            "FILE",
+            &format!(
+                "Merged {} entries from {}",
+                report.from_backup.len(),
+                backup_path.display()
+            ),
+        );
+    }
+
+    if versions.is_empty() {
+        return Err(format!(
+            "{} is corrupt and nothing could be salvaged from it or its backups",
+            path.display()
+        )
+        .into());
+    }
+
+    report.lost = broken
+        .into_iter()
+        .filter(|key| !versions.contains_key(key))
+        .collect();
+
+    let mut copy_name = path.file_name().unwrap_or_default().to_os_string();
+    copy_name.push(format!(".corrupt-{}", Local::now().format("%Y%m%d-%H%M%S")));
+    let copy = path.with_file_name(copy_name);
+    match fs::write(paths::long_path(&copy), content) {
+        Ok(()) => report.corrupt_copy = Some(copy),
+        Err(e) => log_warning("FILE", &format!("Failed to keep corrupt copy: {}", e)),
+    }
+
+    log_warning(
+        "FILE",
+        &format!(
+            "Salvaged {} entries, {} more from backup, lost {}{}",
+            report.salvaged,
+            report.from_backup.len(),
+            report.lost.len(),
+            if report.lost.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", report.lost.join(", "))
+            }
+        ),
+    );
+    if let Some(copy) = &report.corrupt_copy {
+        log_info("FILE", &format!("Damaged file kept as {}", copy.display()));
+    }
+
+    Ok((versions, report))
+}
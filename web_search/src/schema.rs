@@ -0,0 +1,92 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::log::log_info;
+use crate::paths;
+use crate::stats;
+use crate::Result;
+
+/// Schema of the versions map this build reads and writes.
+pub const CURRENT: u32 = 2;
+
+/// Upgrades a versions map from schema `from` to `from + 1`.
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    pub apply: fn(&mut HashMap<String, Value>),
+}
+
+/// Every format change since the sidecar was introduced, in order.
+pub const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "add buildDateIso",
+    apply: stats::add_build_date_iso,
+}];
+
+/// The schema version lives next to the file (`versions_web.json.schema.json`)
+/// so consumers of the plain key -> entry map are unaffected.
+pub fn sidecar_path(file: &Path) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(".schema.json");
+    file.with_file_name(name)
+}
+
+/// Schema of an existing file; files from before the sidecar are schema 1.
+pub fn read_version(file: &Path) -> Result<u32> {
+    let sidecar = sidecar_path(file);
+    let Some(content) = paths::read_optional(&sidecar)? else {
+        return Ok(1);
+    };
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", sidecar.display(), e))?;
+    value["schemaVersion"]
+        .as_u64()
+        .map(|version| version as u32)
+        .ok_or_else(|| format!("{} has no schemaVersion", sidecar.display()).into())
+}
+
+pub fn write_version(file: &Path) -> Result<()> {
+    let content = serde_json::to_string_pretty(&json!({ "schemaVersion": CURRENT }))?;
+    paths::write_atomic(&sidecar_path(file), content.as_bytes())
+}
+
+/// Applies `migrations` in order to bring `versions` from schema `from` up to `to`.
+/// Returns whether anything ran. Data from a newer schema is refused rather than
+/// rewritten in the old format.
+pub fn migrate_with(
+    versions: &mut HashMap<String, Value>,
+    from: u32,
+    to: u32,
+    migrations: &[Migration],
+) -> Result<bool> {
+    if from > to {
+        return Err(format!(
+            "Versions data has schema {} but this build only understands up to {}; upgrade web_search",
+            from, to
+        )
+        .into());
+    }
+
+    for version in from..to {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.from == version)
+            .ok_or_else(|| format!("No migration from schema {}", version))?;
+        log_info(
+            "SCHEMA",
+            &format!(
+                "Migrating schema {} -> {}: {}",
+                version,
+                version + 1,
+                migration.description
+            ),
+        );
+        (migration.apply)(versions);
+    }
+    Ok(from < to)
+}
+
+pub fn migrate(versions: &mut HashMap<String, Value>, from: u32) -> Result<bool> {
+    migrate_with(versions, from, CURRENT, MIGRATIONS)
+}
@@ -0,0 +1,311 @@
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::check::{self, Observation, Observed};
+use crate::config::{BackupConfig, ChangelogConfig, Config, EventsConfig, ExtractConfig, Surface};
+use crate::events::VersionEvent;
+use crate::store::VersionStore;
+use crate::timing::Timings;
+use crate::Result;
+
+/// Events a subscriber may fall behind by before it starts missing them.
+const EVENT_CAPACITY: usize = 64;
+
+type Callback = Arc<dyn Fn(&VersionEvent) + Send + Sync>;
+
+/// Callbacks and subscribers notified after every check.
+#[derive(Clone)]
+struct Hooks {
+    callbacks: Vec<Callback>,
+    sender: broadcast::Sender<VersionEvent>,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Hooks {
+            callbacks: Vec::new(),
+            sender: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+}
+
+impl fmt::Debug for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Hooks")
+            .field("callbacks", &self.callbacks.len())
+            .field("subscribers", &self.sender.receiver_count())
+            .finish()
+    }
+}
+
+impl Hooks {
+    fn dispatch(&self, event: VersionEvent) {
+        for callback in &self.callbacks {
+            callback(&event);
+        }
+        // Nobody subscribed is fine
+        let _ = self.sender.send(event);
+    }
+}
+
+/// Library entry point: checks one surface with a [`Config`] built in code.
+#[derive(Debug, Clone)]
+pub struct Scraper {
+    config: Config,
+    surface: String,
+    hooks: Hooks,
+}
+
+impl Default for Scraper {
+    fn default() -> Self {
+        Scraper::new()
+    }
+}
+
+impl Scraper {
+    /// The web surface with default settings, minus the files only the CLI wants
+    /// (failure snapshots, changelog, events and backups).
+    pub fn new() -> Self {
+        Scraper::with_config(Config {
+            failure_snapshots: 0,
+            changelog: ChangelogConfig {
+                enabled: false,
+                ..ChangelogConfig::default()
+            },
+            events: EventsConfig {
+                enabled: false,
+                ..EventsConfig::default()
+            },
+            backup: BackupConfig {
+                enabled: false,
+                ..BackupConfig::default()
+            },
+            ..Config::default()
+        })
+    }
+
+    pub fn builder() -> ScraperBuilder {
+        ScraperBuilder::new()
+    }
+
+    /// Uses `config` as is, e.g. one loaded with [`Config::load`].
+    pub fn with_config(config: Config) -> Self {
+        Scraper {
+            config,
+            surface: "web".to_string(),
+            hooks: Hooks::default(),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn surface(&self) -> Result<Surface<'_>> {
+        self.config
+            .surface(&self.surface)
+            .ok_or_else(|| format!("Unknown surface '{}'", self.surface).into())
+    }
+
+    /// Fetches the version currently served, without reading or writing any store.
+    pub async fn latest(&self) -> Result<Observation> {
+        let surface = self.surface()?;
+        match check::observe(&self.config, &surface, &mut Timings::default()).await? {
+            Observed::Version(observation) => Ok(*observation),
+            Observed::Failed(output) => Err(output
+                .error
+                .map_or_else(|| "Check failed".to_string(), |error| error.message)
+                .into()),
+        }
+    }
+
+    /// Receives a [`VersionEvent`] for every check run from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<VersionEvent> {
+        self.hooks.sender.subscribe()
+    }
+
+    /// Runs a full check against the store configured in `[store]`.
+    pub async fn check(&self) -> Result<Value> {
+        let surface = self.surface()?;
+        let result = check::run_surface(&self.config, &surface).await;
+        self.notify(&result);
+        result
+    }
+
+    /// Runs a full check against `store` instead of the configured one.
+    pub async fn check_with_store(&self, store: &dyn VersionStore) -> Result<Value> {
+        let surface = self.surface()?;
+        let result = check::run_with_store(&self.config, &surface, store).await;
+        self.notify(&result);
+        result
+    }
+
+    /// Checks every `interval` until the future is dropped; results only reach
+    /// callbacks and subscribers.
+    pub async fn watch(&self, interval: Duration) {
+        loop {
+            let _ = self.check().await;
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    fn notify(&self, result: &Result<Value>) {
+        self.hooks
+            .dispatch(VersionEvent::from_check(&self.surface, result));
+    }
+}
+
+/// Sets in code what the CLI reads from `web_search.toml`. Starts from the same
+/// defaults as [`Scraper::new`], or from a whole [`Config`] via [`ScraperBuilder::from_config`].
+#[derive(Debug, Clone)]
+pub struct ScraperBuilder {
+    scraper: Scraper,
+    url: Option<String>,
+    extract: Option<ExtractConfig>,
+}
+
+impl Default for ScraperBuilder {
+    fn default() -> Self {
+        ScraperBuilder::new()
+    }
+}
+
+impl ScraperBuilder {
+    pub fn new() -> Self {
+        ScraperBuilder::from_config(Scraper::new().config)
+    }
+
+    pub fn from_config(config: Config) -> Self {
+        ScraperBuilder {
+            scraper: Scraper::with_config(config),
+            url: None,
+            extract: None,
+        }
+    }
+
+    /// `web` (default), `embed`, `youtube-music`, `apple-music` or the name of one of [`Config::surfaces`].
+    pub fn surface(mut self, name: impl Into<String>) -> Self {
+        self.scraper.surface = name.into();
+        self
+    }
+
+    /// Page to scrape for the chosen surface.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.scraper.config.http.timeout_secs = timeout.as_secs_f64();
+        self
+    }
+
+    /// Fixed User-Agent instead of the one fetched from `user_agent_api`.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.scraper.config.http.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// `http://`, `https://` or `socks5://` proxy for every request.
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.scraper.config.http.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Retry transport errors and 429/5xx responses `retries` more times,
+    /// waiting `backoff` before the first retry and doubling it after that.
+    pub fn retry(mut self, retries: u32, backoff: Duration) -> Self {
+        self.scraper.config.http.retries = retries;
+        self.scraper.config.http.retry_backoff_ms = backoff.as_millis() as u64;
+        self
+    }
+
+    /// Selectors, regex and field names for the chosen surface.
+    pub fn extract(mut self, extract: ExtractConfig) -> Self {
+        self.extract = Some(extract);
+        self
+    }
+
+    /// Called with the key and entry of each new version.
+    pub fn on_new_version(self, f: impl Fn(&str, &Value) + Send + Sync + 'static) -> Self {
+        self.on_event(move |event| {
+            if let VersionEvent::NewVersion { key, entry, .. } = event {
+                f(key, entry);
+            }
+        })
+    }
+
+    /// Called with the key when the served version is already known.
+    pub fn on_unchanged(self, f: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.on_event(move |event| {
+            if let VersionEvent::Unchanged { key, .. } = event {
+                f(key);
+            }
+        })
+    }
+
+    /// Called with the error message when a check fails.
+    pub fn on_error(self, f: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.on_event(move |event| {
+            if let VersionEvent::Error { error, .. } = event {
+                f(error);
+            }
+        })
+    }
+
+    /// Called after every check, in registration order and before subscribers.
+    pub fn on_event(mut self, f: impl Fn(&VersionEvent) + Send + Sync + 'static) -> Self {
+        self.scraper.hooks.callbacks.push(Arc::new(f));
+        self
+    }
+
+    /// Gives access to everything else in [`Config`].
+    pub fn configure(mut self, f: impl FnOnce(&mut Config)) -> Self {
+        f(&mut self.scraper.config);
+        self
+    }
+
+    pub fn build(self) -> Result<Scraper> {
+        let ScraperBuilder {
+            mut scraper,
+            url,
+            extract,
+        } = self;
+        let config = &mut scraper.config;
+        let (surface_url, surface_extract) = match scraper.surface.as_str() {
+            "web" => (&mut config.spotify_url, &mut config.extract),
+            "embed" => (&mut config.embed.url, &mut config.embed.extract),
+            "youtube-music" => (
+                &mut config.youtube_music.url,
+                &mut config.youtube_music.extract,
+            ),
+            "apple-music" => (&mut config.apple_music.url, &mut config.apple_music.extract),
+            other => match config.surfaces.iter_mut().find(|s| s.name == other) {
+                Some(surface) => {
+                    surface.fill_defaults();
+                    (&mut surface.url, &mut surface.extract)
+                }
+                None => return Err(format!("Unknown surface '{}'", other).into()),
+            },
+        };
+        if let Some(url) = url {
+            *surface_url = url;
+        }
+        if let Some(extract) = extract {
+            *surface_extract = extract;
+        }
+        if let Some(proxy) = &config.http.proxy {
+            reqwest::Proxy::all(proxy).map_err(|e| format!("Invalid proxy {}: {}", proxy, e))?;
+        }
+        Ok(scraper)
+    }
+
+    /// Same as [`ScraperBuilder::build`], wrapped in a [`crate::blocking::Scraper`].
+    #[cfg(feature = "blocking")]
+    pub fn build_blocking(self) -> Result<crate::blocking::Scraper> {
+        crate::blocking::Scraper::from_async(self.build()?)
+    }
+}
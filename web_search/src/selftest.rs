@@ -0,0 +1,70 @@
+use serde_json::{json, Value};
+
+use crate::config::ExtractConfig;
+use crate::extract;
+use crate::log::{log_error, log_success};
+
+/// Pages built into the binary, each with the key it must yield or the error it must fail with.
+const CASES: [(&str, &str, Result<&str, &str>); 4] = [
+    (
+        "selector",
+        include_str!("../tests/fixtures/selector.html"),
+        Ok("1.2.86.316"),
+    ),
+    (
+        "regex_fallback",
+        include_str!("../tests/fixtures/regex_fallback.html"),
+        Ok("1.2.86.316"),
+    ),
+    (
+        "empty_tag",
+        include_str!("../tests/fixtures/empty_tag.html"),
+        Err("Base64 content is empty"),
+    ),
+    (
+        "blocked",
+        include_str!("../tests/fixtures/blocked.html"),
+        Err("appServerConfig tag not found"),
+    ),
+];
+
+/// Runs the extraction pipeline with the built-in defaults over the embedded
+/// pages, without network or config, so a build can be verified offline.
+pub fn run() -> Value {
+    let config = ExtractConfig::default();
+    let mut failed = 0;
+
+    let cases: Vec<Value> = CASES
+        .iter()
+        .map(|(name, html, expected)| {
+            let got = extract::version_entry(html, &config)
+                .map(|entry| entry.key)
+                .map_err(|e| e.to_string());
+            let ok = match (&got, expected) {
+                (Ok(key), Ok(expected)) => key == expected,
+                (Err(error), Err(expected)) => error == expected,
+                _ => false,
+            };
+            let got = match got {
+                Ok(key) => json!({ "key": key }),
+                Err(error) => json!({ "error": error }),
+            };
+            if ok {
+                log_success("SELFTEST", &format!("{}: {}", name, got));
+            } else {
+                failed += 1;
+                log_error(
+                    "SELFTEST",
+                    &format!("{}: expected {:?}, got {}", name, expected, got),
+                );
+            }
+            json!({ "name": name, "ok": ok, "got": got })
+        })
+        .collect();
+
+    json!({
+        "success": failed == 0,
+        "failed": failed,
+        "cases": cases
+    })
+}
@@ -0,0 +1,559 @@
+use axum::body::Bytes;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use super::auth::{self, Denied, Role};
+use super::responses::{ApiError, Health, Latest, Versions};
+use super::ServerState;
+use crate::config::{Config, ServeConfig};
+use crate::integrity::sha256_hex;
+use crate::log::{log_info, log_warning};
+use crate::store;
+use crate::Result;
+
+type SharedState = Arc<ServerState>;
+
+/// Hex digits of the body's SHA-256 used as its ETag.
+const ETAG_LEN: usize = 32;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "web-versions", description = "Spotify web player versions seen by web_search"),
+    paths(latest, versions, health, admin_scrape, hook_check),
+    components(schemas(Latest, Versions, Health, ApiError)),
+    modifiers(&BearerAuth)
+)]
+struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+        }
+    }
+}
+
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+/// `/health` and the API description stay open for load balancers and client
+/// generators; the rest needs a token once `[serve]` configures some.
+pub fn router(state: SharedState) -> Result<Router> {
+    let cors = cors_layer(&state.config.serve)?;
+    let read = Router::new()
+        .route("/latest", get(latest))
+        .route("/versions", get(versions));
+    let read = with_graphql(read, &state)
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_read));
+    let admin = Router::new()
+        .route("/admin/scrape", post(admin_scrape))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_admin));
+
+    let mut router = Router::new()
+        .route("/health", get(health))
+        .route("/openapi.json", get(|| async { Json(openapi()) }))
+        .merge(read)
+        .merge(admin);
+    // Callers prove themselves with the webhook secret, not a bearer token
+    if state.config.serve.webhook_listener {
+        router = router.route("/hooks/check", post(hook_check));
+    }
+    let mut router = with_swagger_ui(router)
+        .layer(middleware::from_fn_with_state(state.clone(), limit))
+        .layer(RequestBodyLimitLayer::new(
+            state.config.serve.max_body_bytes,
+        ));
+    // Outermost, so preflights skip auth and even 401/429 carry the headers
+    if let Some(cors) = cors {
+        router = router.layer(cors);
+    }
+    Ok(router.with_state(state))
+}
+
+/// `None` while `[serve] cors_origins` is empty.
+fn cors_layer(config: &ServeConfig) -> Result<Option<CorsLayer>> {
+    if config.cors_origins.is_empty() {
+        return Ok(None);
+    }
+
+    let origins = if config.cors_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        let origins = config
+            .cors_origins
+            .iter()
+            .map(|origin| {
+                HeaderValue::from_str(origin.trim_end_matches('/'))
+                    .map_err(|e| format!("Invalid cors_origins entry '{}': {}", origin, e))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        AllowOrigin::list(origins)
+    };
+    let methods = config
+        .cors_methods
+        .iter()
+        .map(|method| {
+            Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                .map_err(|e| format!("Invalid cors_methods entry '{}': {}", method, e))
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers([
+                header::AUTHORIZATION,
+                header::IF_NONE_MATCH,
+                header::IF_MODIFIED_SINCE,
+            ])
+            .expose_headers([header::ETAG, header::LAST_MODIFIED, header::RETRY_AFTER])
+            .max_age(Duration::from_secs(config.cors_max_age_secs)),
+    ))
+}
+
+/// `/graphql` (queries over HTTP) and `/graphql/ws` (the `newVersions`
+/// subscription over WebSocket), behind the same read tokens as `/versions`.
+#[cfg(feature = "graphql")]
+fn with_graphql(read: Router<SharedState>, state: &ServerState) -> Router<SharedState> {
+    use async_graphql_axum::{GraphQL, GraphQLSubscription};
+
+    let schema = crate::graphql::schema(
+        &state.surface,
+        state.store.clone(),
+        state.events.clone(),
+        state.config.bundle.archive_dir.clone(),
+    );
+    read.route_service("/graphql", GraphQL::new(schema.clone()))
+        .route_service("/graphql/ws", GraphQLSubscription::new(schema))
+}
+
+#[cfg(not(feature = "graphql"))]
+fn with_graphql(read: Router<SharedState>, _state: &ServerState) -> Router<SharedState> {
+    read
+}
+
+/// Swagger UI for `/openapi.json` at `/docs`.
+#[cfg(feature = "swagger-ui")]
+fn with_swagger_ui(router: Router<SharedState>) -> Router<SharedState> {
+    use utoipa_swagger_ui::{Config as SwaggerConfig, SwaggerUi};
+
+    router.merge(SwaggerUi::new("/docs").config(SwaggerConfig::from("/openapi.json")))
+}
+
+#[cfg(not(feature = "swagger-ui"))]
+fn with_swagger_ui(router: Router<SharedState>) -> Router<SharedState> {
+    router
+}
+
+/// Serves the HTTP API on `[serve] http_addr` until `shutdown` resolves.
+pub async fn serve(
+    config: &Config,
+    state: SharedState,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let addr = &config.serve.http_addr;
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Failed to listen on {}: {}", addr, e))?;
+    log_info("SERVE", &format!("HTTP listening on {}", addr));
+    let app = router(state)?.into_make_service_with_connect_info::<SocketAddr>();
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
+
+fn error(status: StatusCode, code: &str, message: String) -> Response {
+    let body = ApiError {
+        success: false,
+        code: code.to_string(),
+        error: message,
+    };
+    (status, Json(body)).into_response()
+}
+
+/// Turns away oversized headers and clients over their rate limit.
+async fn limit(
+    State(state): State<SharedState>,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let header_bytes: usize = request
+        .headers()
+        .iter()
+        .map(|(name, value)| name.as_str().len() + value.len())
+        .sum();
+    if header_bytes > state.config.serve.max_header_bytes {
+        return error(
+            StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+            "headers_too_large",
+            format!(
+                "Request headers exceed {} bytes",
+                state.config.serve.max_header_bytes
+            ),
+        );
+    }
+
+    if let Some(limiter) = &state.limiter {
+        if let Err(wait) = limiter.check(client.ip(), Instant::now()) {
+            let retry_after = wait.as_secs() + 1;
+            let mut response = error(
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limited",
+                format!("Too many requests; retry in {} s", retry_after),
+            );
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            return response;
+        }
+    }
+    next.run(request).await
+}
+
+async fn require_read(State(state): State<SharedState>, request: Request, next: Next) -> Response {
+    require(&state, Role::Read, request, next).await
+}
+
+async fn require_admin(State(state): State<SharedState>, request: Request, next: Next) -> Response {
+    require(&state, Role::Admin, request, next).await
+}
+
+async fn require(state: &ServerState, role: Role, request: Request, next: Next) -> Response {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    match auth::authorize(&state.config.serve, authorization, role) {
+        Ok(()) => next.run(request).await,
+        Err(denied) => denied_response(&denied),
+    }
+}
+
+fn denied_response(denied: &Denied) -> Response {
+    match denied {
+        Denied::Unauthorized(..) => {
+            let mut response = error(
+                StatusCode::UNAUTHORIZED,
+                denied.code(),
+                denied.message().to_string(),
+            );
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            response
+        }
+        Denied::Forbidden(..) => error(
+            StatusCode::FORBIDDEN,
+            denied.code(),
+            denied.message().to_string(),
+        ),
+    }
+}
+
+async fn load(
+    state: &ServerState,
+) -> std::result::Result<std::collections::HashMap<String, Value>, Response> {
+    state.store.load().await.map_err(|e| {
+        error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "store_unavailable",
+            format!("Failed to load versions: {}", e),
+        )
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/latest",
+    responses(
+        (status = 200, description = "Newest stored version", body = Latest),
+        (status = 304, description = "Unchanged since If-None-Match/If-Modified-Since"),
+        (status = 401, description = "Missing or unknown token", body = ApiError),
+        (status = 404, description = "Nothing stored yet", body = ApiError),
+        (status = 500, description = "The store couldn't be read", body = ApiError)
+    ),
+    security((), ("bearer" = []))
+)]
+async fn latest(State(state): State<SharedState>, headers: HeaderMap) -> Response {
+    let versions = match load(&state).await {
+        Ok(versions) => versions,
+        Err(response) => return response,
+    };
+    let Some(key) = store::sorted_keys(&versions).into_iter().next() else {
+        return error(
+            StatusCode::NOT_FOUND,
+            "no_versions",
+            "No versions stored yet".to_string(),
+        );
+    };
+    let body = Latest {
+        success: true,
+        surface: state.surface.clone(),
+        data: versions[&key].clone(),
+        key,
+        last_check: state.last_check(),
+    };
+    cached(&state, "/latest", &headers, body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/versions",
+    responses(
+        (status = 200, description = "All stored versions, newest first", body = Versions),
+        (status = 304, description = "Unchanged since If-None-Match/If-Modified-Since"),
+        (status = 401, description = "Missing or unknown token", body = ApiError),
+        (status = 500, description = "The store couldn't be read", body = ApiError)
+    ),
+    security((), ("bearer" = []))
+)]
+async fn versions(State(state): State<SharedState>, headers: HeaderMap) -> Response {
+    let versions = match load(&state).await {
+        Ok(versions) => versions,
+        Err(response) => return response,
+    };
+    let list: Vec<Value> = store::sorted_keys(&versions)
+        .into_iter()
+        .map(|key| {
+            let mut item = versions[&key].clone();
+            item["key"] = json!(key);
+            item
+        })
+        .collect();
+    let body = Versions {
+        success: true,
+        surface: state.surface.clone(),
+        versions: list,
+        last_check: state.last_check(),
+    };
+    cached(&state, "/versions", &headers, body)
+}
+
+/// Answers `body` with validators, or 304 when the client's copy is still
+/// current. The ETag is weak because `lastCheck` isn't part of it: a check that
+/// finds nothing new doesn't invalidate caches. Last-Modified is when this
+/// process first served the current content.
+fn cached(
+    state: &ServerState,
+    path: &'static str,
+    headers: &HeaderMap,
+    body: impl Serialize,
+) -> Response {
+    let mut body = json!(body);
+    let last_check = body
+        .as_object_mut()
+        .and_then(|body| body.remove("lastCheck"));
+    let etag = format!(
+        "W/\"{}\"",
+        &sha256_hex(body.to_string().as_bytes())[..ETAG_LEN]
+    );
+    let modified = {
+        let mut validators = state.validators.lock().unwrap();
+        let entry = validators
+            .entry(path)
+            .or_insert_with(|| (etag.clone(), Utc::now()));
+        if entry.0 != etag {
+            *entry = (etag.clone(), Utc::now());
+        }
+        entry.1
+    };
+
+    // If-None-Match wins over If-Modified-Since (RFC 9110 13.1.3)
+    let fresh = match headers.get(header::IF_NONE_MATCH) {
+        Some(value) => value.to_str().is_ok_and(|value| etag_matches(value, &etag)),
+        None => headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+            .is_some_and(|since| modified.timestamp() <= since.timestamp()),
+    };
+    let mut response = if fresh {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        body["lastCheck"] = last_check.unwrap_or(Value::Null);
+        Json(body).into_response()
+    };
+
+    // Shared caches must not hand token-protected data to anyone who asks
+    let scope = if state.config.serve.read_tokens.is_empty() {
+        "public"
+    } else {
+        "private"
+    };
+    let cache_control = format!(
+        "{}, max-age={}",
+        scope, state.config.serve.cache_max_age_secs
+    );
+    let response_headers = response.headers_mut();
+    for (name, value) in [
+        (header::ETAG, etag),
+        (header::LAST_MODIFIED, http_date(modified)),
+        (header::CACHE_CONTROL, cache_control),
+    ] {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response_headers.insert(name, value);
+        }
+    }
+    response
+}
+
+/// `If-None-Match` uses weak comparison, so `W/` prefixes don't matter.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    if_none_match.trim() == "*"
+        || if_none_match
+            .split(',')
+            .any(|candidate| opaque(candidate) == opaque(etag))
+}
+
+fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+#[utoipa::path(
+    get,
+    path = "/health",
+    responses(
+        (status = 200, description = "Checks succeed and the data is fresh", body = Health),
+        (status = 503, description = "The last check failed or the data is stale", body = Health)
+    )
+)]
+async fn health(State(state): State<SharedState>) -> Response {
+    let (healthy, body) = state.health();
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, [(header::CACHE_CONTROL, "no-store")], Json(body)).into_response()
+}
+
+/// Runs a check right away instead of waiting for the schedule. Admin only.
+#[utoipa::path(
+    post,
+    path = "/admin/scrape",
+    responses(
+        (status = 200, description = "The check's result, as printed by `web_search check`", body = Object),
+        (status = 401, description = "Missing or unknown token", body = ApiError),
+        (status = 403, description = "Not an admin token, or no admin tokens configured", body = ApiError),
+        (status = 409, description = "This server doesn't run checks", body = ApiError),
+        (status = 502, description = "The page couldn't be read; same body as 200", body = Object)
+    ),
+    security(("bearer" = []))
+)]
+async fn admin_scrape(State(state): State<SharedState>) -> Response {
+    if !state.scraping {
+        return error(
+            StatusCode::CONFLICT,
+            "scrape_disabled",
+            "Checks are disabled in this server ([serve] scrape = false)".to_string(),
+        );
+    }
+
+    log_info("SERVE", "Check requested via /admin/scrape");
+    match state.check().await {
+        Ok(output) => {
+            let status = if output["success"] == true {
+                StatusCode::OK
+            } else {
+                StatusCode::BAD_GATEWAY
+            };
+            (status, Json(output)).into_response()
+        }
+        Err(e) => error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "check_failed",
+            e.to_string(),
+        ),
+    }
+}
+
+/// Asks for a check right away, e.g. from a CI job or an IFTTT applet. Answers
+/// at once; the check runs in the background, and a request arriving while
+/// one runs is folded into it. Only routed with `serve --webhook-listener`.
+#[utoipa::path(
+    post,
+    path = "/hooks/check",
+    params(
+        ("X-Webhook-Secret" = Option<String>, Header, description = "[serve] webhook_secret"),
+        ("X-Hub-Signature-256" = Option<String>, Header, description = "sha256=<HMAC-SHA256 of the body with webhook_secret>")
+    ),
+    responses(
+        (status = 202, description = "Check started, or one is already running", body = Object),
+        (status = 401, description = "Missing or wrong secret or signature", body = ApiError),
+        (status = 409, description = "This server doesn't run checks", body = ApiError)
+    )
+)]
+async fn hook_check(State(state): State<SharedState>, headers: HeaderMap, body: Bytes) -> Response {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    if !auth::webhook_authorized(
+        &state.config.serve,
+        header("x-webhook-secret"),
+        header("x-hub-signature-256"),
+        &body,
+    ) {
+        log_warning("SERVE", "Rejected /hooks/check without a valid secret");
+        return error(
+            StatusCode::UNAUTHORIZED,
+            "invalid_webhook_secret",
+            "Send X-Webhook-Secret or X-Hub-Signature-256".to_string(),
+        );
+    }
+    if !state.scraping {
+        return error(
+            StatusCode::CONFLICT,
+            "scrape_disabled",
+            "Checks are disabled in this server ([serve] scrape = false)".to_string(),
+        );
+    }
+
+    // The guard goes to the spawned check, so nothing can start in between
+    let started = match state.checking.clone().try_lock_owned() {
+        Ok(checking) => {
+            log_info("SERVE", "Check requested via /hooks/check");
+            let state = state.clone();
+            tokio::spawn(async move {
+                let _checking = checking;
+                // Failures are already logged and recorded for /health
+                let _ = state.check_locked().await;
+            });
+            true
+        }
+        Err(_) => {
+            log_info(
+                "SERVE",
+                "Check requested via /hooks/check, one is already running",
+            );
+            false
+        }
+    };
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({ "success": true, "started": started })),
+    )
+        .into_response()
+}
@@ -0,0 +1,152 @@
+use sha2::{Digest, Sha256};
+
+use crate::config::ServeConfig;
+
+/// What a token may do. Admin tokens can also read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Read,
+    Admin,
+}
+
+/// Why a request was turned away, with a stable code for the error body.
+#[derive(Debug)]
+pub enum Denied {
+    /// No token, or one that isn't configured (401).
+    Unauthorized(&'static str, String),
+    /// A known token without the needed role, or admin endpoints switched off (403).
+    Forbidden(&'static str, String),
+}
+
+impl Denied {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Denied::Unauthorized(code, _) | Denied::Forbidden(code, _) => code,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Denied::Unauthorized(_, message) | Denied::Forbidden(_, message) => message,
+        }
+    }
+}
+
+#[cfg(feature = "grpc")]
+impl From<Denied> for tonic::Status {
+    fn from(denied: Denied) -> Self {
+        match denied {
+            Denied::Unauthorized(_, message) => tonic::Status::unauthenticated(message),
+            Denied::Forbidden(_, message) => tonic::Status::permission_denied(message),
+        }
+    }
+}
+
+/// Checks an `Authorization` header value against `[serve] read_tokens` and
+/// `admin_tokens`. Reads are open while no read token is configured; admin
+/// endpoints are closed while no admin token is.
+pub fn authorize(
+    config: &ServeConfig,
+    authorization: Option<&str>,
+    needed: Role,
+) -> Result<(), Denied> {
+    if needed == Role::Read && config.read_tokens.is_empty() {
+        return Ok(());
+    }
+    if needed == Role::Admin && config.admin_tokens.is_empty() {
+        return Err(Denied::Forbidden(
+            "admin_disabled",
+            "Admin endpoints are disabled; set [serve] admin_tokens".to_string(),
+        ));
+    }
+
+    let Some(token) = authorization.and_then(bearer) else {
+        return Err(Denied::Unauthorized(
+            "missing_token",
+            "Send `Authorization: Bearer <token>`".to_string(),
+        ));
+    };
+    match role_of(config, token) {
+        Some(role) if role >= needed => Ok(()),
+        Some(_) => Err(Denied::Forbidden(
+            "admin_required",
+            "This endpoint needs an admin token".to_string(),
+        )),
+        None => Err(Denied::Unauthorized(
+            "invalid_token",
+            "Unknown token".to_string(),
+        )),
+    }
+}
+
+fn bearer(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+fn role_of(config: &ServeConfig, token: &str) -> Option<Role> {
+    let matches = |tokens: &[String]| {
+        tokens
+            .iter()
+            .any(|known| constant_time_eq(known.as_bytes(), token.as_bytes()))
+    };
+    if matches(&config.admin_tokens) {
+        Some(Role::Admin)
+    } else if matches(&config.read_tokens) {
+        Some(Role::Read)
+    } else {
+        None
+    }
+}
+
+/// Checks a `/hooks/check` request against `[serve] webhook_secret`: the
+/// secret itself in `X-Webhook-Secret`, or a GitHub-style
+/// `X-Hub-Signature-256: sha256=<hex HMAC-SHA256 of the body>`. Open while no
+/// secret is set.
+pub fn webhook_authorized(
+    config: &ServeConfig,
+    secret: Option<&str>,
+    signature: Option<&str>,
+    body: &[u8],
+) -> bool {
+    let Some(expected) = config.webhook_secret.as_deref() else {
+        return true;
+    };
+    if let Some(secret) = secret {
+        return constant_time_eq(secret.trim().as_bytes(), expected.as_bytes());
+    }
+    match signature.and_then(|signature| signature.trim().strip_prefix("sha256=")) {
+        Some(hex) => constant_time_eq(
+            hex.to_ascii_lowercase().as_bytes(),
+            hmac_sha256_hex(expected.as_bytes(), body).as_bytes(),
+        ),
+        None => false,
+    }
+}
+
+/// HMAC-SHA256 (RFC 2104) of `message`, as lowercase hex.
+pub fn hmac_sha256_hex(key: &[u8], message: &[u8]) -> String {
+    const BLOCK: usize = 64;
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let pad = |byte: u8| block.iter().map(|k| k ^ byte).collect::<Vec<u8>>();
+    let inner = Sha256::new()
+        .chain_update(pad(0x36))
+        .chain_update(message)
+        .finalize();
+    let outer = Sha256::new()
+        .chain_update(pad(0x5c))
+        .chain_update(inner)
+        .finalize();
+    format!("{:x}", outer)
+}
+
+/// Compares without bailing out at the first differing byte, so response
+/// timing doesn't leak how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,336 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+
+use crate::check;
+use crate::config::{Config, Surface};
+use crate::cron::CronSchedule;
+use crate::events::VersionEvent;
+use crate::healthcheck;
+use crate::lock::{lock_path, InstanceLock};
+use crate::log::{log_error, log_info, log_success, log_warning};
+use crate::result::CheckResult;
+use crate::results;
+use crate::shutdown;
+use crate::store::{self, VersionStore};
+use crate::version::compare_versions;
+use crate::watch::next_delay;
+use crate::Result;
+use limit::RateLimiter;
+use responses::Health;
+
+mod api;
+pub mod auth;
+pub mod limit;
+pub mod responses;
+
+/// Events a slow watcher may fall behind by before it starts missing them.
+const EVENT_CAPACITY: usize = 64;
+/// Missed checks after which /health reports the data as stale.
+const STALE_AFTER_INTERVALS: u32 = 3;
+
+#[derive(Debug, Default)]
+struct ScrapeStatus {
+    checks: u64,
+    last_check: Option<DateTime<Utc>>,
+    last_success: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+/// What the HTTP and gRPC handlers share.
+pub struct ServerState {
+    config: Arc<Config>,
+    surface: String,
+    store: Arc<dyn VersionStore>,
+    events: broadcast::Sender<VersionEvent>,
+    /// Checks run in this process; otherwise the store is only read.
+    scraping: bool,
+    interval: Duration,
+    started: DateTime<Utc>,
+    status: RwLock<ScrapeStatus>,
+    /// Keys already published, `None` until the store has been read once.
+    known: Mutex<Option<HashSet<String>>>,
+    /// Per path: the ETag last served and when it changed, for Last-Modified.
+    validators: Mutex<HashMap<&'static str, (String, DateTime<Utc>)>>,
+    /// Per-IP request budget, `None` when rate limiting is off.
+    limiter: Option<RateLimiter>,
+    /// Held for the duration of a check, so scheduled and forced checks don't
+    /// overlap. Shared so a spawned check can own the guard.
+    checking: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl ServerState {
+    /// Checks the surface now, records the outcome and reports it to the
+    /// healthcheck and the result sinks.
+    async fn check(&self) -> Result<Value> {
+        let _checking = self.checking.lock().await;
+        self.check_locked().await
+    }
+
+    /// [`check`](Self::check) for a caller already holding `checking`.
+    async fn check_locked(&self) -> Result<Value> {
+        let surface = self
+            .config
+            .surface(&self.surface)
+            .ok_or_else(|| format!("Unknown surface '{}'", self.surface))?;
+        let result = check::run_with_store(&self.config, &surface, self.store.as_ref()).await;
+        let output = match &result {
+            Ok(output) => output.clone(),
+            Err(e) => {
+                log_error("SERVE", &format!("Check failed: {}", e));
+                CheckResult::from_error(e.as_ref()).to_value()
+            }
+        };
+        self.record_check(&result);
+        healthcheck::report(&self.config.healthcheck, &output).await;
+        results::deliver(&self.config.results, &self.config.http, &output).await;
+        result
+    }
+
+    /// Records a finished check and publishes its event.
+    fn record_check(&self, result: &Result<Value>) {
+        let event = VersionEvent::from_check(&self.surface, result);
+        let now = Utc::now();
+        {
+            let mut status = self.status.write().unwrap();
+            status.checks += 1;
+            status.last_check = Some(now);
+            match &event {
+                VersionEvent::Error { error, .. } => status.last_error = Some(error.clone()),
+                _ => {
+                    status.last_success = Some(now);
+                    status.last_error = None;
+                }
+            }
+        }
+        if let VersionEvent::NewVersion { key, .. } = &event {
+            if let Some(known) = self.known.lock().unwrap().as_mut() {
+                known.insert(key.clone());
+            }
+        }
+        // Nobody watching is fine
+        let _ = self.events.send(event);
+    }
+
+    /// `/health` body and whether it counts as healthy.
+    fn health(&self) -> (bool, Health) {
+        let status = self.status.read().unwrap();
+        let now = Utc::now();
+        let stale_after = self.interval * STALE_AFTER_INTERVALS;
+        let stale = self.scraping
+            && (now - status.last_success.unwrap_or(self.started))
+                .to_std()
+                .is_ok_and(|age| age > stale_after);
+        let state = if status.last_error.is_some() {
+            "failing"
+        } else if stale {
+            "stale"
+        } else {
+            "ok"
+        };
+
+        (
+            state == "ok",
+            Health {
+                status: state.to_string(),
+                surface: self.surface.clone(),
+                scraping: self.scraping,
+                checks: status.checks,
+                last_check: status.last_check.map(timestamp),
+                last_success: status.last_success.map(timestamp),
+                last_error: status.last_error.clone(),
+                uptime_secs: (now - self.started).num_seconds(),
+            },
+        )
+    }
+
+    fn last_check(&self) -> Option<String> {
+        self.status.read().unwrap().last_check.map(timestamp)
+    }
+}
+
+/// The OpenAPI 3 document served at `/openapi.json`.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    api::openapi()
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Serves the surface's store over HTTP (and gRPC with `grpc`) until
+/// Ctrl-C/SIGTERM, checking for new versions on the `[watch]` schedule unless
+/// `[serve] scrape` is off.
+pub async fn run(config: &Config, surface: &Surface<'_>, grpc: bool) -> Result<()> {
+    let cron: Option<CronSchedule> = config.watch.cron.as_deref().map(str::parse).transpose()?;
+    if config.serve.webhook_listener && config.serve.webhook_secret.is_none() {
+        log_warning(
+            "SERVE",
+            "/hooks/check is open to anyone who can reach it; set [serve] webhook_secret",
+        );
+    }
+    // Same single-writer guarantee as `watch`
+    let _lock = if config.serve.scrape {
+        Some(InstanceLock::acquire(
+            &lock_path(surface.versions_file),
+            false,
+        )?)
+    } else {
+        None
+    };
+
+    let state = Arc::new(ServerState {
+        config: Arc::new(config.clone()),
+        surface: surface.name.to_string(),
+        store: Arc::from(store::open(config, surface).await?),
+        events: broadcast::channel(EVENT_CAPACITY).0,
+        scraping: config.serve.scrape,
+        interval: Duration::from_secs(config.watch.interval_secs),
+        started: Utc::now(),
+        status: RwLock::new(ScrapeStatus::default()),
+        known: Mutex::new(None),
+        validators: Mutex::new(HashMap::new()),
+        limiter: RateLimiter::new(
+            config.serve.rate_limit_per_min,
+            config.serve.rate_limit_burst,
+        ),
+        checking: Arc::new(tokio::sync::Mutex::new(())),
+    });
+
+    let (stop, stopped) = watch::channel(false);
+    tokio::spawn(async move {
+        shutdown::signal().await;
+        let _ = stop.send(true);
+    });
+
+    let poll = tokio::spawn(poll_store(
+        state.clone(),
+        Duration::from_secs(config.serve.poll_secs),
+    ));
+    let scrape = state
+        .scraping
+        .then(|| tokio::spawn(scrape_loop(state.clone(), cron, stopped.clone())));
+
+    let http = api::serve(config, state.clone(), wait(stopped.clone()));
+    let result = if grpc {
+        tokio::try_join!(http, serve_grpc(config, state.clone(), wait(stopped))).map(|_| ())
+    } else {
+        http.await
+    };
+
+    poll.abort();
+    // Let a running check finish its store writes: the loop stops between
+    // checks, and holding `checking` keeps webhook checks from starting
+    if let Some(scrape) = scrape {
+        let _ = scrape.await;
+    }
+    let _checking = state.checking.lock().await;
+    log_success("SERVE", "Stopped");
+    result
+}
+
+async fn wait(mut stopped: watch::Receiver<bool>) {
+    let _ = stopped.wait_for(|stopped| *stopped).await;
+}
+
+#[cfg(feature = "grpc")]
+async fn serve_grpc(
+    config: &Config,
+    state: Arc<ServerState>,
+    shutdown: impl std::future::Future<Output = ()>,
+) -> Result<()> {
+    use crate::grpc;
+
+    let addr = config
+        .serve
+        .grpc_addr
+        .parse()
+        .map_err(|e| format!("Invalid grpc_addr '{}': {}", config.serve.grpc_addr, e))?;
+    log_info("SERVE", &format!("gRPC listening on {}", addr));
+    let service =
+        grpc::VersionsService::new(&state.surface, state.store.clone(), state.events.clone());
+    // Every RPC reads, so read tokens (or admin tokens) apply
+    let serve_config = state.config.serve.clone();
+    // tonic fixes the interceptor's signature, Status and all
+    #[allow(clippy::result_large_err)]
+    let interceptor = move |request: tonic::Request<()>| -> std::result::Result<_, tonic::Status> {
+        let authorization = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+        auth::authorize(&serve_config, authorization, auth::Role::Read)?;
+        Ok(request)
+    };
+    grpc::serve(addr, service, interceptor, shutdown).await
+}
+
+#[cfg(not(feature = "grpc"))]
+async fn serve_grpc(
+    _config: &Config,
+    _state: Arc<ServerState>,
+    _shutdown: impl std::future::Future<Output = ()>,
+) -> Result<()> {
+    Err("serve --grpc needs a build with `--features grpc`".into())
+}
+
+/// Checks the surface on the `[watch]` schedule, like `watch` does.
+async fn scrape_loop(
+    state: Arc<ServerState>,
+    cron: Option<CronSchedule>,
+    stopped: watch::Receiver<bool>,
+) {
+    let config = state.config.clone();
+    let Some(surface) = config.surface(&state.surface) else {
+        return;
+    };
+    while !*stopped.borrow() {
+        // Failures are already logged and recorded for /health
+        let _ = state.check().await;
+
+        let next = next_delay(&config, &surface, cron.as_ref(), state.interval).await;
+        log_info("SERVE", &format!("Next check in {} s", next.as_secs()));
+        tokio::select! {
+            _ = tokio::time::sleep(next) => {}
+            _ = wait(stopped.clone()) => break,
+        }
+    }
+}
+
+/// Re-reads the store every `interval` and publishes keys that weren't there
+/// before, so watchers also hear about versions saved by other processes.
+async fn poll_store(state: Arc<ServerState>, interval: Duration) {
+    loop {
+        match state.store.load().await {
+            Ok(versions) => {
+                let mut added: Vec<&String> = {
+                    let mut known = state.known.lock().unwrap();
+                    match known.as_mut() {
+                        Some(known) => versions
+                            .keys()
+                            .filter(|key| known.insert((*key).clone()))
+                            .collect(),
+                        None => {
+                            *known = Some(versions.keys().cloned().collect());
+                            Vec::new()
+                        }
+                    }
+                };
+                // Oldest first, so watchers see them in release order
+                added.sort_by(|a, b| compare_versions(b, a));
+                for key in added {
+                    log_info("SERVE", &format!("Publishing new version {}", key));
+                    let _ = state.events.send(VersionEvent::NewVersion {
+                        surface: state.surface.clone(),
+                        key: key.clone(),
+                        entry: versions[key].clone(),
+                    });
+                }
+            }
+            Err(e) => log_warning("SERVE", &format!("Failed to load versions: {}", e)),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
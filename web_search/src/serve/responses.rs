@@ -0,0 +1,57 @@
+use serde::Serialize;
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// `GET /latest`: the newest stored version.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Latest {
+    pub success: bool,
+    pub surface: String,
+    #[schema(example = "1.2.86.316")]
+    pub key: String,
+    /// The stored entry: clientVersion, buildDate and, when known, buildVersion and webPlayer.
+    #[schema(value_type = Object)]
+    pub data: Value,
+    /// When this server last checked for a new version (RFC 3339).
+    pub last_check: Option<String>,
+}
+
+/// `GET /versions`: every stored version, newest first.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Versions {
+    pub success: bool,
+    pub surface: String,
+    /// Stored entries with their `key` added.
+    #[schema(value_type = Vec<Object>)]
+    pub versions: Vec<Value>,
+    pub last_check: Option<String>,
+}
+
+/// `GET /health`.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Health {
+    /// `ok`, `failing` (the last check failed) or `stale` (no successful check for a while).
+    #[schema(example = "ok")]
+    pub status: String,
+    pub surface: String,
+    /// Whether this server runs checks itself.
+    pub scraping: bool,
+    pub checks: u64,
+    pub last_check: Option<String>,
+    pub last_success: Option<String>,
+    pub last_error: Option<String>,
+    pub uptime_secs: i64,
+}
+
+/// Every non-2xx answer.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiError {
+    pub success: bool,
+    /// Stable, machine-readable reason such as `missing_token` or `no_versions`.
+    #[schema(example = "missing_token")]
+    pub code: String,
+    pub error: String,
+}
@@ -0,0 +1,27 @@
+use crate::log::log_info;
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix.
+pub async fn signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => log_info("SIGNAL", "Received Ctrl-C"),
+        _ = terminate => log_info("SIGNAL", "Received SIGTERM"),
+    }
+}
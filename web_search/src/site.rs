@@ -0,0 +1,171 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config::SiteConfig;
+use crate::log::{log_info, log_success};
+use crate::stats;
+use crate::version::compare_versions;
+use crate::Result;
+
+const SORT_SCRIPT: &str = r#"<script>
+document.querySelectorAll("th[data-col]").forEach(function (th) {
+  th.addEventListener("click", function () {
+    var table = th.closest("table");
+    var col = Number(th.dataset.col);
+    var asc = th.dataset.dir !== "asc";
+    th.dataset.dir = asc ? "asc" : "desc";
+    var key = function (row) {
+      return row.children[col].dataset.sort || row.children[col].textContent;
+    };
+    var rows = Array.from(table.tBodies[0].rows);
+    rows.sort(function (a, b) {
+      var x = key(a), y = key(b);
+      var r = x.localeCompare(y, undefined, { numeric: true });
+      return asc ? r : -r;
+    });
+    rows.forEach(function (row) { table.tBodies[0].appendChild(row); });
+  });
+});
+</script>"#;
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:960px;margin:2em auto;padding:0 1em}\
+table{border-collapse:collapse;width:100%}th,td{padding:4px 8px;border-bottom:1px solid #ddd;text-align:left}\
+th[data-col]{cursor:pointer}code{font-size:90%}";
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn field<'a>(entry: &'a Value, name: &str) -> &'a str {
+    entry.get(name).and_then(|v| v.as_str()).unwrap_or("")
+}
+
+fn page(title: &str, head: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n{}</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        head,
+        body
+    )
+}
+
+fn index_page(config: &SiteConfig, keys: &[&String], versions: &HashMap<String, Value>) -> String {
+    let mut rows = String::new();
+    for key in keys {
+        let entry = &versions[*key];
+        rows.push_str(&format!(
+            "<tr><td data-sort=\"{key}\"><a href=\"versions/{key}.html\">{key}</a></td><td><code>{}</code></td><td>{}</td><td><code>{}</code></td></tr>\n",
+            escape(field(entry, "clientVersion")),
+            escape(field(entry, "buildDate")),
+            escape(field(entry, "buildVersion")),
+            key = escape(key),
+        ));
+    }
+
+    let body = format!(
+        "<h1>{}</h1>\n<p>{} versions &middot; <a href=\"feed.xml\">RSS</a></p>\n<table>\n<thead><tr><th data-col=\"0\">Version</th><th data-col=\"1\">clientVersion</th><th data-col=\"2\">buildDate</th><th data-col=\"3\">buildVersion</th></tr></thead>\n<tbody>\n{}</tbody>\n</table>\n{}\n",
+        escape(&config.title),
+        keys.len(),
+        rows,
+        SORT_SCRIPT
+    );
+    let head =
+        "<link rel=\"alternate\" type=\"application/rss+xml\" title=\"RSS\" href=\"feed.xml\">\n";
+    page(&config.title, head, &body)
+}
+
+fn version_page(config: &SiteConfig, key: &str, entry: &Value) -> String {
+    let mut rows = String::new();
+    if let Some(object) = entry.as_object() {
+        for (name, value) in object {
+            let text = match value {
+                Value::String(s) if s.starts_with("http") => {
+                    format!("<a href=\"{0}\">{0}</a>", escape(s))
+                }
+                Value::String(s) => escape(s),
+                other => format!("<code>{}</code>", escape(&other.to_string())),
+            };
+            rows.push_str(&format!(
+                "<tr><th>{}</th><td>{}</td></tr>\n",
+                escape(name),
+                text
+            ));
+        }
+    }
+
+    let body = format!(
+        "<p><a href=\"../index.html\">&larr; {}</a></p>\n<h1>{}</h1>\n<table>\n{}</table>\n",
+        escape(&config.title),
+        escape(key),
+        rows
+    );
+    page(&format!("{} {}", config.title, key), "", &body)
+}
+
+fn rss_date(build_date: &str) -> Option<String> {
+    stats::parse_build_date(build_date).map(|time| time.to_rfc2822())
+}
+
+fn feed(config: &SiteConfig, keys: &[&String], versions: &HashMap<String, Value>) -> String {
+    let base_url = config.base_url.trim_end_matches('/');
+    let mut items = String::new();
+    for key in keys.iter().take(config.feed_items) {
+        let entry = &versions[*key];
+        let link = format!("{}/versions/{}.html", base_url, key);
+        items.push_str(&format!(
+            "<item><title>{}</title><link>{}</link><guid>{}</guid>",
+            escape(field(entry, "clientVersion")),
+            escape(&link),
+            escape(&link)
+        ));
+        if let Some(date) = rss_date(field(entry, "buildDate")) {
+            items.push_str(&format!("<pubDate>{}</pubDate>", date));
+        }
+        items.push_str("</item>\n");
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\">\n<channel>\n<title>{}</title>\n<link>{}/</link>\n<description>{}</description>\n{}</channel>\n</rss>\n",
+        escape(&config.title),
+        escape(base_url),
+        escape(&config.title),
+        items
+    )
+}
+
+pub fn build(config: &SiteConfig, versions: &HashMap<String, Value>, out_dir: &Path) -> Result<()> {
+    let mut keys: Vec<&String> = versions.keys().collect();
+    keys.sort_by(|a, b| compare_versions(a, b));
+
+    log_info("SITE", &format!("Building site in {}", out_dir.display()));
+    fs::create_dir_all(out_dir.join("versions"))?;
+
+    fs::write(
+        out_dir.join("index.html"),
+        index_page(config, &keys, versions),
+    )?;
+    for key in &keys {
+        fs::write(
+            out_dir.join("versions").join(format!("{}.html", key)),
+            version_page(config, key, &versions[*key]),
+        )?;
+    }
+    fs::write(out_dir.join("feed.xml"), feed(config, &keys, versions))?;
+
+    log_success(
+        "SITE",
+        &format!(
+            "Wrote {} version pages to {}",
+            keys.len(),
+            out_dir.display()
+        ),
+    );
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use chrono::Local;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::fetch::FetchedPage;
+use crate::log::log_info;
+use crate::paths;
+use crate::Result;
+
+pub fn save_failure(dir: &Path, page: &FetchedPage, keep: usize) -> Result<PathBuf> {
+    fs::create_dir_all(paths::long_path(dir))?;
+
+    let path = dir.join(format!("{}.html", Local::now().format("%Y%m%d-%H%M%S-%3f")));
+
+    let mut content = String::from("<!--\n");
+    for redirect in &page.redirects {
+        content.push_str(&format!(
+            "redirect: {} {} -> {}\n",
+            redirect.status, redirect.from, redirect.to
+        ));
+    }
+    content.push_str(&format!("url: {}\nstatus: {}\n", page.url, page.status));
+    for (name, value) in &page.headers {
+        content.push_str(&format!("{}: {}\n", name, value));
+    }
+    content.push_str("-->\n");
+    content.push_str(&page.body);
+
+    fs::write(paths::long_path(&path), content)?;
+    prune(dir, keep)?;
+    Ok(path)
+}
+
+fn prune(dir: &Path, keep: usize) -> Result<()> {
+    let mut snapshots: Vec<PathBuf> = fs::read_dir(paths::long_path(dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    snapshots.sort();
+
+    let excess = snapshots.len().saturating_sub(keep);
+    for path in snapshots.into_iter().take(excess) {
+        fs::remove_file(&path)?;
+        log_info(
+            "SNAPSHOT",
+            &format!("Removed old snapshot {}", path.display()),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::check::{self, Observed};
+use crate::config::{Config, DesktopConfig, EntryKey, HttpConfig};
+use crate::extract;
+use crate::fetch;
+use crate::ignore;
+use crate::pace;
+use crate::record;
+use crate::timing::Timings;
+use crate::version::version_key;
+use crate::Result;
+
+pub use crate::version::VersionEntry;
+
+/// Anything that can say which version is current. Implement it in another
+/// crate and add it to a [`SourceRegistry`] to have it checked like the built-ins.
+#[async_trait]
+pub trait VersionSource: Send + Sync {
+    /// Registry name, e.g. `spotify-web`.
+    fn name(&self) -> &str;
+    async fn fetch(&self) -> Result<VersionEntry>;
+    /// What fetched entries are stored under; [`EntryKey::FirstSeen`] for
+    /// sources whose builds are named by a hash.
+    fn key(&self) -> EntryKey {
+        EntryKey::Version
+    }
+    /// Keys (or clientVersions) never to save, as kept by `ignore add`.
+    fn ignored(&self) -> Result<BTreeSet<String>> {
+        Ok(BTreeSet::new())
+    }
+}
+
+/// A page surface from the config (a built-in one or one of `[[surfaces]]`),
+/// read the same way a check does.
+pub struct SurfaceSource {
+    name: String,
+    surface: String,
+    config: Arc<Config>,
+}
+
+impl SurfaceSource {
+    pub fn new(name: impl Into<String>, surface: impl Into<String>, config: Arc<Config>) -> Self {
+        SurfaceSource {
+            name: name.into(),
+            surface: surface.into(),
+            config,
+        }
+    }
+}
+
+#[async_trait]
+impl VersionSource for SurfaceSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn key(&self) -> EntryKey {
+        self.config
+            .surface(&self.surface)
+            .map_or(EntryKey::Version, |surface| surface.extract.key)
+    }
+
+    fn ignored(&self) -> Result<BTreeSet<String>> {
+        match self.config.surface(&self.surface) {
+            Some(surface) => ignore::load(surface.versions_file),
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    async fn fetch(&self) -> Result<VersionEntry> {
+        let surface = self
+            .config
+            .surface(&self.surface)
+            .ok_or_else(|| format!("Unknown surface '{}'", self.surface))?;
+        match check::observe(&self.config, &surface, &mut Timings::default()).await? {
+            Observed::Version(observation) => Ok(VersionEntry {
+                key: observation.key,
+                data: observation.entry,
+            }),
+            Observed::Failed(output) => Err(output
+                .error
+                .map_or_else(|| "Check failed".to_string(), |error| error.message)
+                .into()),
+        }
+    }
+}
+
+/// The desktop client release named in `[desktop]`.
+pub struct DesktopSource {
+    desktop: DesktopConfig,
+    http: HttpConfig,
+}
+
+impl DesktopSource {
+    pub fn new(desktop: DesktopConfig, http: HttpConfig) -> Self {
+        DesktopSource { desktop, http }
+    }
+}
+
+#[async_trait]
+impl VersionSource for DesktopSource {
+    fn name(&self) -> &str {
+        "desktop"
+    }
+
+    async fn fetch(&self) -> Result<VersionEntry> {
+        let user_agent = self
+            .http
+            .user_agent
+            .as_deref()
+            .unwrap_or(fetch::FALLBACK_USER_AGENT);
+        let client = fetch::build_client(user_agent, &self.http, false)?;
+        let _permit = pace::wait(&self.http).await;
+        let request = client.get(&self.desktop.url).build()?;
+        let document: Value = serde_json::from_str(&record::send(&client, request).await?.text()?)?;
+
+        let raw = extract::field(&document, &self.desktop.version_field).ok_or_else(|| {
+            format!(
+                "'{}' not found in {}",
+                self.desktop.version_field, self.desktop.url
+            )
+        })?;
+        // Casks append build ids after a comma, e.g. `1.2.86.316,cd065fc0,1`
+        let client_version = raw.split(',').next().unwrap_or(raw);
+
+        let mut data = json!({ "clientVersion": client_version });
+        if client_version != raw {
+            data["release"] = json!(raw);
+        }
+        Ok(VersionEntry {
+            key: version_key(client_version),
+            data,
+        })
+    }
+}
+
+/// Named version sources, checked in registration order.
+#[derive(Clone, Default)]
+pub struct SourceRegistry {
+    sources: Vec<Arc<dyn VersionSource>>,
+}
+
+impl fmt::Debug for SourceRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl SourceRegistry {
+    pub fn new() -> Self {
+        SourceRegistry::default()
+    }
+
+    /// `spotify-web`, `spotify-embed`, `youtube-music`, `apple-music`, `spotify-<name>` for each of `[[surfaces]]`
+    /// and `desktop`, all configured from `config`.
+    pub fn builtin(config: &Config) -> Self {
+        let shared = Arc::new(config.clone());
+        let mut registry = SourceRegistry::new();
+        registry.register(SurfaceSource::new("spotify-web", "web", shared.clone()));
+        registry.register(SurfaceSource::new("spotify-embed", "embed", shared.clone()));
+        for name in ["youtube-music", "apple-music"] {
+            registry.register(SurfaceSource::new(name, name, shared.clone()));
+        }
+        for surface in &config.surfaces {
+            registry.register(SurfaceSource::new(
+                format!("spotify-{}", surface.name),
+                surface.name.clone(),
+                shared.clone(),
+            ));
+        }
+        registry.register(DesktopSource::new(
+            config.desktop.clone(),
+            config.http.clone(),
+        ));
+        registry
+    }
+
+    /// Adds `source`, replacing any registered under the same name.
+    pub fn register(&mut self, source: impl VersionSource + 'static) {
+        self.register_arc(Arc::new(source));
+    }
+
+    pub fn register_arc(&mut self, source: Arc<dyn VersionSource>) {
+        match self.sources.iter_mut().find(|s| s.name() == source.name()) {
+            Some(existing) => *existing = source,
+            None => self.sources.push(source),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn VersionSource>> {
+        self.sources.iter().find(|s| s.name() == name).cloned()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.sources.iter().map(|s| s.name()).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn VersionSource>> {
+        self.sources.iter()
+    }
+}
@@ -0,0 +1,208 @@
+use chrono::{
+    DateTime, Datelike, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Timelike, Utc,
+};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Layouts `buildDate` has been seen in or could plausibly switch to, tried in
+/// order after RFC 3339. Times without an offset are taken as UTC.
+const DATE_TIME_FORMATS: [&str; 3] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+];
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y/%m/%d", "%Y%m%d"];
+
+/// Parses a raw `buildDate` string; dates without a time are midnight UTC.
+pub fn parse_build_date(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(raw) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = DateTime::parse_from_rfc2822(raw) {
+        return Some(time.with_timezone(&Utc));
+    }
+    DATE_TIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+        .or_else(|| {
+            DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|datetime| Utc.from_utc_datetime(&datetime))
+}
+
+/// `buildDateIso` for a raw `buildDate`: RFC 3339 in UTC, whole seconds.
+pub fn build_date_iso(raw: &str) -> Option<String> {
+    parse_build_date(raw).map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+/// Build time of an entry. `buildVersion` looks like
+/// `open-server_2026-03-15_1773590236035_cd065fc` and carries the exact build
+/// time in milliseconds; otherwise `buildDateIso`, or `buildDate` parsed the
+/// same way, is used.
+pub fn build_time(entry: &Value) -> Option<DateTime<Utc>> {
+    let from_build_version = entry
+        .get("buildVersion")
+        .and_then(|v| v.as_str())
+        .and_then(|bv| bv.split('_').find(|part| part.len() == 13))
+        .and_then(|millis| millis.parse::<i64>().ok())
+        .and_then(|millis| Utc.timestamp_millis_opt(millis).single());
+
+    from_build_version.or_else(|| {
+        ["buildDateIso", "buildDate"]
+            .iter()
+            .filter_map(|field| entry.get(*field).and_then(|v| v.as_str()))
+            .find_map(parse_build_date)
+    })
+}
+
+/// Schema 1 -> 2: adds `buildDateIso` next to every parseable `buildDate`.
+pub fn add_build_date_iso(versions: &mut HashMap<String, Value>) {
+    for entry in versions.values_mut() {
+        let iso = entry
+            .get("buildDate")
+            .and_then(|v| v.as_str())
+            .and_then(build_date_iso);
+        if let Some(iso) = iso {
+            entry["buildDateIso"] = json!(iso);
+        }
+    }
+}
+
+/// Entries with a known build time, oldest first.
+pub fn timeline(versions: &HashMap<String, Value>) -> Vec<(String, DateTime<Utc>)> {
+    let mut points: Vec<(String, DateTime<Utc>)> = versions
+        .iter()
+        .filter_map(|(key, entry)| build_time(entry).map(|time| (key.clone(), time)))
+        .collect();
+    points.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    points
+}
+
+/// When the crawler first saw an entry (`firstSeen`, written on save).
+pub fn first_seen(entry: &Value) -> Option<DateTime<Utc>> {
+    entry
+        .get("firstSeen")
+        .and_then(|v| v.as_str())
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Hours from build to first sighting, per build month and overall. Entries
+/// with only a `buildDate` count from midnight UTC, so their lag reads up to
+/// a day long.
+pub fn detection_lag(versions: &HashMap<String, Value>) -> Value {
+    let mut lags: Vec<(String, f64)> = versions
+        .values()
+        .filter_map(|entry| {
+            let built = build_time(entry)?;
+            let seen = first_seen(entry)?;
+            let hours = (seen - built).num_minutes() as f64 / 60.0;
+            Some((built.format("%Y-%m").to_string(), hours))
+        })
+        .collect();
+    lags.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let mut per_month: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+    for (month, hours) in &lags {
+        per_month.entry(month.clone()).or_default().push(*hours);
+    }
+    let per_month: serde_json::Map<String, Value> = per_month
+        .into_iter()
+        .map(|(month, hours)| (month, lag_summary(&hours)))
+        .collect();
+
+    let all: Vec<f64> = lags.into_iter().map(|(_, hours)| hours).collect();
+    let mut summary = lag_summary(&all);
+    summary["per_month"] = json!(per_month);
+    summary
+}
+
+/// `hours` must be sorted.
+fn lag_summary(hours: &[f64]) -> Value {
+    let round = |hours: f64| (hours * 10.0).round() / 10.0;
+    if hours.is_empty() {
+        return json!({ "versions": 0 });
+    }
+    // Nearest-rank percentile
+    let p95 = hours[((hours.len() as f64 * 0.95).ceil() as usize).max(1) - 1];
+    json!({
+        "versions": hours.len(),
+        "min_hours": round(hours[0]),
+        "avg_hours": round(hours.iter().sum::<f64>() / hours.len() as f64),
+        "p95_hours": round(p95),
+        "max_hours": round(hours[hours.len() - 1])
+    })
+}
+
+/// `bundleBytes` of entries with a known build time, oldest first.
+pub fn bundle_sizes(versions: &HashMap<String, Value>) -> Vec<(String, DateTime<Utc>, u64)> {
+    timeline(versions)
+        .into_iter()
+        .filter_map(|(key, time)| {
+            let bytes = versions[&key]["bundleBytes"].as_u64()?;
+            Some((key, time, bytes))
+        })
+        .collect()
+}
+
+/// First and last recorded web-player size and the change between them.
+fn bundle_size_summary(sizes: &[(String, DateTime<Utc>, u64)]) -> Value {
+    let (Some(first), Some(last)) = (sizes.first(), sizes.last()) else {
+        return json!({ "versions": 0 });
+    };
+    json!({
+        "versions": sizes.len(),
+        "first": { "key": first.0, "bytes": first.2 },
+        "last": { "key": last.0, "bytes": last.2 },
+        "growth_bytes": last.2 as i64 - first.2 as i64,
+        "max_bytes": sizes.iter().map(|(_, _, bytes)| *bytes).max()
+    })
+}
+
+pub fn compute(versions: &HashMap<String, Value>) -> Value {
+    let points = timeline(versions);
+
+    let mut per_month: BTreeMap<String, usize> = BTreeMap::new();
+    let mut per_weekday = [0usize; 7];
+    let mut per_hour = [0usize; 24];
+    for (_, time) in &points {
+        *per_month
+            .entry(time.format("%Y-%m").to_string())
+            .or_default() += 1;
+        per_weekday[time.weekday().num_days_from_monday() as usize] += 1;
+        per_hour[time.hour() as usize] += 1;
+    }
+
+    let average_hours_between_builds = match (points.first(), points.last()) {
+        (Some(first), Some(last)) if points.len() > 1 => {
+            let span = (last.1 - first.1).num_minutes() as f64 / 60.0;
+            Some((span / (points.len() - 1) as f64 * 10.0).round() / 10.0)
+        }
+        _ => None,
+    };
+
+    let per_weekday: serde_json::Map<String, Value> = WEEKDAYS
+        .iter()
+        .zip(per_weekday)
+        .map(|(day, count)| (day.to_string(), json!(count)))
+        .collect();
+
+    json!({
+        "versions": versions.len(),
+        "with_build_time": points.len(),
+        "first_build": points.first().map(|(key, time)| json!({"key": key, "time": time.to_rfc3339()})),
+        "last_build": points.last().map(|(key, time)| json!({"key": key, "time": time.to_rfc3339()})),
+        "average_hours_between_builds": average_hours_between_builds,
+        "per_month": per_month,
+        "per_weekday": per_weekday,
+        "per_hour_utc": per_hour.to_vec(),
+        "detection_lag": detection_lag(versions),
+        "bundle_size": bundle_size_summary(&bundle_sizes(versions))
+    })
+}
@@ -0,0 +1,98 @@
+//! The last few saves of a versions file, so `undo` can revert a bad one
+//! without hand-editing JSON. Each record holds the before and after of every
+//! key the save changed; they live oldest first in
+//! `<versions_file>.journal.json`.
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::sorted_keys;
+use crate::paths;
+use crate::Result;
+
+/// One key as a save changed it; `None` is "not stored".
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Change {
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Record {
+    pub at: String,
+    pub changes: Vec<Change>,
+}
+
+pub fn journal_path(versions_file: &Path) -> PathBuf {
+    let mut name = versions_file.file_name().unwrap_or_default().to_os_string();
+    name.push(".journal.json");
+    versions_file.with_file_name(name)
+}
+
+/// Keys that differ between `old` and `new`, newest first.
+pub fn changes(old: &HashMap<String, Value>, new: &HashMap<String, Value>) -> Vec<Change> {
+    let mut all = old.clone();
+    all.extend(new.iter().map(|(key, entry)| (key.clone(), entry.clone())));
+    sorted_keys(&all)
+        .into_iter()
+        .filter(|key| old.get(key) != new.get(key))
+        .map(|key| Change {
+            before: old.get(&key).cloned(),
+            after: new.get(&key).cloned(),
+            key,
+        })
+        .collect()
+}
+
+pub fn load(versions_file: &Path) -> Result<Vec<Record>> {
+    let path = journal_path(versions_file);
+    let Some(content) = paths::read_optional(&path)? else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e).into())
+}
+
+pub fn save(versions_file: &Path, records: &[Record]) -> Result<()> {
+    let content = serde_json::to_string_pretty(records)?;
+    paths::write_atomic(&journal_path(versions_file), content.as_bytes())
+}
+
+/// Journals the difference between `old` and `new`, keeping the `keep` newest
+/// records. Saves that change nothing are not recorded.
+pub fn append(
+    versions_file: &Path,
+    old: &HashMap<String, Value>,
+    new: &HashMap<String, Value>,
+    keep: usize,
+) -> Result<()> {
+    let changes = changes(old, new);
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let mut records = load(versions_file)?;
+    records.push(Record {
+        at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        changes,
+    });
+    let excess = records.len().saturating_sub(keep);
+    records.drain(..excess);
+    save(versions_file, &records)
+}
+
+/// Puts every key of `record` back as it was before. Reverting twice is
+/// harmless, so an undo interrupted between the two writes can be repeated.
+pub fn revert(versions: &mut HashMap<String, Value>, record: &Record) {
+    for change in &record.changes {
+        match &change.before {
+            Some(entry) => versions.insert(change.key.clone(), entry.clone()),
+            None => versions.remove(&change.key),
+        };
+    }
+}
@@ -0,0 +1,52 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::RwLock;
+
+use super::VersionStore;
+use crate::Result;
+
+/// Keeps the versions map in memory only, for library use without any disk IO.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    versions: RwLock<HashMap<String, Value>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+
+    pub fn from_versions(versions: HashMap<String, Value>) -> Self {
+        MemoryStore {
+            versions: RwLock::new(versions),
+        }
+    }
+
+    /// Seeds the store from a versions file's JSON read from anywhere (file, HTTP body, bytes).
+    pub fn from_reader(reader: impl Read) -> Result<Self> {
+        Ok(MemoryStore::from_versions(serde_json::from_reader(reader)?))
+    }
+
+    /// Copy of the current map.
+    pub fn versions(&self) -> HashMap<String, Value> {
+        self.versions.read().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl VersionStore for MemoryStore {
+    fn describe(&self) -> String {
+        "memory".to_string()
+    }
+
+    async fn load(&self) -> Result<HashMap<String, Value>> {
+        Ok(self.versions())
+    }
+
+    async fn save(&self, versions: &HashMap<String, Value>) -> Result<()> {
+        *self.versions.write().unwrap() = versions.clone();
+        Ok(())
+    }
+}
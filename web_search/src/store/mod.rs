@@ -0,0 +1,324 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::backup;
+use crate::config::{BackupConfig, Config, IntegrityConfig, StoreBackend, Surface};
+use crate::integrity;
+use crate::log::{log_info, log_success, log_warning};
+use crate::paths;
+use crate::salvage;
+use crate::schema;
+use crate::version::compare_versions;
+use crate::Result;
+
+pub mod journal;
+mod memory;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "redis")]
+pub mod redis;
+#[cfg(feature = "s3")]
+pub mod s3;
+#[cfg(feature = "store-sqlite")]
+pub mod sqlite;
+
+pub use memory::MemoryStore;
+
+/// Where a surface's versions map is kept. `save` replaces the whole map.
+#[async_trait]
+pub trait VersionStore: Send + Sync {
+    /// Location for log messages, e.g. a path or `s3://bucket/key`.
+    fn describe(&self) -> String;
+    async fn load(&self) -> Result<HashMap<String, Value>>;
+    async fn save(&self, versions: &HashMap<String, Value>) -> Result<()>;
+
+    /// Called once a newly detected version has been saved; backends with a
+    /// notification channel publish it there.
+    async fn announce(&self, _key: &str, _entry: &Value) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Opens the configured backend for a surface.
+pub async fn open(config: &Config, surface: &Surface<'_>) -> Result<Box<dyn VersionStore>> {
+    match config.store.backend {
+        StoreBackend::File => Ok(Box::new(open_file(config, surface))),
+        #[cfg(feature = "s3")]
+        StoreBackend::S3 => Ok(Box::new(
+            s3::S3Store::connect(&config.store.s3, surface).await?,
+        )),
+        #[cfg(not(feature = "s3"))]
+        StoreBackend::S3 => Err("The s3 store backend needs a build with `--features s3`".into()),
+        #[cfg(feature = "redis")]
+        StoreBackend::Redis => Ok(Box::new(
+            redis::RedisStore::connect(&config.store.redis, surface).await?,
+        )),
+        #[cfg(not(feature = "redis"))]
+        StoreBackend::Redis => {
+            Err("The redis store backend needs a build with `--features redis`".into())
+        }
+        #[cfg(feature = "postgres")]
+        StoreBackend::Postgres => Ok(Box::new(
+            postgres::PostgresStore::connect(&config.store.postgres, surface).await?,
+        )),
+        #[cfg(not(feature = "postgres"))]
+        StoreBackend::Postgres => {
+            Err("The postgres store backend needs a build with `--features postgres`".into())
+        }
+        #[cfg(feature = "store-sqlite")]
+        StoreBackend::Sqlite => Ok(Box::new(
+            sqlite::SqliteStore::connect(&config.store.sqlite, surface).await?,
+        )),
+        #[cfg(not(feature = "store-sqlite"))]
+        StoreBackend::Sqlite => {
+            Err("The sqlite store backend needs a build with `--features store-sqlite`".into())
+        }
+    }
+}
+
+/// The file store for a surface, whatever backend is configured.
+pub fn open_file(config: &Config, surface: &Surface<'_>) -> FileStore {
+    FileStore::new(surface.versions_file)
+        .with_backups(config.backup.clone())
+        .with_salvage(config.store.salvage)
+        .with_integrity(config.integrity.clone())
+        .with_journal(config.store.journal)
+}
+
+/// The JSON file in the repo, in its historical layout.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    path: PathBuf,
+    backups: Option<BackupConfig>,
+    salvage: bool,
+    integrity: Option<IntegrityConfig>,
+    journal: usize,
+}
+
+impl FileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileStore {
+            path: path.into(),
+            backups: None,
+            salvage: false,
+            integrity: None,
+            journal: 0,
+        }
+    }
+
+    /// Back the file up before every save.
+    pub fn with_backups(mut self, config: BackupConfig) -> Self {
+        self.backups = Some(config);
+        self
+    }
+
+    /// Salvage a damaged file on load instead of failing (see [`salvage::recover`]).
+    pub fn with_salvage(mut self, salvage: bool) -> Self {
+        self.salvage = salvage;
+        self
+    }
+
+    /// Write checksum/signature sidecars after every save.
+    pub fn with_integrity(mut self, config: IntegrityConfig) -> Self {
+        self.integrity = Some(config);
+        self
+    }
+
+    /// Journal the `keep` most recent saves for [`FileStore::undo`]; 0 disables it.
+    pub fn with_journal(mut self, keep: usize) -> Self {
+        self.journal = keep;
+        self
+    }
+
+    /// Reverts the most recent journaled save and drops it from the journal.
+    /// Returns the reverted record, or `None` when the journal is empty.
+    pub fn undo(&self) -> Result<Option<journal::Record>> {
+        let mut records = journal::load(&self.path)?;
+        let Some(record) = records.pop() else {
+            return Ok(None);
+        };
+        let mut versions = self.load_file()?;
+        journal::revert(&mut versions, &record);
+        self.write(&versions)?;
+        journal::save(&self.path, &records)?;
+        Ok(Some(record))
+    }
+
+    fn write(&self, versions: &HashMap<String, Value>) -> Result<()> {
+        if let Some(backups) = &self.backups {
+            backup::backup(backups, &self.path)?;
+        }
+        save_versions(&self.path, versions)?;
+        schema::write_version(&self.path)?;
+        if let Some(integrity) = &self.integrity {
+            integrity::write_sidecars(integrity, &self.path)?;
+        }
+        Ok(())
+    }
+
+    fn load_file(&self) -> Result<HashMap<String, Value>> {
+        let error = match load_existing_versions(&self.path) {
+            Ok(versions) => return Ok(versions),
+            Err(e) if !self.salvage => return Err(e),
+            Err(e) => e,
+        };
+        // Only a file that reads but doesn't parse is worth salvaging
+        match paths::read_optional(&self.path)? {
+            Some(content) => salvage::recover(&self.path, &content, self.backups.as_ref())
+                .map(|(versions, _)| versions),
+            None => Err(error),
+        }
+    }
+}
+
+#[async_trait]
+impl VersionStore for FileStore {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    async fn load(&self) -> Result<HashMap<String, Value>> {
+        let mut versions = self.load_file()?;
+        let from = schema::read_version(&self.path)?;
+        // Persist right away so consumers reading the file see the new format
+        // Not journaled: undoing a migration would leave old data under the new schema
+        if schema::migrate(&mut versions, from)? && !versions.is_empty() {
+            self.write(&versions)?;
+        }
+        Ok(versions)
+    }
+
+    async fn save(&self, versions: &HashMap<String, Value>) -> Result<()> {
+        // A file that no longer parses has nothing worth journaling against
+        let before = if self.journal == 0 {
+            None
+        } else {
+            paths::read_optional(&self.path)?
+                .map_or(Ok(HashMap::new()), |content| serde_json::from_str(&content))
+                .ok()
+        };
+        self.write(versions)?;
+        if let Some(before) = before {
+            journal::append(&self.path, &before, versions, self.journal)?;
+        }
+        Ok(())
+    }
+}
+
+#[tracing::instrument(skip_all, fields(path = %path.display()))]
+pub fn load_existing_versions(path: &Path) -> Result<HashMap<String, Value>> {
+    log_info("FILE", &format!("Loading existing {}", path.display()));
+    let Some(content) = paths::read_optional(path)? else {
+        log_warning(
+            "FILE",
+            &format!("{} not found, treating as new", path.display()),
+        );
+        return Ok(HashMap::new());
+    };
+
+    let versions: HashMap<String, Value> = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    log_success(
+        "FILE",
+        &format!("Loaded {} existing versions", versions.len()),
+    );
+    Ok(versions)
+}
+
+#[tracing::instrument(skip_all, fields(path = %path.display(), versions = versions.len()))]
+pub fn save_versions(path: &Path, versions: &HashMap<String, Value>) -> Result<()> {
+    let json_content = to_json(versions)?;
+    paths::write_atomic(path, json_content.as_bytes())?;
+    log_success("FILE", &format!("Saved {} to disk", path.display()));
+    Ok(())
+}
+
+/// Version keys, newest first.
+pub fn sorted_keys(versions: &HashMap<String, Value>) -> Vec<String> {
+    let mut keys: Vec<String> = versions.keys().cloned().collect();
+    keys.sort_by(|a, b| compare_versions(a, b));
+    keys
+}
+
+/// Drops all but the `keep` newest versions and returns the removed keys, newest first.
+pub fn prune(versions: &mut HashMap<String, Value>, keep: usize) -> Vec<String> {
+    let removed: Vec<String> = sorted_keys(versions).into_iter().skip(keep).collect();
+    for key in &removed {
+        versions.remove(key);
+    }
+    removed
+}
+
+/// Entry field holding user annotations. Checks never write it, so it
+/// survives every later update of the entry.
+pub const ANNOTATIONS: &str = "x-annotations";
+
+/// Sets `name=value` pairs and removes `unset` names in the annotations of
+/// `key`, returning the resulting annotations object.
+pub fn annotate(
+    versions: &mut HashMap<String, Value>,
+    key: &str,
+    set: &[(String, String)],
+    unset: &[String],
+) -> Result<Value> {
+    let entry = versions
+        .get_mut(key)
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| format!("No version {} to annotate", key))?;
+    let annotations = entry
+        .entry(ANNOTATIONS)
+        .or_insert_with(|| json!({}))
+        .as_object_mut()
+        .ok_or_else(|| format!("{} of {} is not an object", ANNOTATIONS, key))?;
+    for (name, value) in set {
+        annotations.insert(name.clone(), json!(value));
+    }
+    for name in unset {
+        annotations.remove(name);
+    }
+    let annotations = Value::Object(annotations.clone());
+    if annotations
+        .as_object()
+        .is_some_and(|fields| fields.is_empty())
+    {
+        entry.remove(ANNOTATIONS);
+    }
+    Ok(annotations)
+}
+
+/// Renders the versions map newest first, in the same layout as the file in the repo.
+pub fn to_json(versions: &HashMap<String, Value>) -> Result<String> {
+    log_info("SORT", "Sorting versions...");
+
+    let sorted_keys = sorted_keys(versions);
+
+    log_success("SORT", "Versions sorted");
+
+    let mut json_parts = Vec::new();
+    json_parts.push("{\n".to_string());
+
+    for (i, key) in sorted_keys.iter().enumerate() {
+        if let Some(value) = versions.get(key) {
+            let value_str = serde_json::to_string_pretty(value)?;
+
+            let indented_value: Vec<String> = value_str
+                .lines()
+                .map(|line| format!("  {}", line))
+                .collect();
+
+            json_parts.push(format!("  \"{}\": {}", key, indented_value.join("\n")));
+
+            if i < sorted_keys.len() - 1 {
+                json_parts.push(",\n".to_string());
+            } else {
+                json_parts.push("\n".to_string());
+            }
+        }
+    }
+
+    json_parts.push("}".to_string());
+
+    Ok(json_parts.join(""))
+}
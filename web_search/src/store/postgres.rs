@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::types::Json;
+use std::collections::HashMap;
+
+use super::VersionStore;
+use crate::config::{PostgresConfig, Surface};
+use crate::log::{log_info, log_success};
+use crate::Result;
+
+/// Rows of the `versions` table for one surface. `first_seen` is set on insert
+/// and never touched by later saves.
+pub struct PostgresStore {
+    pool: PgPool,
+    surface: String,
+}
+
+impl PostgresStore {
+    pub async fn connect(config: &PostgresConfig, surface: &Surface<'_>) -> Result<PostgresStore> {
+        let pool = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .connect(&config.url)
+            .await?;
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        log_info("POSTGRES", "Schema is up to date");
+
+        Ok(PostgresStore {
+            pool,
+            surface: surface.name.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl VersionStore for PostgresStore {
+    fn describe(&self) -> String {
+        format!("postgres table versions (surface {})", self.surface)
+    }
+
+    async fn load(&self) -> Result<HashMap<String, Value>> {
+        log_info("POSTGRES", &format!("Loading {}", self.describe()));
+        let rows: Vec<(String, Json<Value>)> =
+            // Rows from before firstSeen was written into entries get it from the column
+            sqlx::query_as(
+                "SELECT key, jsonb_build_object('firstSeen', first_seen) || entry
+                 FROM versions WHERE surface = $1",
+            )
+                .bind(&self.surface)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let versions: HashMap<String, Value> = rows
+            .into_iter()
+            .map(|(key, Json(entry))| (key, entry))
+            .collect();
+        log_success(
+            "POSTGRES",
+            &format!("Loaded {} existing versions", versions.len()),
+        );
+        Ok(versions)
+    }
+
+    async fn save(&self, versions: &HashMap<String, Value>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for (key, entry) in versions {
+            sqlx::query(
+                "INSERT INTO versions (surface, key, entry) VALUES ($1, $2, $3)
+                 ON CONFLICT (surface, key) DO UPDATE
+                 SET entry = EXCLUDED.entry, updated_at = now()
+                 WHERE versions.entry IS DISTINCT FROM EXCLUDED.entry",
+            )
+            .bind(&self.surface)
+            .bind(key)
+            .bind(Json(entry))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let keys: Vec<String> = versions.keys().cloned().collect();
+        let deleted =
+            sqlx::query("DELETE FROM versions WHERE surface = $1 AND NOT (key = ANY($2))")
+                .bind(&self.surface)
+                .bind(&keys)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        tx.commit().await?;
+        log_success(
+            "POSTGRES",
+            &format!("Saved {} versions ({} removed)", versions.len(), deleted),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,96 @@
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use super::VersionStore;
+use crate::config::{RedisConfig, Surface};
+use crate::log::{log_info, log_success};
+use crate::Result;
+
+/// One Redis hash per surface; new versions are also published on a channel.
+pub struct RedisStore {
+    connection: MultiplexedConnection,
+    hash: String,
+    channel: String,
+    surface: String,
+}
+
+impl RedisStore {
+    pub async fn connect(config: &RedisConfig, surface: &Surface<'_>) -> Result<RedisStore> {
+        let client = redis::Client::open(config.url.as_str())?;
+        let connection = client.get_multiplexed_async_connection().await?;
+        Ok(RedisStore {
+            connection,
+            hash: format!("{}:{}", config.key_prefix, surface.name),
+            channel: config.channel.clone(),
+            surface: surface.name.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl VersionStore for RedisStore {
+    fn describe(&self) -> String {
+        format!("redis hash {}", self.hash)
+    }
+
+    async fn load(&self) -> Result<HashMap<String, Value>> {
+        log_info("REDIS", &format!("Loading {}", self.describe()));
+        let mut connection = self.connection.clone();
+        let fields: HashMap<String, String> = connection.hgetall(&self.hash).await?;
+
+        let mut versions = HashMap::with_capacity(fields.len());
+        for (key, entry) in fields {
+            let entry: Value = serde_json::from_str(&entry)
+                .map_err(|e| format!("Failed to parse {} in {}: {}", key, self.hash, e))?;
+            versions.insert(key, entry);
+        }
+
+        log_success(
+            "REDIS",
+            &format!("Loaded {} existing versions", versions.len()),
+        );
+        Ok(versions)
+    }
+
+    async fn save(&self, versions: &HashMap<String, Value>) -> Result<()> {
+        let fields: Vec<(&str, String)> = versions
+            .iter()
+            .map(|(key, entry)| (key.as_str(), entry.to_string()))
+            .collect();
+
+        // Replace the hash in one MULTI/EXEC so readers never see it half written
+        let mut pipeline = redis::pipe();
+        pipeline.atomic().del(&self.hash).ignore();
+        if !fields.is_empty() {
+            pipeline.hset_multiple(&self.hash, &fields).ignore();
+        }
+        let mut connection = self.connection.clone();
+        let _: () = pipeline.query_async(&mut connection).await?;
+
+        log_success("REDIS", &format!("Saved {}", self.describe()));
+        Ok(())
+    }
+
+    async fn announce(&self, key: &str, entry: &Value) -> Result<()> {
+        let message = json!({
+            "surface": self.surface,
+            "key": key,
+            "data": entry
+        });
+        let mut connection = self.connection.clone();
+        let receivers: i64 = connection
+            .publish(&self.channel, message.to_string())
+            .await?;
+        log_info(
+            "REDIS",
+            &format!(
+                "Published {} on {} ({} subscribers)",
+                key, self.channel, receivers
+            ),
+        );
+        Ok(())
+    }
+}
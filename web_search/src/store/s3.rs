@@ -0,0 +1,134 @@
+use async_trait::async_trait;
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::{to_json, VersionStore};
+use crate::config::{S3Config, Surface};
+use crate::log::{log_info, log_success, log_warning};
+use crate::Result;
+
+/// The versions map as one JSON object in a bucket.
+///
+/// `save` is a conditional PUT against the ETag seen by the last `load` (or
+/// `If-None-Match: *` if the object didn't exist), so a concurrent writer makes
+/// the save fail instead of silently losing its update.
+pub struct S3Store {
+    client: Client,
+    bucket: String,
+    key: String,
+    etag: Mutex<Option<String>>,
+}
+
+impl S3Store {
+    pub async fn connect(config: &S3Config, surface: &Surface<'_>) -> Result<S3Store> {
+        if config.bucket.is_empty() {
+            return Err("[store.s3] bucket is not set".into());
+        }
+        let file_name = surface
+            .versions_file
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or("versions_file has no file name to use as the object key")?;
+
+        let mut loader = aws_config::defaults(BehaviorVersion::latest());
+        if let Some(region) = &config.region {
+            loader = loader.region(aws_config::Region::new(region.clone()));
+        }
+        let shared = loader.load().await;
+
+        let mut builder = aws_sdk_s3::config::Builder::from(&shared);
+        if let Some(endpoint) = &config.endpoint {
+            builder = builder.endpoint_url(endpoint).force_path_style(true);
+        }
+
+        Ok(S3Store {
+            client: Client::from_conf(builder.build()),
+            bucket: config.bucket.clone(),
+            key: format!("{}{}", config.prefix, file_name),
+            etag: Mutex::new(None),
+        })
+    }
+}
+
+#[async_trait]
+impl VersionStore for S3Store {
+    fn describe(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.key)
+    }
+
+    async fn load(&self) -> Result<HashMap<String, Value>> {
+        log_info("S3", &format!("Loading {}", self.describe()));
+        let response = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .send()
+            .await;
+
+        let object = match response {
+            Ok(object) => object,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => {
+                log_warning(
+                    "S3",
+                    &format!("{} not found, treating as new", self.describe()),
+                );
+                *self.etag.lock().unwrap() = None;
+                return Ok(HashMap::new());
+            }
+            Err(e) => return Err(format!("Failed to get {}: {}", self.describe(), e).into()),
+        };
+
+        let etag = object.e_tag().map(str::to_string);
+        let bytes = object.body.collect().await?.into_bytes();
+        let versions: HashMap<String, Value> = serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Failed to parse {}: {}", self.describe(), e))?;
+        *self.etag.lock().unwrap() = etag;
+
+        log_success(
+            "S3",
+            &format!("Loaded {} existing versions", versions.len()),
+        );
+        Ok(versions)
+    }
+
+    async fn save(&self, versions: &HashMap<String, Value>) -> Result<()> {
+        let body = to_json(versions)?;
+        let etag = self.etag.lock().unwrap().clone();
+
+        let mut request = self
+            .client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .content_type("application/json")
+            .body(ByteStream::from(body.into_bytes()));
+        request = match &etag {
+            Some(etag) => request.if_match(etag),
+            None => request.if_none_match("*"),
+        };
+
+        match request.send().await {
+            Ok(output) => {
+                *self.etag.lock().unwrap() = output.e_tag().map(str::to_string);
+                log_success("S3", &format!("Saved {}", self.describe()));
+                Ok(())
+            }
+            Err(e)
+                if e.raw_response()
+                    .is_some_and(|r| matches!(r.status().as_u16(), 409 | 412)) =>
+            {
+                Err(format!(
+                    "{} was changed by another writer since it was loaded; not overwriting",
+                    self.describe()
+                )
+                .into())
+            }
+            Err(e) => Err(format!("Failed to put {}: {}", self.describe(), e).into()),
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::types::Json;
+use std::collections::{HashMap, HashSet};
+
+use super::VersionStore;
+use crate::config::{SqliteConfig, Surface};
+use crate::log::{log_info, log_success};
+use crate::Result;
+
+/// Rows of the `versions` table for one surface, in a single database file
+/// that several surfaces can share.
+pub struct SqliteStore {
+    pool: SqlitePool,
+    path: String,
+    surface: String,
+}
+
+impl SqliteStore {
+    pub async fn connect(config: &SqliteConfig, surface: &Surface<'_>) -> Result<SqliteStore> {
+        let options = SqliteConnectOptions::new()
+            .filename(&config.path)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS versions (
+                surface TEXT NOT NULL,
+                key TEXT NOT NULL,
+                entry TEXT NOT NULL,
+                PRIMARY KEY (surface, key)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        log_info("SQLITE", "Schema is up to date");
+
+        Ok(SqliteStore {
+            pool,
+            path: config.path.display().to_string(),
+            surface: surface.name.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl VersionStore for SqliteStore {
+    fn describe(&self) -> String {
+        format!("sqlite {} (surface {})", self.path, self.surface)
+    }
+
+    async fn load(&self) -> Result<HashMap<String, Value>> {
+        log_info("SQLITE", &format!("Loading {}", self.describe()));
+        let rows: Vec<(String, Json<Value>)> =
+            sqlx::query_as("SELECT key, entry FROM versions WHERE surface = ?")
+                .bind(&self.surface)
+                .fetch_all(&self.pool)
+                .await?;
+
+        let versions: HashMap<String, Value> = rows
+            .into_iter()
+            .map(|(key, Json(entry))| (key, entry))
+            .collect();
+        log_success(
+            "SQLITE",
+            &format!("Loaded {} existing versions", versions.len()),
+        );
+        Ok(versions)
+    }
+
+    async fn save(&self, versions: &HashMap<String, Value>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for (key, entry) in versions {
+            sqlx::query(
+                "INSERT INTO versions (surface, key, entry) VALUES (?, ?, ?)
+                 ON CONFLICT (surface, key) DO UPDATE SET entry = excluded.entry",
+            )
+            .bind(&self.surface)
+            .bind(key)
+            .bind(Json(entry))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let stored: Vec<(String,)> = sqlx::query_as("SELECT key FROM versions WHERE surface = ?")
+            .bind(&self.surface)
+            .fetch_all(&mut *tx)
+            .await?;
+        let keep: HashSet<&String> = versions.keys().collect();
+        let mut deleted = 0;
+        for (key,) in stored.iter().filter(|(key,)| !keep.contains(key)) {
+            deleted += sqlx::query("DELETE FROM versions WHERE surface = ? AND key = ?")
+                .bind(&self.surface)
+                .bind(key)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+
+        tx.commit().await?;
+        log_success(
+            "SQLITE",
+            &format!("Saved {} versions ({} removed)", versions.len(), deleted),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+//! Pulls a published versions file into the local store with a three-way
+//! merge. The base is the copy fetched by the previous sync, kept in
+//! `<versions_file>.sync-base.json`, so local edits (annotations, removed test
+//! builds) survive and upstream fixes still come through.
+
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, Surface};
+use crate::fetch;
+use crate::ignore;
+use crate::log::{log_info, log_success, log_warning};
+use crate::paths;
+use crate::record;
+use crate::store;
+use crate::Result;
+
+/// The dataset this repository publishes.
+pub const CANONICAL_URL: &str =
+    "https://raw.githubusercontent.com/LoaderSpot/web-versions/main/versions_web.json";
+
+pub fn base_path(versions_file: &Path) -> PathBuf {
+    let mut name = versions_file.file_name().unwrap_or_default().to_os_string();
+    name.push(".sync-base.json");
+    versions_file.with_file_name(name)
+}
+
+/// Parses a downloaded versions file, refusing anything that isn't a non-empty
+/// map of version keys to entry objects.
+pub fn validate(content: &str) -> Result<HashMap<String, Value>> {
+    let versions: HashMap<String, Value> = serde_json::from_str(content)
+        .map_err(|e| format!("Downloaded file is not a versions map: {}", e))?;
+    if versions.is_empty() {
+        return Err("Downloaded versions file is empty".into());
+    }
+    for (key, entry) in &versions {
+        let numeric = key
+            .split('.')
+            .next()
+            .is_some_and(|first| !first.is_empty() && first.chars().all(|c| c.is_ascii_digit()));
+        if !numeric {
+            return Err(
+                format!("Downloaded file has '{}', which is not a version key", key).into(),
+            );
+        }
+        if !entry.is_object() {
+            return Err(format!("Entry {} in the downloaded file is not an object", key).into());
+        }
+    }
+    Ok(versions)
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Merge {
+    pub versions: HashMap<String, Value>,
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+    /// Keys with fields changed differently on both sides; the local value was kept.
+    pub conflicts: Vec<String>,
+}
+
+/// Merges `remote` into `local` relative to `base`, entry by entry and, within
+/// entries, field by field. A side that left something as it was in `base`
+/// takes the other side's change; where both changed it differently, local wins.
+pub fn merge(
+    base: &HashMap<String, Value>,
+    local: &HashMap<String, Value>,
+    remote: &HashMap<String, Value>,
+) -> Merge {
+    let mut merged = Merge::default();
+    let mut all = local.clone();
+    all.extend(
+        remote
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.clone())),
+    );
+    all.extend(base.iter().map(|(key, entry)| (key.clone(), entry.clone())));
+
+    for key in store::sorted_keys(&all) {
+        let local_entry = local.get(&key);
+        let (entry, conflict) = merge_entry(base.get(&key), local_entry, remote.get(&key));
+        if conflict {
+            merged.conflicts.push(key.clone());
+        }
+        match (local_entry, &entry) {
+            (None, Some(_)) => merged.added.push(key.clone()),
+            (Some(_), None) => merged.removed.push(key.clone()),
+            (Some(old), Some(new)) if old != new => merged.updated.push(key.clone()),
+            _ => {}
+        }
+        if let Some(entry) = entry {
+            merged.versions.insert(key, entry);
+        }
+    }
+    merged
+}
+
+fn merge_entry(
+    base: Option<&Value>,
+    local: Option<&Value>,
+    remote: Option<&Value>,
+) -> (Option<Value>, bool) {
+    if local == remote || remote == base {
+        return (local.cloned(), false);
+    }
+    if local == base {
+        return (remote.cloned(), false);
+    }
+    let (Some(Value::Object(local)), Some(Value::Object(remote))) = (local, remote) else {
+        return (local.cloned(), true);
+    };
+    let empty = Map::new();
+    let base = base.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut fields = Map::new();
+    let mut conflict = false;
+    let mut names: Vec<&String> = local
+        .keys()
+        .chain(remote.keys())
+        .chain(base.keys())
+        .collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let (value, clash) = match (base.get(name), local.get(name), remote.get(name)) {
+            (base, local, remote) if local == remote || remote == base => (local, false),
+            (base, local, remote) if local == base => (remote, false),
+            (_, local, _) => (local, true),
+        };
+        conflict |= clash;
+        if let Some(value) = value {
+            fields.insert(name.clone(), value.clone());
+        }
+    }
+    (Some(Value::Object(fields)), conflict)
+}
+
+/// Downloads `from`, merges it into the surface's store and records it as the
+/// base of the next sync. Keys on the ignore list are never pulled in. With `dry_run` only the report is produced.
+pub async fn run(
+    config: &Config,
+    surface: &Surface<'_>,
+    from: &str,
+    dry_run: bool,
+) -> Result<Value> {
+    log_info("SYNC", &format!("Downloading {}", from));
+    let client = fetch::api_client(&config.http)?;
+    let body = record::send(&client, client.get(from).build()?)
+        .await?
+        .text()?;
+    let mut remote = validate(&body)?;
+    for key in ignore::load(surface.versions_file)? {
+        remote.remove(&key);
+    }
+
+    let base_path = base_path(surface.versions_file);
+    let base: HashMap<String, Value> = match paths::read_optional(&base_path)? {
+        Some(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse {}: {}", base_path.display(), e))?,
+        None => HashMap::new(),
+    };
+    let store = store::open(config, surface).await?;
+    let local = store.load().await?;
+
+    let merge = merge(&base, &local, &remote);
+    for key in &merge.conflicts {
+        log_warning(
+            "SYNC",
+            &format!("{} changed on both sides, kept the local fields", key),
+        );
+    }
+    let changed = !(merge.added.is_empty() && merge.updated.is_empty() && merge.removed.is_empty());
+    if !dry_run {
+        if changed {
+            store.save(&merge.versions).await?;
+        }
+        paths::write_atomic(&base_path, store::to_json(&remote)?.as_bytes())?;
+    }
+    log_success(
+        "SYNC",
+        &format!(
+            "{} {} added, {} updated, {} removed from {}",
+            if dry_run { "Would merge" } else { "Merged" },
+            merge.added.len(),
+            merge.updated.len(),
+            merge.removed.len(),
+            from
+        ),
+    );
+
+    Ok(json!({
+        "success": true,
+        "surface": surface.name,
+        "from": from,
+        "dry_run": dry_run,
+        "added": merge.added,
+        "updated": merge.updated,
+        "removed": merge.removed,
+        "conflicts": merge.conflicts,
+        "versions": merge.versions.len()
+    }))
+}
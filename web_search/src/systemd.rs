@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+/// Sends a state string such as `READY=1` to `$NOTIFY_SOCKET`.
+/// Returns false when not running under systemd or the send failed.
+#[cfg(unix)]
+pub fn notify(state: &str) -> bool {
+    use std::os::unix::net::UnixDatagram;
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return false;
+    };
+    let Ok(socket) = UnixDatagram::unbound() else {
+        return false;
+    };
+
+    if let Some(name) = path.to_str().and_then(|p| p.strip_prefix('@')) {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::linux::net::SocketAddrExt;
+            use std::os::unix::net::SocketAddr;
+            return SocketAddr::from_abstract_name(name.as_bytes())
+                .and_then(|addr| socket.send_to_addr(state.as_bytes(), &addr))
+                .is_ok();
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = name;
+            return false;
+        }
+    }
+
+    socket.send_to(state.as_bytes(), &path).is_ok()
+}
+
+#[cfg(not(unix))]
+pub fn notify(_state: &str) -> bool {
+    false
+}
+
+/// The watchdog timeout systemd expects pings within, if enabled for this process.
+pub fn watchdog_timeout() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok() != Some(std::process::id()) {
+            return None;
+        }
+    }
+    std::env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|usec| usec.parse::<u64>().ok())
+        .map(Duration::from_micros)
+}
@@ -0,0 +1,90 @@
+use crate::config::TelemetryConfig;
+use crate::Result;
+
+/// Keeps the OTLP exporters alive; dropping it flushes pending spans and metrics.
+#[cfg(feature = "otel")]
+pub struct Telemetry {
+    tracer_provider: opentelemetry_sdk::trace::TracerProvider,
+    meter_provider: opentelemetry_sdk::metrics::SdkMeterProvider,
+}
+
+#[cfg(not(feature = "otel"))]
+pub struct Telemetry;
+
+/// Installs the OTLP tracing subscriber if `otlp_endpoint` is configured.
+#[cfg(feature = "otel")]
+pub fn init(config: &TelemetryConfig) -> Result<Option<Telemetry>> {
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::trace::TracerProvider;
+    use opentelemetry_sdk::{runtime, Resource};
+    use tracing_opentelemetry::{MetricsLayer, OpenTelemetryLayer};
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    let Some(endpoint) = config.otlp_endpoint.as_deref() else {
+        return Ok(None);
+    };
+
+    let resource = Resource::new([KeyValue::new("service.name", config.service_name.clone())]);
+
+    let span_exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    let tracer_provider = TracerProvider::builder()
+        .with_batch_exporter(span_exporter, runtime::Tokio)
+        .with_resource(resource.clone())
+        .build();
+
+    let metric_exporter = opentelemetry_otlp::MetricExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    let reader = PeriodicReader::builder(metric_exporter, runtime::Tokio).build();
+    let meter_provider = SdkMeterProvider::builder()
+        .with_reader(reader)
+        .with_resource(resource)
+        .build();
+
+    tracing_subscriber::registry()
+        .with(OpenTelemetryLayer::new(
+            tracer_provider.tracer(config.service_name.clone()),
+        ))
+        .with(MetricsLayer::new(meter_provider.clone()))
+        .try_init()?;
+
+    crate::log::log_info(
+        "OTEL",
+        &format!("Exporting traces and metrics to {}", endpoint),
+    );
+    Ok(Some(Telemetry {
+        tracer_provider,
+        meter_provider,
+    }))
+}
+
+#[cfg(not(feature = "otel"))]
+pub fn init(config: &TelemetryConfig) -> Result<Option<Telemetry>> {
+    if config.otlp_endpoint.is_some() {
+        crate::log::log_warning(
+            "OTEL",
+            "otlp_endpoint is set but this build lacks the `otel` feature, not exporting",
+        );
+    }
+    Ok(None)
+}
+
+#[cfg(feature = "otel")]
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Err(e) = self.tracer_provider.shutdown() {
+            crate::log::log_warning("OTEL", &format!("Failed to flush traces: {}", e));
+        }
+        if let Err(e) = self.meter_provider.shutdown() {
+            crate::log::log_warning("OTEL", &format!("Failed to flush metrics: {}", e));
+        }
+    }
+}
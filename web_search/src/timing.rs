@@ -0,0 +1,70 @@
+use serde_json::{json, Map, Value};
+use std::time::{Duration, Instant};
+
+/// Wall-clock time spent in each phase of a check, plus HTTP body bytes received.
+#[derive(Debug, Default)]
+pub struct Timings {
+    phases: Vec<(&'static str, Duration)>,
+    bytes: u64,
+}
+
+impl Timings {
+    /// Adds the time since `started` to `phase`; a phase may be timed more than once.
+    pub fn record(&mut self, phase: &'static str, started: Instant) {
+        let elapsed = started.elapsed();
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += elapsed,
+            None => self.phases.push((phase, elapsed)),
+        }
+    }
+
+    pub fn add_bytes(&mut self, bytes: usize) {
+        self.bytes += bytes as u64;
+    }
+
+    pub fn to_json(&self, total: Duration) -> Value {
+        let phases: Map<String, Value> = self
+            .phases
+            .iter()
+            .map(|(name, elapsed)| (name.to_string(), json!(millis(*elapsed))))
+            .collect();
+        json!({
+            "phases_ms": phases,
+            "total_ms": millis(total),
+            "bytes": self.bytes
+        })
+    }
+
+    /// One-line human form, e.g. `user_agent 120 ms, page 450 ms, total 610 ms, 512.3 KiB received`.
+    pub fn summary(&self, total: Duration) -> String {
+        let mut parts: Vec<String> = self
+            .phases
+            .iter()
+            .map(|(name, elapsed)| format!("{} {} ms", name, millis(*elapsed)))
+            .collect();
+        parts.push(format!("total {} ms", millis(total)));
+        parts.push(format!("{} received", format_bytes(self.bytes)));
+        parts.join(", ")
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{:.1} {}", value, unit)
+}
@@ -0,0 +1,53 @@
+use serde_json::{json, Value};
+
+use crate::config::TokenConfig;
+use crate::extract;
+use crate::log::{log_info, log_success, log_warning};
+use crate::record;
+use crate::version::version_key;
+use crate::Result;
+
+/// The client build the anonymous token endpoint reports, if it names one.
+pub async fn fetch_version(
+    config: &TokenConfig,
+    client: &reqwest::Client,
+) -> Result<Option<String>> {
+    log_info("TOKEN", &format!("Requesting {}", config.url));
+    let body = record::send(client, client.get(&config.url).build()?)
+        .await?
+        .text()?;
+    let document: Value = serde_json::from_str(&body)
+        .map_err(|e| format!("Token endpoint did not return JSON: {}", e))?;
+    Ok(extract::field(&document, &config.version_field).map(str::to_string))
+}
+
+/// Compares the token endpoint's build with the page's `clientVersion`, by
+/// version key so a build suffix on only one side still matches.
+pub fn cross_check(url: &str, reported: Option<&str>, client_version: &str) -> Value {
+    let status = match reported {
+        None => {
+            log_warning("TOKEN", "No client version in the token response");
+            "not_found"
+        }
+        Some(reported) if version_key(reported) == version_key(client_version) => {
+            log_success("TOKEN", &format!("Token endpoint agrees on {}", reported));
+            "match"
+        }
+        Some(reported) => {
+            log_warning(
+                "TOKEN",
+                &format!(
+                    "Version mismatch: appServerConfig has {}, token endpoint has {}",
+                    client_version, reported
+                ),
+            );
+            "mismatch"
+        }
+    };
+
+    let mut check = json!({ "status": status, "url": url });
+    if let Some(reported) = reported {
+        check["version"] = json!(reported);
+    }
+    check
+}
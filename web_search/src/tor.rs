@@ -0,0 +1,91 @@
+use serde_json::Value;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use crate::config::TorConfig;
+use crate::log::{log_info, log_success, log_warning};
+use crate::Result;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Blocked checks in a row in this process.
+static BLOCKED_STREAK: AtomicU32 = AtomicU32::new(0);
+
+/// Counts consecutive blocked checks and asks Tor for a new circuit once
+/// `newnym_after` is reached.
+pub async fn record(config: &TorConfig, output: &Value) {
+    if config.control_addr.is_none() || config.newnym_after == 0 {
+        return;
+    }
+    if output["blocked"] != true {
+        BLOCKED_STREAK.store(0, Ordering::Relaxed);
+        return;
+    }
+
+    let streak = BLOCKED_STREAK.fetch_add(1, Ordering::Relaxed) + 1;
+    if streak < config.newnym_after {
+        log_info(
+            "TOR",
+            &format!(
+                "Blocked {} of {} times in a row",
+                streak, config.newnym_after
+            ),
+        );
+        return;
+    }
+
+    BLOCKED_STREAK.store(0, Ordering::Relaxed);
+    match new_identity(config).await {
+        Ok(()) => log_success(
+            "TOR",
+            &format!("Blocked {} times in a row, requested a new circuit", streak),
+        ),
+        Err(e) => log_warning("TOR", &format!("NEWNYM failed: {}", e)),
+    }
+}
+
+/// Sends `SIGNAL NEWNYM` on the control port, so the next connections use a new circuit.
+pub async fn new_identity(config: &TorConfig) -> Result<()> {
+    let addr = config
+        .control_addr
+        .as_deref()
+        .ok_or("[tor] control_addr is not set")?;
+    let auth = match (&config.cookie_file, &config.control_password) {
+        (Some(path), _) => {
+            let cookie = std::fs::read(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            cookie.iter().map(|byte| format!("{:02x}", byte)).collect()
+        }
+        (None, Some(password)) => format!(
+            "\"{}\"",
+            password.replace('\\', "\\\\").replace('"', "\\\"")
+        ),
+        (None, None) => String::new(),
+    };
+
+    tokio::time::timeout(TIMEOUT, async {
+        let stream = TcpStream::connect(addr).await?;
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        for command in [
+            format!("AUTHENTICATE {}", auth),
+            "SIGNAL NEWNYM".to_string(),
+        ] {
+            writer
+                .write_all(format!("{}\r\n", command.trim_end()).as_bytes())
+                .await?;
+            let reply = lines.next_line().await?.unwrap_or_default();
+            if !reply.starts_with("250") {
+                let verb = command.split(' ').next().unwrap_or_default();
+                return Err(format!("{} refused: {}", verb, reply).into());
+            }
+        }
+        let _ = writer.write_all(b"QUIT\r\n").await;
+        Ok::<_, crate::Error>(())
+    })
+    .await
+    .map_err(|_| format!("Tor control port {} timed out", addr))?
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Segment {
+    Number(u64),
+    Text(String),
+}
+
+impl Segment {
+    fn parse(part: &str) -> Self {
+        match part.parse() {
+            Ok(n) => Segment::Number(n),
+            Err(_) => Segment::Text(part.to_string()),
+        }
+    }
+}
+
+/// A dotted version such as `1.2.86.316` or `1.2.86.316.gcd065fc0`.
+///
+/// Segments compare numerically, missing trailing segments count as `0`, and
+/// text segments sort after numbers. Versions that are only numerically equal
+/// (`1.2` and `1.2.0`) fall back to comparing the raw strings, so the order is
+/// total and consistent with `Eq`.
+#[derive(Debug, Clone)]
+pub struct WebVersion {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+impl WebVersion {
+    pub fn new(raw: &str) -> Self {
+        WebVersion {
+            raw: raw.to_string(),
+            segments: raw.split('.').map(Segment::parse).collect(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+fn segment_cmp(a: Option<&Segment>, b: Option<&Segment>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(a), None) => a.cmp(&Segment::Number(0)),
+        (None, Some(b)) => Segment::Number(0).cmp(b),
+        (None, None) => Ordering::Equal,
+    }
+}
+
+impl Ord for WebVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.segments.len().max(other.segments.len());
+        (0..len)
+            .map(|i| segment_cmp(self.segments.get(i), other.segments.get(i)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| self.raw.cmp(&other.raw))
+    }
+}
+
+impl PartialOrd for WebVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for WebVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Eq for WebVersion {}
+
+impl Hash for WebVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state);
+    }
+}
+
+impl fmt::Display for WebVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl From<&str> for WebVersion {
+    fn from(raw: &str) -> Self {
+        WebVersion::new(raw)
+    }
+}
+
+/// Orders version strings newest first, the order used in `versions_web.json`.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    WebVersion::new(b).cmp(&WebVersion::new(a))
+}
+
+/// The store key for a clientVersion: its first four segments.
+pub fn version_key(client_version: &str) -> String {
+    client_version
+        .split('.')
+        .take(4)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// The store key for a build first seen at `time`: `2026.03.15.094512`, which
+/// sorts like the versions it stands in for.
+pub fn first_seen_key(time: DateTime<Utc>) -> String {
+    time.format("%Y.%m.%d.%H%M%S").to_string()
+}
+
+/// Whether `version` starts with the whole segments of `prefix`: `1.2.5`
+/// matches `1.2.5` and `1.2.5.40`, not `1.2.55`. A trailing dot (`1.2.`) is
+/// ignored.
+pub fn has_prefix(version: &str, prefix: &str) -> bool {
+    let mut segments = version.split('.');
+    prefix
+        .trim_end_matches('.')
+        .split('.')
+        .all(|part| segments.next() == Some(part))
+}
+
+/// Whether `version` lies between `since` and `until`, both inclusive. A bound
+/// covers every build under it, so an `until` of `1.2.60` keeps `1.2.60.5`.
+pub fn in_range(version: &str, since: Option<&str>, until: Option<&str>) -> bool {
+    let parsed = WebVersion::new(version);
+    let after = |bound: &str| parsed >= WebVersion::new(bound) || has_prefix(version, bound);
+    let before = |bound: &str| parsed <= WebVersion::new(bound) || has_prefix(version, bound);
+    since.is_none_or(after) && until.is_none_or(before)
+}
+
+/// One version as it is stored: the key plus the entry saved under it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionEntry {
+    /// Store key, e.g. `1.2.86.316`.
+    pub key: String,
+    /// `clientVersion`, `buildDate` and whatever else is recorded.
+    pub data: Value,
+}
@@ -0,0 +1,231 @@
+use chrono::{Local, Utc};
+use std::time::{Duration, Instant};
+
+use crate::cadence::Cadence;
+use crate::check;
+use crate::config::{Config, Surface};
+use crate::cron::CronSchedule;
+use crate::healthcheck;
+use crate::lock::{lock_path, InstanceLock};
+use crate::log::{log_error, log_info, log_success, log_warning};
+use crate::output::OutputWriter;
+use crate::pace;
+use crate::result::CheckResult;
+use crate::results;
+use crate::shutdown;
+use crate::store;
+use crate::systemd;
+use crate::Result;
+
+#[derive(Debug, Default)]
+struct Summary {
+    checks: u64,
+    new_versions: u64,
+    failures: u64,
+}
+
+pub async fn run(
+    config: &Config,
+    surface: &Surface<'_>,
+    force: bool,
+    out: &OutputWriter,
+) -> Result<()> {
+    let schedule: Option<CronSchedule> =
+        config.watch.cron.as_deref().map(str::parse).transpose()?;
+    let _lock = InstanceLock::acquire(&lock_path(surface.versions_file), force)?;
+    let interval = Duration::from_secs(config.watch.interval_secs);
+    let started = Instant::now();
+    let mut summary = Summary::default();
+
+    match &config.watch.cron {
+        Some(expression) => log_info(
+            "WATCH",
+            &format!("Watching {} on cron '{}'", surface.url, expression),
+        ),
+        None => log_info(
+            "WATCH",
+            &format!(
+                "Watching {} every {} s",
+                surface.url, config.watch.interval_secs
+            ),
+        ),
+    }
+
+    if let Some(timeout) = systemd::watchdog_timeout() {
+        if timeout <= interval {
+            log_warning(
+                "SYSTEMD",
+                &format!(
+                    "WatchdogSec ({} s) is not longer than the interval ({} s); the service will be restarted between checks",
+                    timeout.as_secs(),
+                    interval.as_secs()
+                ),
+            );
+        }
+    }
+    if systemd::notify("READY=1") {
+        log_info("SYSTEMD", "Notified readiness");
+    }
+
+    let shutdown = shutdown::signal();
+    tokio::pin!(shutdown);
+    let mut stopping = false;
+
+    loop {
+        let check = check::run_surface(config, surface);
+        tokio::pin!(check);
+
+        let result = tokio::select! {
+            result = &mut check => result,
+            _ = &mut shutdown => {
+                log_info("WATCH", "Shutdown requested, finishing the current check...");
+                stopping = true;
+                check.await
+            }
+        };
+
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                log_error("WATCH", &format!("Check failed: {}", e));
+                CheckResult::from_error(e.as_ref()).to_value()
+            }
+        };
+        summary.checks += 1;
+        if output["success"] != true {
+            summary.failures += 1;
+        } else {
+            if output["is_new"] == true {
+                summary.new_versions += 1;
+            }
+            systemd::notify(&format!(
+                "WATCHDOG=1\nSTATUS={} checks, {} new, {} failures",
+                summary.checks, summary.new_versions, summary.failures
+            ));
+        }
+        out.emit(&output)?;
+        healthcheck::report(&config.healthcheck, &output).await;
+        results::deliver(&config.results, &config.http, &output).await;
+
+        if stopping {
+            break;
+        }
+
+        let next = next_delay(config, surface, schedule.as_ref(), interval).await;
+        log_info("WATCH", &format!("Next check in {} s", next.as_secs()));
+
+        tokio::select! {
+            _ = tokio::time::sleep(next) => {}
+            _ = &mut shutdown => {
+                log_info("WATCH", "Shutdown requested");
+                break;
+            }
+        }
+    }
+
+    systemd::notify("STOPPING=1");
+    log_success(
+        "WATCH",
+        &format!(
+            "Stopped after {} s: {} checks, {} new versions, {} failures",
+            started.elapsed().as_secs(),
+            summary.checks,
+            summary.new_versions,
+            summary.failures
+        ),
+    );
+    Ok(())
+}
+
+/// Watches every enabled surface in `[sources]` side by side, each with its overrides
+/// applied. Runs until shutdown; a surface that cannot start is reported and
+/// the others keep going.
+pub async fn run_all(config: &Config, force: bool, out: &OutputWriter) -> Result<()> {
+    let names = config.enabled_sources();
+    if names.is_empty() {
+        return Err("watch --all needs at least one enabled [sources.<surface>] table".into());
+    }
+    if let Some(unknown) = names.iter().find(|name| config.surface(name).is_none()) {
+        return Err(format!(
+            "Unknown surface '{}' in [sources], expected one of {}",
+            unknown,
+            config.surface_names().join(", ")
+        )
+        .into());
+    }
+
+    let mut watches = tokio::task::JoinSet::new();
+    for name in names {
+        let config = config.for_source(name);
+        let name = name.to_string();
+        let out = OutputWriter::new(out.format());
+        watches.spawn(async move {
+            let surface = config.surface(&name).expect("checked above");
+            let result = run(&config, &surface, force, &out).await;
+            (name, result.map_err(|e| e.to_string()))
+        });
+    }
+
+    let mut failed = Vec::new();
+    while let Some(joined) = watches.join_next().await {
+        let (name, result) = joined.map_err(|e| format!("Watch task failed: {}", e))?;
+        if let Err(e) = result {
+            log_error("WATCH", &format!("{}: {}", name, e));
+            failed.push(name);
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!("Watching {} failed", failed.join(", ")).into());
+    }
+    Ok(())
+}
+
+/// Time until the next check: the cron schedule if there is one, else the
+/// adaptive or fixed interval with `[watch] jitter_secs` applied.
+pub async fn next_delay(
+    config: &Config,
+    surface: &Surface<'_>,
+    schedule: Option<&CronSchedule>,
+    interval: Duration,
+) -> Duration {
+    match schedule {
+        Some(schedule) => until_next_run(schedule).unwrap_or(interval),
+        None => pace::jitter(
+            next_interval(config, surface, interval).await,
+            Duration::from_secs(config.watch.jitter_secs),
+        ),
+    }
+}
+
+async fn next_interval(config: &Config, surface: &Surface<'_>, interval: Duration) -> Duration {
+    let adaptive = &config.watch.adaptive;
+    if !adaptive.enabled {
+        return interval;
+    }
+
+    let loaded = match store::open(config, surface).await {
+        Ok(store) => store.load().await,
+        Err(e) => Err(e),
+    };
+    let versions = match loaded {
+        Ok(versions) => versions,
+        Err(e) => {
+            log_warning("WATCH", &format!("Adaptive interval unavailable: {}", e));
+            return interval;
+        }
+    };
+
+    Cadence::from_versions(&versions)
+        .interval_at(
+            &Utc::now(),
+            Duration::from_secs(adaptive.min_interval_secs),
+            Duration::from_secs(adaptive.max_interval_secs),
+        )
+        .unwrap_or(interval)
+}
+
+fn until_next_run(schedule: &CronSchedule) -> Option<Duration> {
+    let now = Local::now();
+    let next = schedule.next_after(&now)?;
+    (next - now).to_std().ok()
+}
@@ -0,0 +1,100 @@
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+use web_search::anomaly::{inspect, is_client_version};
+use web_search::config::{AnomalyConfig, Config};
+
+#[test]
+fn client_version_shape() {
+    assert!(is_client_version("1.2.86.316.gcd065fc0"));
+    assert!(!is_client_version("1.2.86.316"));
+    assert!(!is_client_version("1.2.86.x.gcd065fc0"));
+    assert!(!is_client_version("1.2.86.316.cd065fc0"));
+    assert!(!is_client_version("<html>"));
+}
+
+#[test]
+fn inspect_flags_dates_and_backward_jumps() {
+    let config = AnomalyConfig::default();
+    let defaults = Config::default();
+    let web = defaults.surface("web").unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 3, 16, 12, 0, 0).unwrap();
+    let entry = |date: &str| json!({"clientVersion": "1.2.86.316.gcd065fc0", "buildDate": date});
+
+    assert!(inspect(
+        &config,
+        &web,
+        "1.2.86.316",
+        &entry("2026-03-15"),
+        Some("1.2.86.300"),
+        now
+    )
+    .is_empty());
+    assert!(inspect(&config, &web, "1.2.86.316", &entry("2026-03-15"), None, now).is_empty());
+    // Slightly older versions turn up when a rollout is reverted
+    assert!(inspect(
+        &config,
+        &web,
+        "1.2.84.10",
+        &entry("2026-03-15"),
+        Some("1.2.86.316"),
+        now
+    )
+    .is_empty());
+
+    assert_eq!(
+        inspect(&config, &web, "1.2.86.316", &entry("2026-04-01"), None, now).len(),
+        1
+    );
+    assert_eq!(
+        inspect(&config, &web, "1.2.86.316", &entry("2020-01-01"), None, now).len(),
+        1
+    );
+    assert_eq!(
+        inspect(
+            &config,
+            &web,
+            "1.2.70.1",
+            &entry("2026-03-15"),
+            Some("1.2.86.316"),
+            now
+        )
+        .len(),
+        1
+    );
+    assert_eq!(
+        inspect(
+            &config,
+            &web,
+            "1.1.99.1",
+            &entry("2026-03-15"),
+            Some("1.2.86.316"),
+            now
+        )
+        .len(),
+        1
+    );
+}
+
+#[test]
+fn other_services_only_get_the_build_time_checked() {
+    let config = AnomalyConfig::default();
+    let defaults = Config::default();
+    let youtube = defaults.surface("youtube-music").unwrap();
+    let now = Utc.with_ymd_and_hms(2026, 3, 16, 12, 0, 0).unwrap();
+    let entry = json!({"clientVersion": "1.20260310.01.00"});
+
+    assert!(inspect(
+        &config,
+        &youtube,
+        "1.20260310.01.00",
+        &entry,
+        Some("2.20260101.00.00"),
+        now
+    )
+    .is_empty());
+    let stale = json!({"clientVersion": "8.34.0", "buildDate": "2020-01-01"});
+    assert_eq!(
+        inspect(&config, &youtube, "8.34.0", &stale, None, now).len(),
+        1
+    );
+}
@@ -0,0 +1,927 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use web_search::bundle;
+use web_search::check;
+use web_search::config::{
+    AnomalyConfig, BackupConfig, BundleConfig, ChangelogConfig, Compression, Config, Decode,
+    EventsConfig, ExtractConfig, HttpConfig, PublishConfig, PublishMethod, SurfaceConfig,
+    TokenConfig,
+};
+use web_search::events::VersionEvent;
+use web_search::fetch::{self, OffSite};
+use web_search::ignore;
+use web_search::notify;
+use web_search::pace;
+use web_search::result::{self, CheckError, CheckResult, ErrorKind};
+use web_search::scraper::ScraperBuilder;
+use web_search::store::MemoryStore;
+use web_search::Scraper;
+use wiremock::matchers::{body_partial_json, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+async fn spotify_mock(fixture_name: &str, status: u16) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(status).set_body_string(fixture(fixture_name)))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/user-agents.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!(["Mozilla/5.0 (test)"])))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn config_for(server: &MockServer, dir: &Path) -> Config {
+    Config {
+        spotify_url: server.uri(),
+        user_agent_api: format!("{}/user-agents.json", server.uri()),
+        versions_file: dir.join("versions_web.json"),
+        failures_dir: dir.join("failures"),
+        bundle: BundleConfig {
+            cross_check: false,
+            ..BundleConfig::default()
+        },
+        changelog: ChangelogConfig {
+            enabled: true,
+            path: dir.join("CHANGELOG.md"),
+        },
+        events: EventsConfig {
+            enabled: true,
+            path: dir.join("events.jsonl"),
+        },
+        backup: BackupConfig {
+            dir: dir.join("backups"),
+            ..BackupConfig::default()
+        },
+        // The fixtures' build date only gets older
+        anomaly: AnomalyConfig {
+            max_age_days: 365 * 100,
+            quarantine_file: dir.join("quarantine.jsonl"),
+            ..AnomalyConfig::default()
+        },
+        ..Config::default()
+    }
+}
+
+#[tokio::test]
+async fn selector_path_detects_and_saves_new_version() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let config = config_for(&server, dir.path());
+
+    let output = check::run(&config).await.unwrap();
+
+    assert_eq!(output["success"], true);
+    assert_eq!(output["is_new"], true);
+    assert_eq!(output["key"], "1.2.86.316");
+    assert_eq!(output["data"]["clientVersion"], "1.2.86.316.gcd065fc0");
+    assert_eq!(output["data"]["buildDate"], "2026-03-15");
+    assert!(output["data"]["firstSeen"].is_string());
+    assert_eq!(
+        output["data"]["webPlayer"],
+        "https://open.spotifycdn.com/cdn/build/web-player/web-player.ca73afa1.js"
+    );
+
+    let saved: Value =
+        serde_json::from_str(&std::fs::read_to_string(&config.versions_file).unwrap()).unwrap();
+    assert_eq!(saved["1.2.86.316"], output["data"]);
+}
+
+#[tokio::test]
+async fn result_fields_are_stable() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let config = config_for(&server, dir.path());
+
+    let output = check::run(&config).await.unwrap();
+
+    // Renaming or removing any of these breaks downstream parsers
+    let fields: Vec<&str> = output
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    for field in [
+        "apiVersion",
+        "success",
+        "is_new",
+        "surface",
+        "key",
+        "data",
+        "message",
+        "timings",
+    ] {
+        assert!(
+            fields.contains(&field),
+            "{} missing from {:?}",
+            field,
+            fields
+        );
+    }
+    assert_eq!(output["apiVersion"], result::API_VERSION);
+
+    let parsed: CheckResult = serde_json::from_value(output.clone()).unwrap();
+    assert_eq!(parsed.to_value(), output);
+}
+
+#[tokio::test]
+async fn known_version_is_not_saved_twice() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let config = config_for(&server, dir.path());
+
+    check::run(&config).await.unwrap();
+    let output = check::run(&config).await.unwrap();
+
+    assert_eq!(output["success"], true);
+    assert_eq!(output["is_new"], false);
+    assert_eq!(output["key"], "1.2.86.316");
+
+    let changelog = std::fs::read_to_string(dir.path().join("CHANGELOG.md")).unwrap();
+    assert_eq!(changelog.matches("## 1.2.86.316").count(), 1);
+    assert!(changelog.contains("`ca73afa1`"));
+
+    let events = std::fs::read_to_string(dir.path().join("events.jsonl")).unwrap();
+    let lines: Vec<Value> = events
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["event"], "new_version");
+    assert_eq!(lines[0]["key"], "1.2.86.316");
+    assert_eq!(lines[0]["surface"], "web");
+}
+
+#[tokio::test]
+async fn ignored_versions_are_never_saved() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let config = config_for(&server, dir.path());
+
+    assert!(ignore::add(&config.versions_file, "1.2.86.316").unwrap());
+    assert!(!ignore::add(&config.versions_file, "1.2.86.316").unwrap());
+    let output = check::run(&config).await.unwrap();
+
+    assert_eq!(output["success"], true);
+    assert_eq!(output["is_new"], false);
+    assert_eq!(output["message"], "Version 1.2.86.316 is ignored");
+    assert!(!config.versions_file.exists());
+
+    assert!(ignore::remove(&config.versions_file, "1.2.86.316").unwrap());
+    assert!(ignore::load(&config.versions_file).unwrap().is_empty());
+    let output = check::run(&config).await.unwrap();
+    assert_eq!(output["is_new"], true);
+}
+
+#[tokio::test]
+async fn new_versions_are_published_with_auth() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    Mock::given(method("POST"))
+        .and(path("/hub/web"))
+        .and(header("authorization", "Bearer hub-token"))
+        .and(header("user-agent", "hub-test"))
+        .and(body_partial_json(
+            json!({ "surface": "web", "key": "1.2.86.316" }),
+        ))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let mut config = Config {
+        publish: PublishConfig {
+            url: Some(format!("{}/hub/{{surface}}", server.uri())),
+            method: PublishMethod::Post,
+            token: Some("hub-token".to_string()),
+            ..PublishConfig::default()
+        },
+        ..config_for(&server, dir.path())
+    };
+    config.http.user_agent = Some("hub-test".to_string());
+
+    let output = check::run(&config).await.unwrap();
+    assert_eq!(output["is_new"], true);
+    // Known versions are not published again
+    check::run(&config).await.unwrap();
+}
+
+#[tokio::test]
+async fn regex_fallback_finds_tag_missed_by_selectors() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("regex_fallback.html", 200).await;
+    let config = config_for(&server, dir.path());
+
+    let output = check::run(&config).await.unwrap();
+
+    assert_eq!(output["success"], true);
+    assert_eq!(output["key"], "1.2.86.316");
+}
+
+#[tokio::test]
+async fn empty_tag_is_reported() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("empty_tag.html", 200).await;
+    let config = config_for(&server, dir.path());
+
+    let output = check::run(&config).await.unwrap();
+
+    assert_eq!(output["success"], false);
+    assert_eq!(output["error"]["kind"], "extraction");
+    assert_eq!(output["error"]["message"], "Base64 content is empty");
+    assert_eq!(output["error"]["retryable"], false);
+    assert!(!config.versions_file.exists());
+}
+
+#[tokio::test]
+async fn blocked_page_is_reported() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("blocked.html", 403).await;
+    let config = config_for(&server, dir.path());
+
+    let output = check::run(&config).await.unwrap();
+
+    assert_eq!(output["success"], false);
+    assert_eq!(
+        output["error"],
+        json!({
+            "kind": "blocked",
+            "message": "HTTP 403: appServerConfig tag not found",
+            "retryable": false,
+            "http_status": 403
+        })
+    );
+    assert_eq!(output["blocked"], true);
+    assert!(!config.versions_file.exists());
+
+    let snapshot = std::fs::read_to_string(output["snapshot"].as_str().unwrap()).unwrap();
+    assert!(snapshot.contains("status: 403"));
+    assert!(snapshot.contains("Access denied"));
+}
+
+#[tokio::test]
+async fn memory_store_keeps_versions_off_disk() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let config = config_for(&server, dir.path());
+    let surface = config.surface("web").unwrap();
+
+    let seed =
+        r#"{"1.2.85.519": {"buildDate": "2026-03-01", "clientVersion": "1.2.85.519.g1a2b3c4d"}}"#;
+    let store = MemoryStore::from_reader(seed.as_bytes()).unwrap();
+
+    let output = check::run_with_store(&config, &surface, &store)
+        .await
+        .unwrap();
+    assert_eq!(output["is_new"], true);
+    assert_eq!(store.versions().len(), 2);
+    assert!(!config.versions_file.exists());
+
+    let output = check::run_with_store(&config, &surface, &store)
+        .await
+        .unwrap();
+    assert_eq!(output["is_new"], false);
+}
+
+#[tokio::test]
+async fn extra_surfaces_are_stored_apart_and_tagged() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let mut config = config_for(&server, dir.path());
+    config.surfaces.push(SurfaceConfig {
+        name: "lite".to_string(),
+        url: server.uri(),
+        ..SurfaceConfig::default()
+    });
+    config.data_dir = dir.path().to_path_buf();
+    config.resolve_paths();
+    let surface = config.surface("lite").unwrap();
+    assert_eq!(surface.versions_file, dir.path().join("versions_lite.json"));
+
+    let store = MemoryStore::default();
+    let output = check::run_with_store(&config, &surface, &store)
+        .await
+        .unwrap();
+    assert_eq!(output["surface"], "lite");
+    assert_eq!(store.versions()["1.2.86.316"]["surface"], "lite");
+
+    let web = MemoryStore::default();
+    check::run_with_store(&config, &config.surface("web").unwrap(), &web)
+        .await
+        .unwrap();
+    assert!(web.versions()["1.2.86.316"].get("surface").is_none());
+}
+
+#[tokio::test]
+async fn token_endpoint_mismatch_is_flagged_and_kept() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    Mock::given(method("GET"))
+        .and(path("/get_access_token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "accessToken": "anonymous",
+            "isAnonymous": true,
+            "clientVersion": "1.2.87.120.g0a1b2c3d"
+        })))
+        .mount(&server)
+        .await;
+    let mut config = config_for(&server, dir.path());
+    config.token = TokenConfig {
+        enabled: true,
+        url: format!("{}/get_access_token", server.uri()),
+        ..TokenConfig::default()
+    };
+
+    let store = MemoryStore::default();
+    let output = check::run_with_store(&config, &config.surface("web").unwrap(), &store)
+        .await
+        .unwrap();
+    assert_eq!(output["success"], true);
+    assert_eq!(output["token_check"]["status"], "mismatch");
+    assert_eq!(output["token_check"]["version"], "1.2.87.120.g0a1b2c3d");
+    assert_eq!(
+        store.versions()["1.2.86.316"]["tokenClientVersion"],
+        "1.2.87.120.g0a1b2c3d"
+    );
+}
+
+#[tokio::test]
+async fn plain_json_profiles_are_saved_end_to_end() {
+    let dir = TempDir::new().unwrap();
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/deezer"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            r#"<html><body><script>window.__APP_STATE__ = {"app": {"version": "8.34.0", "built": "2026-03-02"}}</script></body></html>"#,
+        ))
+        .mount(&server)
+        .await;
+    let mut config = config_for(&server, dir.path());
+    config.surfaces.push(SurfaceConfig {
+        name: "deezer".to_string(),
+        url: format!("{}/deezer", server.uri()),
+        versions_file: dir.path().join("versions_deezer.json"),
+        extract: ExtractConfig {
+            selectors: Vec::new(),
+            regex: r"window\.__APP_STATE__\s*=\s*(\{.*\})</script>".to_string(),
+            decode: Decode::None,
+            client_version_field: "/app/version".to_string(),
+            build_date_field: "/app/built".to_string(),
+            build_version_field: String::new(),
+            bundle_marker: String::new(),
+            ..ExtractConfig::default()
+        },
+    });
+
+    // [anomaly] stays on: a version without Spotify's shape is still saved
+    let store = MemoryStore::default();
+    let output = check::run_with_store(&config, &config.surface("deezer").unwrap(), &store)
+        .await
+        .unwrap();
+    assert_eq!(output["success"], true, "{output}");
+    assert_eq!(output["is_new"], true);
+    assert_eq!(output["key"], "8.34.0");
+    let saved = &store.versions()["8.34.0"];
+    assert_eq!(saved["clientVersion"], "8.34.0");
+    assert_eq!(saved["buildDate"], "2026-03-02");
+    assert_eq!(saved["surface"], "deezer");
+}
+
+#[tokio::test]
+async fn youtube_music_versions_are_saved_end_to_end() {
+    let dir = TempDir::new().unwrap();
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/youtube"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(fixture("youtube_music.html")))
+        .mount(&server)
+        .await;
+    let mut config = config_for(&server, dir.path());
+    config.youtube_music.url = format!("{}/youtube", server.uri());
+    config.youtube_music.versions_file = dir.path().join("versions_youtube_music.json");
+
+    let store = MemoryStore::default();
+    let surface = config.surface("youtube-music").unwrap();
+    let output = check::run_with_store(&config, &surface, &store)
+        .await
+        .unwrap();
+    assert_eq!(output["success"], true, "{output}");
+    assert_eq!(output["is_new"], true);
+    assert_eq!(output["key"], "1.20260310.01.00");
+    let saved = &store.versions()["1.20260310.01.00"];
+    assert_eq!(saved["clientVersion"], "1.20260310.01.00");
+    assert_eq!(saved["buildVersion"], "1.20260310.01.00");
+    assert!(!dir.path().join("quarantine.jsonl").exists());
+
+    let output = check::run_with_store(&config, &surface, &store)
+        .await
+        .unwrap();
+    assert_eq!(output["is_new"], false);
+}
+
+#[tokio::test]
+async fn apple_music_builds_are_keyed_by_when_they_were_first_seen() {
+    let dir = TempDir::new().unwrap();
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/apple"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(fixture("apple_music.html")))
+        .mount(&server)
+        .await;
+    let mut config = config_for(&server, dir.path());
+    config.apple_music.url = format!("{}/apple", server.uri());
+    config.apple_music.versions_file = dir.path().join("versions_apple_music.json");
+
+    let store = MemoryStore::default();
+    let surface = config.surface("apple-music").unwrap();
+    let output = check::run_with_store(&config, &surface, &store)
+        .await
+        .unwrap();
+    assert_eq!(output["success"], true, "{output}");
+    assert_eq!(output["is_new"], true);
+    let key = output["key"].as_str().unwrap().to_string();
+    let widths: Vec<usize> = key.split('.').map(str::len).collect();
+    assert_eq!(widths, [4, 2, 2, 6], "{key}");
+    assert!(key.chars().all(|c| c == '.' || c.is_ascii_digit()), "{key}");
+    assert_eq!(store.versions()[&key]["clientVersion"], "8a3c2b1f0e");
+
+    let output = check::run_with_store(&config, &surface, &store)
+        .await
+        .unwrap();
+    assert_eq!(output["is_new"], false);
+    assert_eq!(output["key"], key.as_str());
+    assert_eq!(store.versions().len(), 1);
+}
+
+#[tokio::test]
+async fn token_endpoint_is_only_asked_about_spotify_players() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    Mock::given(method("GET"))
+        .and(path("/get_access_token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "clientVersion": "1.2.87.120.g0a1b2c3d"
+        })))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let mut config = config_for(&server, dir.path());
+    config.token = TokenConfig {
+        enabled: true,
+        url: format!("{}/get_access_token", server.uri()),
+        ..TokenConfig::default()
+    };
+    config.surfaces.push(SurfaceConfig {
+        name: "lite".to_string(),
+        url: server.uri(),
+        versions_file: dir.path().join("versions_lite.json"),
+        ..SurfaceConfig::default()
+    });
+
+    let store = MemoryStore::default();
+    let output = check::run_with_store(&config, &config.surface("lite").unwrap(), &store)
+        .await
+        .unwrap();
+    assert_eq!(output["success"], true);
+    assert!(output["token_check"].is_null());
+    assert!(store.versions()["1.2.86.316"]
+        .get("tokenClientVersion")
+        .is_none());
+}
+
+#[tokio::test]
+async fn scraper_latest_reads_without_saving() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let config = config_for(&server, dir.path());
+
+    let latest = Scraper::with_config(config.clone()).latest().await.unwrap();
+
+    assert_eq!(latest.key, "1.2.86.316");
+    assert_eq!(latest.entry["buildDate"], "2026-03-15");
+    assert!(!config.versions_file.exists());
+    assert!(!dir.path().join("events.jsonl").exists());
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn blocking_scraper_checks_outside_a_runtime() {
+    let dir = TempDir::new().unwrap();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let server = runtime.block_on(spotify_mock("selector.html", 200));
+    let config = config_for(&server, dir.path());
+
+    let scraper = web_search::blocking::Scraper::with_config(config).unwrap();
+    let output = scraper.check_with_store(&MemoryStore::new()).unwrap();
+
+    assert_eq!(output["is_new"], true);
+    assert_eq!(scraper.latest().unwrap().key, "1.2.86.316");
+}
+
+#[tokio::test]
+async fn builder_retries_server_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(fixture("selector.html")))
+        .mount(&server)
+        .await;
+
+    let scraper = Scraper::builder()
+        .url(server.uri())
+        .user_agent("Mozilla/5.0 (test)")
+        .timeout(Duration::from_secs(5))
+        .retry(1, Duration::from_millis(10))
+        .configure(|config| config.bundle.cross_check = false)
+        .build()
+        .unwrap();
+    let latest = scraper.latest().await.unwrap();
+
+    assert_eq!(latest.key, "1.2.86.316");
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn callbacks_and_subscribers_see_each_check() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let config = config_for(&server, dir.path());
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let (new_seen, unchanged_seen) = (seen.clone(), seen.clone());
+    let scraper = ScraperBuilder::from_config(config)
+        .on_new_version(move |key, _| new_seen.lock().unwrap().push(format!("new {}", key)))
+        .on_unchanged(move |key| unchanged_seen.lock().unwrap().push(format!("same {}", key)))
+        .build()
+        .unwrap();
+    let mut events = scraper.subscribe();
+
+    let store = MemoryStore::new();
+    scraper.check_with_store(&store).await.unwrap();
+    scraper.check_with_store(&store).await.unwrap();
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec!["new 1.2.86.316", "same 1.2.86.316"]
+    );
+    assert!(matches!(
+        events.recv().await.unwrap(),
+        VersionEvent::NewVersion { key, .. } if key == "1.2.86.316"
+    ));
+    assert!(matches!(
+        events.recv().await.unwrap(),
+        VersionEvent::Unchanged { surface, .. } if surface == "web"
+    ));
+}
+
+#[tokio::test]
+async fn redirect_chain_is_recorded() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    Mock::given(method("GET"))
+        .and(path("/intl-de/"))
+        .respond_with(ResponseTemplate::new(302).insert_header("Location", "/"))
+        .mount(&server)
+        .await;
+    let mut config = config_for(&server, dir.path());
+    config.spotify_url = format!("{}/intl-de/", server.uri());
+
+    let output = check::run(&config).await.unwrap();
+
+    assert_eq!(output["success"], true);
+    assert_eq!(output["key"], "1.2.86.316");
+    assert_eq!(output["redirects"][0]["status"], 302);
+    assert_eq!(output["redirects"][0]["to"], format!("{}/", server.uri()));
+}
+
+#[tokio::test]
+async fn redirect_to_another_host_is_not_parsed() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let elsewhere = server.uri().replace("127.0.0.1", "localhost");
+    Mock::given(method("GET"))
+        .and(path("/start"))
+        .respond_with(
+            ResponseTemplate::new(302).insert_header("Location", format!("{}/", elsewhere)),
+        )
+        .mount(&server)
+        .await;
+    let mut config = config_for(&server, dir.path());
+    config.spotify_url = format!("{}/start", server.uri());
+
+    let output = check::run(&config).await.unwrap();
+
+    assert_eq!(output["success"], false);
+    assert_eq!(output["redirect"], "offsite_redirect");
+    assert_eq!(output["redirects"].as_array().unwrap().len(), 1);
+    assert!(!config.versions_file.exists());
+}
+
+#[test]
+fn off_site_landings_are_classified() {
+    let requested = "https://open.spotify.com/";
+    assert_eq!(
+        fetch::off_site(requested, "https://open.spotify.com/intl-de/"),
+        None
+    );
+    assert_eq!(
+        fetch::off_site(
+            requested,
+            "https://accounts.spotify.com/en/login?continue=x"
+        ),
+        Some(OffSite::Login)
+    );
+    assert_eq!(
+        fetch::off_site(requested, "https://www.spotify.com/de/select-your-country/"),
+        Some(OffSite::Interstitial)
+    );
+    assert_eq!(
+        fetch::off_site(requested, "https://www.spotify.com/en-gb/"),
+        Some(OffSite::Interstitial)
+    );
+    assert_eq!(
+        fetch::off_site(requested, "https://example.com/blocked"),
+        Some(OffSite::Other)
+    );
+}
+
+#[test]
+fn http_knobs_are_validated_when_the_client_is_built() {
+    let dir = TempDir::new().unwrap();
+    let empty_bundle = dir.path().join("ca.pem");
+    std::fs::write(&empty_bundle, "not a certificate\n").unwrap();
+
+    for http in [
+        HttpConfig {
+            compression: vec!["lz4".to_string()],
+            ..HttpConfig::default()
+        },
+        HttpConfig {
+            compression: vec!["zstd".to_string()],
+            ..HttpConfig::default()
+        },
+        HttpConfig {
+            min_tls_version: Some("1.4".to_string()),
+            ..HttpConfig::default()
+        },
+        HttpConfig {
+            ca_bundle: Some(empty_bundle.clone()),
+            ..HttpConfig::default()
+        },
+    ] {
+        assert!(fetch::build_client("test", &http, false).is_err());
+    }
+
+    let http = HttpConfig {
+        compression: vec!["gzip".to_string(), "br".to_string()],
+        min_tls_version: Some("1.2".to_string()),
+        ..HttpConfig::default()
+    };
+    assert!(fetch::build_client("test", &http, false).is_ok());
+}
+
+#[tokio::test]
+async fn host_overrides_and_doh_replace_the_system_resolver() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let port = server.address().port();
+    let mut config = config_for(&server, dir.path());
+    config.spotify_url = format!("http://open.spotify.test:{}/", port);
+
+    config.http.hosts = [(
+        "open.spotify.test".to_string(),
+        vec!["127.0.0.1".parse().unwrap()],
+    )]
+    .into_iter()
+    .collect();
+    let output = check::run(&config).await.unwrap();
+    assert_eq!(output["key"], "1.2.86.316");
+
+    Mock::given(method("GET"))
+        .and(path("/dns-query"))
+        .and(query_param("name", "open.spotify.test"))
+        .and(query_param("type", "A"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "Status": 0,
+            "Answer": [{ "name": "open.spotify.test", "type": 1, "TTL": 60, "data": "127.0.0.1" }]
+        })))
+        .mount(&server)
+        .await;
+    config.http.hosts.clear();
+    config.http.doh_url = Some(format!("{}/dns-query", server.uri()));
+    let output = check::run(&config).await.unwrap();
+    assert_eq!(output["success"], true);
+    assert_eq!(output["key"], "1.2.86.316");
+}
+
+#[tokio::test]
+async fn tor_gets_a_new_circuit_after_repeated_blocks() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let tor = web_search::config::TorConfig {
+        control_addr: Some(listener.local_addr().unwrap().to_string()),
+        control_password: Some("secret".to_string()),
+        newnym_after: 2,
+        ..Default::default()
+    };
+    let control = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let mut commands = Vec::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            if line == "QUIT" {
+                break;
+            }
+            commands.push(line);
+            writer.write_all(b"250 OK\r\n").await.unwrap();
+        }
+        commands
+    });
+
+    let blocked = json!({ "success": false, "blocked": true });
+    web_search::tor::record(&tor, &blocked).await;
+    web_search::tor::record(&tor, &json!({ "success": true })).await;
+    web_search::tor::record(&tor, &blocked).await;
+    web_search::tor::record(&tor, &blocked).await;
+
+    assert_eq!(
+        control.await.unwrap(),
+        vec!["AUTHENTICATE \"secret\"", "SIGNAL NEWNYM"]
+    );
+}
+
+#[tokio::test]
+async fn deadline_cancels_a_hanging_check() {
+    let dir = TempDir::new().unwrap();
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(fixture("selector.html"))
+                .set_delay(Duration::from_secs(10)),
+        )
+        .mount(&server)
+        .await;
+    let mut config = config_for(&server, dir.path());
+    config.http.user_agent = Some("Mozilla/5.0 (test)".to_string());
+    config.deadline_secs = Some(1);
+
+    let started = std::time::Instant::now();
+    let output = check::run(&config).await.unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(output["success"], false);
+    assert_eq!(output["timed_out"], true);
+    assert!(!config.versions_file.exists());
+}
+
+#[tokio::test]
+async fn pacing_spaces_out_request_starts() {
+    let http = HttpConfig {
+        max_concurrency: 1,
+        request_delay_ms: 200,
+        ..HttpConfig::default()
+    };
+
+    let started = std::time::Instant::now();
+    for _ in 0..3 {
+        let permit = web_search::pace::wait(&http).await;
+        assert!(permit.is_some());
+    }
+
+    assert!(started.elapsed() >= Duration::from_millis(400));
+    assert!(web_search::pace::wait(&HttpConfig::default())
+        .await
+        .is_none());
+}
+
+#[test]
+fn jitter_stays_within_the_spread() {
+    let base = Duration::from_secs(300);
+    let spread = Duration::from_secs(30);
+    for _ in 0..100 {
+        let delay = pace::jitter(base, spread);
+        assert!(delay >= base - spread && delay <= base + spread);
+    }
+    assert_eq!(pace::jitter(base, Duration::ZERO), base);
+    // A spread as wide as the interval or wider never goes below half of it
+    for spread in [Duration::from_secs(10), spread] {
+        for _ in 0..100 {
+            let delay = pace::jitter(Duration::from_secs(10), spread);
+            assert!(delay >= Duration::from_secs(5) && delay <= Duration::from_secs(10) + spread);
+        }
+    }
+}
+
+#[test]
+fn http_errors_are_classified_for_retries() {
+    let unavailable = CheckError::http(503, "HTTP 503");
+    assert_eq!(unavailable.kind, ErrorKind::HttpStatus);
+    assert!(unavailable.retryable);
+
+    let limited = CheckError::http(429, "HTTP 429");
+    assert_eq!(limited.kind, ErrorKind::RateLimited);
+    assert!(limited.retryable);
+
+    let missing = CheckError::http(404, "HTTP 404");
+    assert!(!missing.retryable);
+    assert_eq!(
+        serde_json::to_value(&missing).unwrap(),
+        json!({ "kind": "http_status", "message": "HTTP 404", "retryable": false, "http_status": 404 })
+    );
+
+    let io = std::io::Error::other("disk full");
+    assert_eq!(
+        CheckError::from_error(&io, ErrorKind::Store).kind,
+        ErrorKind::Store
+    );
+}
+
+#[test]
+fn notification_title_names_surface_and_version() {
+    let config = Config::default();
+    let entry = json!({ "clientVersion": "1.2.61.443.g1a2b3c4d" });
+    assert_eq!(
+        notify::title(&config.surface("web").unwrap(), "1.2.61.443", &entry),
+        "Spotify Web 1.2.61.443.g1a2b3c4d released"
+    );
+    assert_eq!(
+        notify::title(&config.surface("embed").unwrap(), "1.2.61.443", &json!({})),
+        "Spotify Embed 1.2.61.443 released"
+    );
+}
+
+#[test]
+fn bundle_scans_show_what_a_release_added() {
+    let dir = TempDir::new().unwrap();
+    let patterns = std::collections::BTreeMap::from([
+        (
+            "endpoints".to_string(),
+            r#"https://[a-z0-9.-]+spotify\.com/[^"]+"#.to_string(),
+        ),
+        ("features".to_string(), r"enable([A-Z]\w+)".to_string()),
+    ]);
+    let old = r#"fetch("https://api.spotify.com/v1/me");const c={enableLyrics:!0}"#;
+    let new = r#"fetch("https://api.spotify.com/v1/me");fetch("https://spclient.wg.spotify.com/jam/v1");const c={enableLyrics:!0,enableJam:!0}"#;
+    for (key, js) in [("1.2.85.519", old), ("1.2.86.316", new)] {
+        bundle::archive(dir.path(), "web", key, js, Compression::None).unwrap();
+        bundle::archive_scan(dir.path(), "web", key, js, &patterns).unwrap();
+    }
+
+    let counts = bundle::archive_scan(dir.path(), "web", "1.2.86.316", new, &patterns).unwrap();
+    assert_eq!(counts["endpoints"], 2);
+    assert_eq!(counts["features"], 2);
+
+    let diff = bundle::diff_archived(dir.path(), "web", "1.2.85.519", "1.2.86.316").unwrap();
+    assert_eq!(
+        diff["scan"]["endpoints"]["added"],
+        json!(["https://spclient.wg.spotify.com/jam/v1"])
+    );
+    assert_eq!(diff["scan"]["features"]["added"], json!(["Jam"]));
+    assert_eq!(diff["scan"]["features"]["removed"], json!([]));
+}
+
+#[test]
+fn identical_bundles_are_linked_to_the_newest_match() {
+    let versions = std::collections::HashMap::from([
+        ("1.2.85.519".to_string(), json!({ "bundleSha256": "aa" })),
+        ("1.2.85.600".to_string(), json!({ "bundleSha256": "aa" })),
+        ("1.2.86.100".to_string(), json!({ "bundleSha256": "bb" })),
+    ]);
+    assert_eq!(
+        bundle::same_bundle(&versions, &json!({ "bundleSha256": "aa" })).as_deref(),
+        Some("1.2.85.600")
+    );
+    assert_eq!(
+        bundle::same_bundle(&versions, &json!({ "bundleSha256": "cc" })),
+        None
+    );
+    assert_eq!(bundle::same_bundle(&versions, &json!({})), None);
+
+    let diff = bundle::diff("1.2.85.600", "x()", "1.2.86.316", "x()");
+    assert_eq!(diff["identical"], true);
+}
@@ -0,0 +1,71 @@
+use serde_json::json;
+use tempfile::TempDir;
+use web_search::config::Config;
+use web_search::doctor;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn report_lists_each_check() {
+    let dir = TempDir::new().unwrap();
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<html></html>"))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/user-agents.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!(["Mozilla/5.0 (test)"])))
+        .mount(&server)
+        .await;
+    let config = Config {
+        spotify_url: server.uri(),
+        user_agent_api: format!("{}/user-agents.json", server.uri()),
+        versions_file: dir.path().join("data/versions_web.json"),
+        ..Config::default()
+    };
+
+    let report = doctor::run(&config, &config.surface("web").unwrap()).await;
+
+    assert_eq!(report["success"], true, "{report}");
+    let names: Vec<&str> = report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|check| check["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "dns",
+            "http",
+            "user_agent_api",
+            "data_dir",
+            "notifiers",
+            "healthcheck"
+        ]
+    );
+    assert!(dir.path().join("data").is_dir());
+}
+
+#[tokio::test]
+async fn failing_page_fails_the_report() {
+    let dir = TempDir::new().unwrap();
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(403))
+        .mount(&server)
+        .await;
+    let config = Config {
+        spotify_url: server.uri(),
+        user_agent_api: format!("{}/user-agents.json", server.uri()),
+        versions_file: dir.path().join("versions_web.json"),
+        ..Config::default()
+    };
+
+    let report = doctor::run(&config, &config.surface("web").unwrap()).await;
+
+    assert_eq!(report["success"], false);
+    assert_eq!(report["failed"], 2);
+}
@@ -0,0 +1,171 @@
+use std::path::Path;
+
+use web_search::config::{Config, Decode, ExtractConfig};
+use web_search::extract;
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn version_entry_reads_the_page_without_io() {
+    let entry =
+        extract::version_entry(&fixture("selector.html"), &ExtractConfig::default()).unwrap();
+
+    assert_eq!(entry.key, "1.2.86.316");
+    assert_eq!(entry.data["clientVersion"], "1.2.86.316.gcd065fc0");
+    assert_eq!(entry.data["buildDate"], "2026-03-15");
+    assert_eq!(entry.data["buildDateIso"], "2026-03-15T00:00:00Z");
+    assert_eq!(
+        entry.data["webPlayer"],
+        "https://open.spotifycdn.com/cdn/build/web-player/web-player.ca73afa1.js"
+    );
+}
+
+#[test]
+fn version_entry_falls_back_to_the_regex() {
+    let entry =
+        extract::version_entry(&fixture("regex_fallback.html"), &ExtractConfig::default()).unwrap();
+    assert_eq!(entry.key, "1.2.86.316");
+}
+
+#[test]
+fn plain_json_configs_are_read_with_pointers() {
+    let html = r#"<html><body><script>window.__APP_STATE__ = {"app": {"version": "8.34.0", "built": "2026-03-02"}}</script></body></html>"#;
+    let config = ExtractConfig {
+        selectors: Vec::new(),
+        regex: r"window\.__APP_STATE__\s*=\s*(\{.*\})</script>".to_string(),
+        decode: Decode::None,
+        client_version_field: "/app/version".to_string(),
+        build_date_field: "/app/built".to_string(),
+        ..ExtractConfig::default()
+    };
+
+    let entry = extract::version_entry(html, &config).unwrap();
+    assert_eq!(entry.key, "8.34.0");
+    assert_eq!(entry.data["buildDate"], "2026-03-02");
+
+    let base64 = ExtractConfig {
+        decode: Decode::Base64,
+        ..config
+    };
+    assert!(extract::version_entry(html, &base64).is_err());
+}
+
+#[test]
+fn youtube_music_reads_the_innertube_client_version() {
+    let config = Config::default();
+    let entry = extract::version_entry(
+        &fixture("youtube_music.html"),
+        &config.youtube_music.extract,
+    )
+    .unwrap();
+
+    assert_eq!(entry.key, "1.20260310.01.00");
+    assert_eq!(entry.data["clientVersion"], "1.20260310.01.00");
+    assert!(entry.data.get("buildDate").is_none());
+    assert!(entry.data.get("webPlayer").is_none());
+}
+
+#[test]
+fn apple_music_uses_the_index_script_hash() {
+    let config = Config::default();
+    let entry =
+        extract::version_entry(&fixture("apple_music.html"), &config.apple_music.extract).unwrap();
+
+    assert_eq!(entry.key, "8a3c2b1f0e");
+    assert_eq!(
+        entry.data,
+        serde_json::json!({ "clientVersion": "8a3c2b1f0e" })
+    );
+}
+
+#[test]
+fn version_entry_reports_missing_and_empty_tags() {
+    let config = ExtractConfig::default();
+
+    let error = extract::version_entry(&fixture("blocked.html"), &config).unwrap_err();
+    assert_eq!(error.to_string(), "appServerConfig tag not found");
+
+    let error = extract::version_entry(&fixture("empty_tag.html"), &config).unwrap_err();
+    assert_eq!(error.to_string(), "Base64 content is empty");
+}
+
+fn page(configs: &[serde_json::Value]) -> String {
+    use base64::Engine as _;
+
+    let tags: String = configs
+        .iter()
+        .map(|config| {
+            let payload = base64::engine::general_purpose::STANDARD.encode(config.to_string());
+            format!(
+                r#"<script id="appServerConfig" type="text/plain">{}</script>"#,
+                payload
+            )
+        })
+        .collect();
+    format!("<html><head>{}</head><body></body></html>", tags)
+}
+
+#[test]
+fn strict_mode_refuses_partial_or_ambiguous_configs() {
+    let config = ExtractConfig {
+        strict: true,
+        ..ExtractConfig::default()
+    };
+    let complete = serde_json::json!({
+        "clientVersion": "1.2.86.316.gcd065fc0",
+        "buildDate": "2026-03-15",
+        "buildVersion": "open-server_2026-03-15_1773590236035_cd065fc"
+    });
+    let partial = serde_json::json!({
+        "clientVersion": "1.2.86.316.gcd065fc0",
+        "buildDate": 20260315
+    });
+
+    assert!(extract::version_entry(&page(std::slice::from_ref(&complete)), &config).is_ok());
+    assert!(extract::version_entry(&page(&[complete.clone(), complete.clone()]), &config).is_ok());
+
+    let error = extract::version_entry(&page(std::slice::from_ref(&partial)), &config).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Strict mode: 'buildDate' is a number instead of a string; 'buildVersion' not found"
+    );
+
+    let error = extract::version_entry(&page(&[complete, partial]), &config).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("Strict mode: 2 appServerConfig tags with different content"));
+}
+
+#[test]
+fn the_first_tag_with_a_client_version_wins() {
+    let experiment = serde_json::json!({ "experiment": "holdout" });
+    let release = serde_json::json!({
+        "clientVersion": "1.2.86.316.gcd065fc0",
+        "buildDate": "2026-03-15"
+    });
+    let later = serde_json::json!({
+        "clientVersion": "1.2.87.100.g00000000",
+        "buildDate": "2026-04-01"
+    });
+
+    let entry = extract::version_entry(
+        &page(&[experiment, release, later]),
+        &ExtractConfig::default(),
+    )
+    .unwrap();
+
+    assert_eq!(entry.key, "1.2.86.316");
+}
+
+#[test]
+fn self_test_passes_on_the_embedded_pages() {
+    let report = web_search::selftest::run();
+
+    assert_eq!(report["success"], true, "{report}");
+    assert_eq!(report["cases"].as_array().unwrap().len(), 4);
+}
@@ -0,0 +1,42 @@
+use serde_json::json;
+use web_search::gaps::{self, Gap};
+
+fn keys(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|key| key.to_string()).collect()
+}
+
+#[test]
+fn gaps_stay_within_a_series() {
+    let keys = keys(&[
+        "1.2.86.5",
+        "1.2.85.9",
+        "1.2.86.1",
+        "1.2.85.10",
+        "1.2.86.2",
+        "beta",
+    ]);
+
+    assert_eq!(
+        gaps::find(&keys, 1),
+        vec![Gap {
+            after: "1.2.86.2".to_string(),
+            before: "1.2.86.5".to_string(),
+            missing: 2,
+        }]
+    );
+}
+
+#[test]
+fn report_lists_likely_builds_and_coverage() {
+    let keys = keys(&["1.2.86.300", "1.2.86.306", "1.2.86.308"]);
+
+    let report = gaps::report(&keys, 2);
+
+    assert_eq!(report["missing"], 2);
+    assert_eq!(report["coverage"], 0.6);
+    assert_eq!(
+        report["gaps"][0]["likely"],
+        json!(["1.2.86.302", "1.2.86.304"])
+    );
+    assert_eq!(report["gaps"].as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use tokio::sync::broadcast;
+use web_search::bundle;
+use web_search::config::Compression;
+use web_search::events::VersionEvent;
+use web_search::graphql::{self, VersionsSchema};
+use web_search::store::MemoryStore;
+
+const SEED: &str = r#"{
+    "1.1.99.12": {"buildDate": "2025-12-01", "clientVersion": "1.1.99.12.gaaaaaaa"},
+    "1.2.85.519": {"buildDate": "2026-03-01", "clientVersion": "1.2.85.519.g1a2b3c4d"},
+    "1.2.86.316": {"buildDate": "2026-03-15", "clientVersion": "1.2.86.316.gcd065fc0"}
+}"#;
+
+fn schema(dir: &TempDir, events: broadcast::Sender<VersionEvent>) -> VersionsSchema {
+    let store = Arc::new(MemoryStore::from_reader(SEED.as_bytes()).unwrap());
+    graphql::schema("web", store, events, dir.path().to_path_buf())
+}
+
+async fn query(schema: &VersionsSchema, query: &str) -> Value {
+    let response = schema.execute(query).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    response.data.into_json().unwrap()
+}
+
+#[tokio::test]
+async fn versions_filter_by_date_prefix_and_archive() {
+    let dir = TempDir::new().unwrap();
+    bundle::archive(dir.path(), "web", "1.2.86.316", "js", Compression::None).unwrap();
+    let schema = schema(&dir, broadcast::channel(8).0);
+
+    let data = query(
+        &schema,
+        r#"{ versions(since: "2026-01-01", prefix: "1.2.") { key archived } }"#,
+    )
+    .await;
+    assert_eq!(
+        data["versions"],
+        json!([
+            {"key": "1.2.86.316", "archived": true},
+            {"key": "1.2.85.519", "archived": false}
+        ])
+    );
+
+    // Whole segments only: 1.2.8 isn't a prefix of 1.2.86
+    let data = query(&schema, r#"{ versions(prefix: "1.2.8") { key } }"#).await;
+    assert_eq!(data["versions"], json!([]));
+    let data = query(&schema, r#"{ versions(prefix: "1.2.86") { key } }"#).await;
+    assert_eq!(data["versions"], json!([{"key": "1.2.86.316"}]));
+
+    let data = query(
+        &schema,
+        r#"{ versions(until: "2026-03-01", archived: false) { key } }"#,
+    )
+    .await;
+    assert_eq!(
+        data["versions"],
+        json!([{"key": "1.2.85.519"}, {"key": "1.1.99.12"}])
+    );
+
+    let data = query(&schema, "{ latest { key clientVersion buildDate } }").await;
+    assert_eq!(
+        data["latest"],
+        json!({"key": "1.2.86.316", "clientVersion": "1.2.86.316.gcd065fc0", "buildDate": "2026-03-15"})
+    );
+}
+
+#[tokio::test]
+async fn subscription_streams_new_versions() {
+    use futures_util::StreamExt;
+
+    let dir = TempDir::new().unwrap();
+    let (events, _) = broadcast::channel(8);
+    let schema = schema(&dir, events.clone());
+
+    let mut stream = schema.execute_stream("subscription { newVersions { key webPlayer } }");
+    let next = tokio::spawn(async move { stream.next().await.unwrap() });
+    // Let the subscription start listening before publishing
+    while events.receiver_count() == 0 {
+        tokio::task::yield_now().await;
+    }
+    events
+        .send(VersionEvent::Unchanged {
+            surface: "web".to_string(),
+            key: "1.2.86.316".to_string(),
+        })
+        .unwrap();
+    events
+        .send(VersionEvent::NewVersion {
+            surface: "web".to_string(),
+            key: "1.2.87.100".to_string(),
+            entry: json!({"clientVersion": "1.2.87.100.g0", "buildDate": "2026-04-01"}),
+        })
+        .unwrap();
+
+    let response = next.await.unwrap();
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        json!({"newVersions": {"key": "1.2.87.100", "webPlayer": null}})
+    );
+}
@@ -0,0 +1,229 @@
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::atomic::{AtomicU32, Ordering};
+use web_search::config::{AppriseConfig, GotifyConfig, NotifyConfig, PushoverConfig};
+use web_search::notify::{
+    self, AppriseNotifier, GotifyNotifier, Message, Notifier, PushoverNotifier,
+};
+use wiremock::matchers::{body_json, body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn message() -> Message {
+    Message {
+        title: "Spotify Web 1.2.61.443.g1a2b3c4d released".to_string(),
+        body: "Built 2026-03-15".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn gotify_posts_the_message_with_the_app_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/message"))
+        .and(header("X-Gotify-Key", "app-token"))
+        .and(body_json(json!({
+            "title": "Spotify Web 1.2.61.443.g1a2b3c4d released",
+            "message": "Built 2026-03-15",
+            "priority": 5
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let notifier = GotifyNotifier::new(GotifyConfig {
+        url: Some(format!("{}/", server.uri())),
+        token: Some("app-token".to_string()),
+        ..GotifyConfig::default()
+    });
+    notifier.send(&message()).await.unwrap();
+}
+
+#[tokio::test]
+async fn apprise_api_gets_the_urls_and_message() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/notify"))
+        .and(body_json(json!({
+            "title": "Spotify Web 1.2.61.443.g1a2b3c4d released",
+            "body": "Built 2026-03-15",
+            "urls": "tgram://bot/chat,ntfys://ntfy.sh/topic"
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let notifier = AppriseNotifier::new(AppriseConfig {
+        urls: vec![
+            "tgram://bot/chat".to_string(),
+            "ntfys://ntfy.sh/topic".to_string(),
+        ],
+        api_url: Some(format!("{}/notify", server.uri())),
+        ..AppriseConfig::default()
+    });
+    notifier.send(&message()).await.unwrap();
+}
+
+#[tokio::test]
+async fn pushover_is_retried_after_a_failure() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("user=user-key"))
+        .and(body_string_contains("priority=1"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let config = NotifyConfig {
+        retry_backoff_ms: 1,
+        ..NotifyConfig::default()
+    };
+    let notifier = PushoverNotifier::new(PushoverConfig {
+        user: Some("user-key".to_string()),
+        token: Some("app-token".to_string()),
+        priority: 1,
+        api_url: server.uri(),
+    });
+    notify::send_with_retry(&config, &notifier, &message())
+        .await
+        .unwrap();
+}
+
+struct Failing(AtomicU32);
+
+#[async_trait]
+impl Notifier for Failing {
+    fn name(&self) -> &'static str {
+        "failing"
+    }
+
+    async fn send(&self, _: &Message) -> web_search::Result<()> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        Err("unreachable".into())
+    }
+}
+
+#[tokio::test]
+async fn retries_stop_after_the_configured_count() {
+    let config = NotifyConfig {
+        retries: 3,
+        retry_backoff_ms: 1,
+        ..NotifyConfig::default()
+    };
+    let notifier = Failing(AtomicU32::new(0));
+
+    assert!(notify::send_with_retry(&config, &notifier, &message())
+        .await
+        .is_err());
+    assert_eq!(notifier.0.load(Ordering::Relaxed), 4);
+}
+
+// Only meaningful while notify-desktop is left out of the build
+#[cfg(not(feature = "notify-desktop"))]
+#[test]
+fn backends_missing_from_the_build_are_skipped() {
+    let mut config = NotifyConfig {
+        gotify: GotifyConfig {
+            url: Some("https://gotify.example".to_string()),
+            token: Some("app-token".to_string()),
+            ..GotifyConfig::default()
+        },
+        ..NotifyConfig::default()
+    };
+    config.desktop.enabled = true;
+
+    let names: Vec<&str> = notify::notifiers(&config)
+        .iter()
+        .map(|notifier| notifier.name())
+        .collect();
+    assert_eq!(names, ["gotify"]);
+    assert_eq!(notify::missing(&config), ["desktop"]);
+}
+
+#[tokio::test]
+async fn probes_check_reachability_without_sending() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/version"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"version": "2.4.0"})))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/1/users/validate.json"))
+        .and(body_string_contains("user=user-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"status": 1})))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/1/users/validate.json"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "status": 0,
+            "errors": ["user key is invalid"]
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/status"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    // Nothing may actually be sent
+    Mock::given(method("POST"))
+        .and(path("/message"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let gotify = GotifyNotifier::new(GotifyConfig {
+        url: Some(server.uri()),
+        token: Some("app-token".to_string()),
+        ..GotifyConfig::default()
+    });
+    assert!(gotify.probe().await.unwrap().contains("2.4.0"));
+
+    let pushover = |user: &str| {
+        PushoverNotifier::new(PushoverConfig {
+            user: Some(user.to_string()),
+            token: Some("app-token".to_string()),
+            priority: 0,
+            api_url: format!("{}/1/messages.json", server.uri()),
+        })
+    };
+    assert!(pushover("user-key").probe().await.is_ok());
+    let error = pushover("wrong").probe().await.unwrap_err();
+    assert!(error.to_string().contains("user key is invalid"));
+
+    let apprise = AppriseNotifier::new(AppriseConfig {
+        api_url: Some(format!("{}/notify/key", server.uri())),
+        ..AppriseConfig::default()
+    });
+    assert!(apprise.probe().await.is_ok());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn apprise_cli_probe_is_a_dry_run() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let script = dir.path().join("apprise");
+    // Succeeds only when asked for a dry run
+    std::fs::write(&script, "#!/bin/sh\n[ \"$1\" = --dry-run ] || exit 1\n").unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let apprise = AppriseNotifier::new(AppriseConfig {
+        urls: vec!["tgram://bot/chat".to_string()],
+        command: script.display().to_string(),
+        ..AppriseConfig::default()
+    });
+    assert!(apprise.probe().await.is_ok());
+    assert!(apprise.send(&message()).await.is_err());
+}
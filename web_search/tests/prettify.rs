@@ -0,0 +1,27 @@
+use web_search::prettify::prettify;
+
+#[test]
+fn braces_and_statements_get_their_own_lines() {
+    let js =
+        r#"function a(b){if(b){return"{x;}"}for(var i=0;i<2;i++){c(i)}return{}}var r=/[;{]/g;"#;
+    assert_eq!(
+        prettify(js),
+        r#"function a(b){
+  if(b){
+    return"{x;}"
+  }
+  for(var i=0;i<2;i++){
+    c(i)
+  }
+  return{}
+}
+var r=/[;{]/g;
+"#
+    );
+}
+
+#[test]
+fn comments_and_templates_are_kept_as_is() {
+    let js = "/* a; { */x=`${y};{`;// done {\nz=1";
+    assert_eq!(prettify(js), "/* a; { */x=`${y};{`;\n// done {\nz=1\n");
+}
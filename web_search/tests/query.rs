@@ -0,0 +1,123 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use web_search::query;
+
+fn versions() -> HashMap<String, Value> {
+    HashMap::from([
+        (
+            "1.2.80.1".to_string(),
+            json!({ "buildDate": "2023-12-30", "buildVersion": "1.2.80.1.gaaaa" }),
+        ),
+        (
+            "1.2.81.7".to_string(),
+            json!({ "buildDate": "2024-01-04", "buildVersion": "1.2.81.7.gbbbb" }),
+        ),
+        (
+            "1.2.100.2".to_string(),
+            json!({
+                "buildDate": "2024-02-10",
+                "buildVersion": "1.2.100.2.gcccc",
+                "webPlayer": "https://open.spotifycdn.com/web-player.js",
+                "regions": ["us", "de"]
+            }),
+        ),
+    ])
+}
+
+fn keys(rows: &[Value]) -> Vec<&str> {
+    rows.iter()
+        .map(|row| row["key"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn where_filters_by_dates_versions_and_presence() {
+    let versions = versions();
+    let run = |filter: &str| {
+        let filter = query::parse(filter).unwrap();
+        query::run(&versions, Some(&filter), &[])
+    };
+
+    assert_eq!(
+        keys(&run("buildDate >= 2024-01-01 && has(webPlayer)")),
+        ["1.2.100.2"]
+    );
+    assert_eq!(keys(&run("key > 1.2.80.1")), ["1.2.100.2", "1.2.81.7"]);
+    assert_eq!(
+        keys(&run("!(buildVersion ~ 'gbbbb') || /regions/1 == de")),
+        ["1.2.100.2", "1.2.80.1"]
+    );
+    assert_eq!(keys(&run("missing == x")), Vec::<&str>::new());
+    assert!(query::parse("buildDate >=").is_err());
+    assert!(query::parse("(has(webPlayer)").is_err());
+}
+
+#[test]
+fn select_keeps_fields_and_csv_quotes_cells() {
+    let versions = versions();
+    let select = [
+        "key".to_string(),
+        "buildVersion".to_string(),
+        "regions".to_string(),
+    ];
+    let rows = query::run(&versions, None, &select);
+
+    assert_eq!(
+        rows[0],
+        json!({ "key": "1.2.100.2", "buildVersion": "1.2.100.2.gcccc", "regions": ["us", "de"] })
+    );
+    assert_eq!(
+        query::to_csv(&rows, &select),
+        "key,buildVersion,regions\n\
+         1.2.100.2,1.2.100.2.gcccc,\"[\"\"us\"\",\"\"de\"\"]\"\n\
+         1.2.81.7,1.2.81.7.gbbbb,\n\
+         1.2.80.1,1.2.80.1.gaaaa,\n"
+    );
+    assert_eq!(
+        query::columns(&query::run(&versions, None, &[])),
+        ["key", "buildDate", "buildVersion", "regions", "webPlayer"]
+    );
+}
+
+#[test]
+fn field_paths_accept_jq_style_and_pointers() {
+    let entry = json!({
+        "webPlayer": "https://open.spotifycdn.com/web-player.js",
+        "regions": ["us", "de"],
+        "bundle": { "bytes": 1234 }
+    });
+
+    let web_player = query::lookup(&entry, ".webPlayer").unwrap();
+    assert_eq!(
+        query::raw(web_player),
+        "https://open.spotifycdn.com/web-player.js"
+    );
+    assert_eq!(
+        web_player.to_string(),
+        "\"https://open.spotifycdn.com/web-player.js\""
+    );
+    assert_eq!(query::lookup(&entry, ".regions[1]"), Some(&json!("de")));
+    assert_eq!(query::lookup(&entry, "/regions/0"), Some(&json!("us")));
+    assert_eq!(
+        query::raw(query::lookup(&entry, ".bundle.bytes").unwrap()),
+        "1234"
+    );
+    assert_eq!(query::lookup(&entry, "webPlayer"), Some(web_player));
+    assert_eq!(query::lookup(&entry, ".missing"), None);
+}
+
+#[test]
+fn entries_that_are_not_objects_are_wrapped() {
+    let versions = HashMap::from([
+        ("1.2.80.1".to_string(), json!("1.2.80.1.gaaaa")),
+        ("1.2.81.7".to_string(), json!({ "buildDate": "2024-02-01" })),
+    ]);
+
+    assert_eq!(
+        query::run(&versions, None, &[]),
+        [
+            json!({ "buildDate": "2024-02-01", "key": "1.2.81.7" }),
+            json!({ "key": "1.2.80.1", "value": "1.2.80.1.gaaaa" }),
+        ]
+    );
+}
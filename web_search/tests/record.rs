@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use serde_json::json;
+use tempfile::TempDir;
+use web_search::check;
+use web_search::config::{
+    AnomalyAction, AnomalyConfig, BackupConfig, BundleConfig, ChangelogConfig, Config, EventsConfig,
+};
+use web_search::record::{self, Mode};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+fn config_for(url: &str, dir: &Path) -> Config {
+    Config {
+        spotify_url: url.to_string(),
+        user_agent_api: format!("{}/user-agents.json", url),
+        versions_file: dir.join("versions_web.json"),
+        failures_dir: dir.join("failures"),
+        bundle: BundleConfig {
+            cross_check: false,
+            ..BundleConfig::default()
+        },
+        changelog: ChangelogConfig {
+            enabled: false,
+            path: dir.join("CHANGELOG.md"),
+        },
+        events: EventsConfig {
+            enabled: false,
+            path: dir.join("events.jsonl"),
+        },
+        backup: BackupConfig {
+            dir: dir.join("backups"),
+            ..BackupConfig::default()
+        },
+        // Replayed pages keep their build date; anomaly rules aren't under test
+        anomaly: AnomalyConfig {
+            action: AnomalyAction::Off,
+            quarantine_file: dir.join("quarantine.jsonl"),
+            ..AnomalyConfig::default()
+        },
+        ..Config::default()
+    }
+}
+
+// Both phases in one test: the mode is process-wide
+#[tokio::test]
+async fn recorded_exchanges_replay_without_the_network() {
+    let dir = TempDir::new().unwrap();
+    let fixtures = dir.path().join("fixtures");
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(fixture("selector.html")))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/user-agents.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!(["Mozilla/5.0 (test)"])))
+        .mount(&server)
+        .await;
+    let url = server.uri();
+
+    record::set_mode(Mode::Record(fixtures.clone()));
+    let live = dir.path().join("live");
+    let recorded = check::run(&config_for(&url, &live)).await.unwrap();
+    assert_eq!(recorded["key"], "1.2.86.316");
+    assert_eq!(std::fs::read_dir(&fixtures).unwrap().count(), 2);
+
+    drop(server);
+    record::set_mode(Mode::Replay(fixtures.clone()));
+    let offline = dir.path().join("offline");
+    let replayed = check::run(&config_for(&url, &offline)).await.unwrap();
+    assert_eq!(replayed["key"], recorded["key"]);
+    assert_eq!(replayed["data"]["webPlayer"], recorded["data"]["webPlayer"]);
+
+    let missing = config_for("http://127.0.0.1:9/unrecorded", &offline);
+    assert!(check::run(&missing).await.is_err());
+    record::set_mode(Mode::Live);
+}
@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use web_search::config::{HttpConfig, ResultsConfig};
+use web_search::results;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn results_go_to_the_file_and_the_log() {
+    let dir = TempDir::new().unwrap();
+    let config = ResultsConfig {
+        file: Some(dir.path().join("run.json")),
+        log: Some(dir.path().join("logs/results.jsonl")),
+        post_url: None,
+    };
+
+    let http = HttpConfig::default();
+    results::deliver(&config, &http, &json!({ "success": true, "key": "1.2.80" })).await;
+    results::deliver(
+        &config,
+        &http,
+        &json!({ "success": false, "error": { "kind": "http_status" } }),
+    )
+    .await;
+
+    let file: Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("run.json")).unwrap())
+            .unwrap();
+    assert_eq!(file["error"]["kind"], "http_status");
+
+    let log = std::fs::read_to_string(dir.path().join("logs/results.jsonl")).unwrap();
+    let lines: Vec<Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["key"], "1.2.80");
+    assert_eq!(lines[1]["success"], false);
+}
+
+#[tokio::test]
+async fn results_are_posted_with_the_http_settings() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/results"))
+        .and(body_json(json!({ "success": true, "key": "1.2.80" })))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/slow"))
+        .respond_with(ResponseTemplate::new(204).set_delay(Duration::from_secs(5)))
+        .mount(&server)
+        .await;
+    let http = HttpConfig {
+        timeout_secs: 0.2,
+        ..HttpConfig::default()
+    };
+    let output = json!({ "success": true, "key": "1.2.80" });
+
+    let config = ResultsConfig {
+        file: None,
+        log: None,
+        post_url: Some(format!("{}/results", server.uri())),
+    };
+    results::deliver(&config, &http, &output).await;
+
+    // [http] timeout_secs applies, so a hanging endpoint can't stall a check
+    let config = ResultsConfig {
+        post_url: Some(format!("{}/slow", server.uri())),
+        ..config
+    };
+    let started = Instant::now();
+    results::deliver(&config, &http, &output).await;
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
@@ -0,0 +1,66 @@
+use web_search::config::RobotsConfig;
+use web_search::robots::{self, Robots};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ROBOTS: &str = "\
+User-agent: *
+Disallow: /search
+Allow: /search/about
+Disallow: /*.json$
+
+User-agent: WebVersionsBot
+User-agent: OtherBot
+Disallow: /
+";
+
+#[test]
+fn longest_rule_for_the_agent_wins() {
+    let robots = Robots::parse(ROBOTS);
+    let browser = "Mozilla/5.0 (test)";
+
+    assert!(robots.allows(browser, "/"));
+    assert!(!robots.allows(browser, "/search?q=x"));
+    assert!(robots.allows(browser, "/search/about"));
+    assert!(!robots.allows(browser, "/config.json"));
+    assert!(robots.allows(browser, "/config.json?v=1"));
+    assert!(!robots.allows("WebVersionsBot/1.0", "/"));
+    assert!(!robots.allows("otherbot", "/album"));
+}
+
+#[test]
+fn empty_or_missing_rules_allow_everything() {
+    assert!(Robots::parse("").allows("Mozilla/5.0", "/anything"));
+    assert!(Robots::parse("User-agent: *\nDisallow:\n").allows("Mozilla/5.0", "/"));
+}
+
+#[tokio::test]
+async fn robots_txt_is_fetched_once_and_respected() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/robots.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(ROBOTS))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let config = RobotsConfig {
+        enabled: true,
+        ..RobotsConfig::default()
+    };
+    let client = reqwest::Client::new();
+    let agent = "Mozilla/5.0 (test)";
+
+    let home = format!("{}/", server.uri());
+    let search = format!("{}/search", server.uri());
+    assert!(robots::allowed(&config, &client, &home, agent)
+        .await
+        .unwrap());
+    assert!(!robots::allowed(&config, &client, &search, agent)
+        .await
+        .unwrap());
+
+    let off = RobotsConfig::default();
+    assert!(robots::allowed(&off, &client, &search, agent)
+        .await
+        .unwrap());
+}
@@ -0,0 +1,54 @@
+use serde_json::json;
+use web_search::config::{Config, FailOn, RunnerConfig};
+use web_search::result::{CheckError, CheckResult, ErrorKind};
+use web_search::runner;
+
+fn outputs() -> Vec<(String, serde_json::Value)> {
+    vec![
+        (
+            "web".to_string(),
+            CheckResult::found("web", "1.2.86.316", true, "new".to_string()).to_value(),
+        ),
+        (
+            "embed".to_string(),
+            CheckResult::failure(CheckError::http(429, "HTTP 429"))
+                .with_surface("embed")
+                .to_value(),
+        ),
+    ]
+}
+
+#[test]
+fn summary_counts_and_applies_the_severity_rules() {
+    let summary = runner::summarize(&RunnerConfig::default(), outputs());
+    assert_eq!(summary["success"], false);
+    assert_eq!(summary["checks"], 2);
+    assert_eq!(summary["new_versions"], 1);
+    assert_eq!(summary["failed"], json!(["embed"]));
+    assert_eq!(summary["results"]["embed"]["error"]["kind"], "rate_limited");
+
+    let ignoring = RunnerConfig {
+        ignore: vec![ErrorKind::RateLimited],
+        ..RunnerConfig::default()
+    };
+    let summary = runner::summarize(&ignoring, outputs());
+    assert_eq!(summary["success"], true);
+    assert_eq!(summary["failed"], json!([]));
+
+    let all = RunnerConfig {
+        fail_on: FailOn::All,
+        ..RunnerConfig::default()
+    };
+    assert_eq!(runner::summarize(&all, outputs())["success"], true);
+}
+
+#[tokio::test]
+async fn run_needs_enabled_sources() {
+    let mut config: Config = toml::from_str("[sources.web]\nenabled = false\n").unwrap();
+    let error = runner::run(&config).await.unwrap_err();
+    assert!(error.to_string().contains("enabled [sources"));
+
+    config = toml::from_str("[sources.nowhere]\n").unwrap();
+    let error = runner::run(&config).await.unwrap_err();
+    assert!(error.to_string().contains("Unknown surface 'nowhere'"));
+}
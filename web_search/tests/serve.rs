@@ -0,0 +1,506 @@
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use web_search::config::{
+    AnomalyConfig, BackupConfig, BundleConfig, ChangelogConfig, Config, EventsConfig, ServeConfig,
+};
+use web_search::serve;
+use web_search::serve::auth::{authorize, hmac_sha256_hex, webhook_authorized, Denied, Role};
+use web_search::serve::limit::RateLimiter;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn fixture(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    std::fs::read_to_string(path).unwrap()
+}
+
+async fn spotify_mock(fixture_name: &str, status: u16) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(status).set_body_string(fixture(fixture_name)))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/user-agents.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!(["Mozilla/5.0 (test)"])))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// Checks `server`, keeps everything it writes in `dir` and serves on a free
+/// port without rate limiting, so tests can poll as fast as they like.
+fn config_for(server: &MockServer, dir: &Path) -> Config {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    Config {
+        spotify_url: server.uri(),
+        user_agent_api: format!("{}/user-agents.json", server.uri()),
+        versions_file: dir.join("versions_web.json"),
+        failures_dir: dir.join("failures"),
+        bundle: BundleConfig {
+            cross_check: false,
+            ..BundleConfig::default()
+        },
+        changelog: ChangelogConfig {
+            enabled: false,
+            path: dir.join("CHANGELOG.md"),
+        },
+        events: EventsConfig {
+            enabled: false,
+            path: dir.join("events.jsonl"),
+        },
+        backup: BackupConfig {
+            dir: dir.join("backups"),
+            ..BackupConfig::default()
+        },
+        anomaly: AnomalyConfig {
+            max_age_days: 365 * 100,
+            quarantine_file: dir.join("quarantine.jsonl"),
+            ..AnomalyConfig::default()
+        },
+        serve: ServeConfig {
+            http_addr: format!("127.0.0.1:{}", port),
+            rate_limit_per_min: 0,
+            ..ServeConfig::default()
+        },
+        ..Config::default()
+    }
+}
+
+/// Runs `serve` for the web surface in the background and returns its base
+/// URL once the first check has finished.
+async fn start(config: Config) -> String {
+    let base = format!("http://{}", config.serve.http_addr);
+    tokio::spawn(async move {
+        let surface = config.surface("web").unwrap();
+        serve::run(&config, &surface, false).await
+    });
+
+    let health = format!("{}/health", base);
+    for _ in 0..200 {
+        if let Ok(response) = reqwest::get(&health).await {
+            let body: Value = response.json().await.unwrap();
+            if body["checks"].as_u64() > Some(0) {
+                return base;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(25)).await;
+    }
+    panic!("serve didn't finish its first check");
+}
+
+fn config() -> ServeConfig {
+    ServeConfig {
+        read_tokens: vec!["reader".to_string()],
+        admin_tokens: vec!["admin".to_string()],
+        ..ServeConfig::default()
+    }
+}
+
+#[test]
+fn reads_are_open_without_read_tokens() {
+    let config = ServeConfig::default();
+
+    assert!(authorize(&config, None, Role::Read).is_ok());
+    assert!(matches!(
+        authorize(&config, Some("Bearer anything"), Role::Admin),
+        Err(Denied::Forbidden("admin_disabled", _))
+    ));
+}
+
+#[test]
+fn tokens_grant_their_role() {
+    let config = config();
+
+    assert!(authorize(&config, Some("Bearer reader"), Role::Read).is_ok());
+    assert!(authorize(&config, Some("bearer admin"), Role::Read).is_ok());
+    assert!(authorize(&config, Some("Bearer admin"), Role::Admin).is_ok());
+    assert!(matches!(
+        authorize(&config, Some("Bearer reader"), Role::Admin),
+        Err(Denied::Forbidden("admin_required", _))
+    ));
+}
+
+#[test]
+fn missing_or_unknown_tokens_are_unauthorized() {
+    let config = config();
+
+    let denied = authorize(&config, None, Role::Read).unwrap_err();
+    assert_eq!(denied.code(), "missing_token");
+    assert!(matches!(
+        authorize(&config, Some("Basic cmVhZGVy"), Role::Read),
+        Err(Denied::Unauthorized("missing_token", _))
+    ));
+    assert!(matches!(
+        authorize(&config, Some("Bearer readers"), Role::Read),
+        Err(Denied::Unauthorized("invalid_token", _))
+    ));
+}
+
+#[test]
+fn openapi_describes_every_endpoint() {
+    let doc = serde_json::to_value(serve::openapi()).unwrap();
+
+    for path in [
+        "/latest",
+        "/versions",
+        "/health",
+        "/admin/scrape",
+        "/hooks/check",
+    ] {
+        assert!(doc["paths"][path].is_object(), "{} is missing", path);
+    }
+    assert_eq!(
+        doc["components"]["securitySchemes"]["bearer"]["scheme"],
+        "bearer"
+    );
+    assert!(doc["components"]["schemas"]["Latest"]["properties"]["lastCheck"].is_object());
+}
+
+#[test]
+fn rate_limiter_allows_a_burst_then_refills() {
+    let limiter = RateLimiter::new(60, 2).unwrap();
+    let (client, other): (IpAddr, IpAddr) =
+        ("192.0.2.1".parse().unwrap(), "192.0.2.2".parse().unwrap());
+    let start = Instant::now();
+
+    assert!(limiter.check(client, start).is_ok());
+    assert!(limiter.check(client, start).is_ok());
+    let wait = limiter.check(client, start).unwrap_err();
+    assert!(wait > Duration::ZERO && wait <= Duration::from_secs(1));
+    assert!(limiter.check(other, start).is_ok());
+
+    assert!(limiter
+        .check(client, start + Duration::from_secs(1))
+        .is_ok());
+    assert!(RateLimiter::new(0, 10).is_none());
+}
+
+#[test]
+fn webhook_secret_is_sent_or_signed() {
+    let body = br#"{"ref":"refs/heads/main"}"#;
+    assert!(webhook_authorized(
+        &ServeConfig::default(),
+        None,
+        None,
+        body
+    ));
+
+    let config = ServeConfig {
+        webhook_secret: Some("It's a Secret to Everybody".to_string()),
+        ..ServeConfig::default()
+    };
+    assert!(!webhook_authorized(&config, None, None, body));
+    assert!(webhook_authorized(
+        &config,
+        Some("It's a Secret to Everybody"),
+        None,
+        body
+    ));
+    assert!(!webhook_authorized(&config, Some("guess"), None, body));
+
+    // GitHub's documented example
+    assert_eq!(
+        hmac_sha256_hex(b"It's a Secret to Everybody", b"Hello, World!"),
+        "757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+    );
+    let signature = format!(
+        "sha256={}",
+        hmac_sha256_hex(b"It's a Secret to Everybody", body)
+    );
+    assert!(webhook_authorized(&config, None, Some(&signature), body));
+    assert!(!webhook_authorized(&config, None, Some(&signature), b"{}"));
+}
+
+#[tokio::test]
+async fn latest_and_versions_serve_the_checked_version() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let base = start(config_for(&server, dir.path())).await;
+
+    let response = reqwest::get(format!("{}/latest", base)).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let latest: Value = response.json().await.unwrap();
+    assert_eq!(latest["success"], true);
+    assert_eq!(latest["surface"], "web");
+    assert_eq!(latest["key"], "1.2.86.316");
+    assert_eq!(latest["data"]["clientVersion"], "1.2.86.316.gcd065fc0");
+    assert!(latest["lastCheck"].is_string());
+
+    let versions: Value = reqwest::get(format!("{}/versions", base))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(versions["success"], true);
+    assert_eq!(versions["versions"].as_array().unwrap().len(), 1);
+    assert_eq!(versions["versions"][0]["key"], "1.2.86.316");
+    assert_eq!(versions["versions"][0]["buildDate"], "2026-03-15");
+}
+
+#[tokio::test]
+async fn health_reports_checks_and_failures() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let base = start(config_for(&server, dir.path())).await;
+
+    let response = reqwest::get(format!("{}/health", base)).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["cache-control"], "no-store");
+    let health: Value = response.json().await.unwrap();
+    assert_eq!(health["status"], "ok");
+    assert_eq!(health["surface"], "web");
+    assert_eq!(health["scraping"], true);
+    assert_eq!(health["checks"], 1);
+    assert!(health["lastError"].is_null());
+
+    let failing_dir = TempDir::new().unwrap();
+    let failing = spotify_mock("empty_tag.html", 503).await;
+    let base = start(config_for(&failing, failing_dir.path())).await;
+
+    let response = reqwest::get(format!("{}/health", base)).await.unwrap();
+    assert_eq!(response.status(), 503);
+    let health: Value = response.json().await.unwrap();
+    assert_eq!(health["status"], "failing");
+    assert!(health["lastError"].is_string());
+    assert!(health["lastSuccess"].is_null());
+
+    let response = reqwest::get(format!("{}/latest", base)).await.unwrap();
+    assert_eq!(response.status(), 404);
+    let error: Value = response.json().await.unwrap();
+    assert_eq!(error["code"], "no_versions");
+}
+
+#[tokio::test]
+async fn reads_carry_validators_and_answer_304_when_unchanged() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let base = start(config_for(&server, dir.path())).await;
+    let client = reqwest::Client::new();
+    let latest = format!("{}/latest", base);
+
+    let response = client.get(&latest).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let headers = response.headers().clone();
+    let etag = headers["etag"].to_str().unwrap();
+    let last_modified = headers["last-modified"].to_str().unwrap();
+    assert!(etag.starts_with("W/\""));
+    assert!(last_modified.ends_with(" GMT"));
+    assert_eq!(headers["cache-control"], "public, max-age=60");
+
+    let response = client
+        .get(&latest)
+        .header("If-None-Match", etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 304);
+    assert_eq!(response.headers()["etag"], etag);
+    assert!(response.bytes().await.unwrap().is_empty());
+
+    let response = client
+        .get(&latest)
+        .header("If-Modified-Since", last_modified)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 304);
+
+    // If-None-Match wins, even with a current If-Modified-Since
+    let response = client
+        .get(&latest)
+        .header("If-None-Match", "W/\"stale\"")
+        .header("If-Modified-Since", last_modified)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // /versions has its own ETag
+    let response = client
+        .get(format!("{}/versions", base))
+        .header("If-None-Match", etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_ne!(response.headers()["etag"], etag);
+}
+
+#[tokio::test]
+async fn token_protected_reads_are_privately_cacheable() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let mut config = config_for(&server, dir.path());
+    config.serve.read_tokens = vec!["reader".to_string()];
+    let base = start(config).await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/latest", base))
+        .bearer_auth("reader")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["cache-control"], "private, max-age=60");
+}
+
+#[tokio::test]
+async fn cors_headers_go_to_allowed_origins_only() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let mut config = config_for(&server, dir.path());
+    config.serve.cors_origins = vec!["https://example.com/".to_string()];
+    config.serve.read_tokens = vec!["reader".to_string()];
+    let base = start(config).await;
+    let client = reqwest::Client::new();
+    let latest = format!("{}/latest", base);
+
+    let response = client
+        .get(&latest)
+        .header("Origin", "https://example.com")
+        .bearer_auth("reader")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let headers = response.headers();
+    assert_eq!(
+        headers["access-control-allow-origin"],
+        "https://example.com"
+    );
+    let exposed = headers["access-control-expose-headers"]
+        .to_str()
+        .unwrap()
+        .to_ascii_lowercase();
+    assert!(exposed.contains("etag") && exposed.contains("last-modified"));
+
+    // Preflights skip auth
+    let response = client
+        .request(reqwest::Method::OPTIONS, &latest)
+        .header("Origin", "https://example.com")
+        .header("Access-Control-Request-Method", "GET")
+        .header("Access-Control-Request-Headers", "authorization")
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let headers = response.headers();
+    assert_eq!(headers["access-control-allow-methods"], "GET");
+    assert_eq!(headers["access-control-max-age"], "3600");
+    assert!(headers["access-control-allow-headers"]
+        .to_str()
+        .unwrap()
+        .to_ascii_lowercase()
+        .contains("authorization"));
+
+    // Rejections carry the headers too, so the browser shows the real error
+    let response = client
+        .get(&latest)
+        .header("Origin", "https://example.com")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "https://example.com"
+    );
+
+    let response = client
+        .get(&latest)
+        .header("Origin", "https://elsewhere.example")
+        .bearer_auth("reader")
+        .send()
+        .await
+        .unwrap();
+    assert!(response
+        .headers()
+        .get("access-control-allow-origin")
+        .is_none());
+}
+
+#[tokio::test]
+async fn no_cors_headers_without_cors_origins() {
+    let dir = TempDir::new().unwrap();
+    let server = spotify_mock("selector.html", 200).await;
+    let base = start(config_for(&server, dir.path())).await;
+
+    let response = reqwest::Client::new()
+        .get(format!("{}/latest", base))
+        .header("Origin", "https://example.com")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response
+        .headers()
+        .get("access-control-allow-origin")
+        .is_none());
+}
+
+#[tokio::test]
+async fn webhook_checks_are_folded_while_one_runs() {
+    let dir = TempDir::new().unwrap();
+    // A slow page keeps the first requested check running
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(fixture("selector.html"))
+                .set_delay(Duration::from_millis(500)),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/user-agents.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!(["Mozilla/5.0 (test)"])))
+        .mount(&server)
+        .await;
+    let mut config = config_for(&server, dir.path());
+    config.serve.webhook_listener = true;
+    let base = start(config).await;
+    let client = reqwest::Client::new();
+    let hook = format!("{}/hooks/check", base);
+
+    let first: Value = client
+        .post(&hook)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let second: Value = client
+        .post(&hook)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(first["started"], true);
+    assert_eq!(second["started"], false);
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let health: Value = reqwest::get(format!("{}/health", base))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(health["checks"], 2);
+}
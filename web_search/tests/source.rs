@@ -0,0 +1,165 @@
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use web_search::check;
+use web_search::config::{Config, DesktopConfig, EntryKey};
+use web_search::ignore;
+use web_search::source::{
+    DesktopSource, SourceRegistry, SurfaceSource, VersionEntry, VersionSource,
+};
+use web_search::store::MemoryStore;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct Fixed(&'static str);
+
+#[async_trait]
+impl VersionSource for Fixed {
+    fn name(&self) -> &str {
+        "spotify-web"
+    }
+
+    async fn fetch(&self) -> web_search::Result<VersionEntry> {
+        Ok(VersionEntry {
+            key: self.0.to_string(),
+            data: json!({ "clientVersion": self.0 }),
+        })
+    }
+}
+
+#[test]
+fn builtin_sources_can_be_replaced() {
+    let mut registry = SourceRegistry::builtin(&Config::default());
+    assert_eq!(
+        registry.names(),
+        vec![
+            "spotify-web",
+            "spotify-embed",
+            "youtube-music",
+            "apple-music",
+            "desktop"
+        ]
+    );
+
+    registry.register(Fixed("1.2.86.316"));
+    assert_eq!(registry.names().len(), 5);
+    assert!(registry.get("spotify-web").is_some());
+    assert!(registry.get("deezer").is_none());
+}
+
+#[tokio::test]
+async fn run_source_saves_only_new_versions() {
+    let store = MemoryStore::new();
+
+    let output = check::run_source(&Fixed("1.2.86.316"), &store)
+        .await
+        .unwrap();
+    assert_eq!(output["is_new"], true);
+    assert_eq!(output["surface"], "spotify-web");
+
+    let output = check::run_source(&Fixed("1.2.86.316"), &store)
+        .await
+        .unwrap();
+    assert_eq!(output["is_new"], false);
+    assert_eq!(store.versions().len(), 1);
+}
+
+struct Hashed(&'static str);
+
+#[async_trait]
+impl VersionSource for Hashed {
+    fn name(&self) -> &str {
+        "apple-music"
+    }
+
+    async fn fetch(&self) -> web_search::Result<VersionEntry> {
+        Ok(VersionEntry {
+            key: self.0.to_string(),
+            data: json!({ "clientVersion": self.0 }),
+        })
+    }
+
+    fn key(&self) -> EntryKey {
+        EntryKey::FirstSeen
+    }
+}
+
+#[tokio::test]
+async fn run_source_keys_hashed_builds_by_first_seen() {
+    let store = MemoryStore::new();
+
+    let output = check::run_source(&Hashed("8a3c2b1f0e"), &store)
+        .await
+        .unwrap();
+    assert_eq!(output["is_new"], true);
+    let key = output["key"].as_str().unwrap().to_string();
+    assert_ne!(key, "8a3c2b1f0e");
+
+    let output = check::run_source(&Hashed("8a3c2b1f0e"), &store)
+        .await
+        .unwrap();
+    assert_eq!(output["is_new"], false);
+    assert_eq!(output["key"], key.as_str());
+    assert_eq!(store.versions()[&key]["clientVersion"], "8a3c2b1f0e");
+}
+
+#[tokio::test]
+async fn run_source_skips_ignored_versions() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let config = Config {
+        versions_file: dir.path().join("versions.json"),
+        ..Config::default()
+    };
+    ignore::add(&config.versions_file, "1.2.86.316").unwrap();
+    let source = SurfaceSource::new("spotify-web", "web", Arc::new(config));
+    assert!(source.ignored().unwrap().contains("1.2.86.316"));
+
+    struct Leaked;
+
+    #[async_trait]
+    impl VersionSource for Leaked {
+        fn name(&self) -> &str {
+            "spotify-web"
+        }
+
+        async fn fetch(&self) -> web_search::Result<VersionEntry> {
+            Fixed("1.2.86.316").fetch().await
+        }
+
+        fn ignored(&self) -> web_search::Result<BTreeSet<String>> {
+            Ok(BTreeSet::from(["1.2.86.316".to_string()]))
+        }
+    }
+
+    let store = MemoryStore::new();
+    let output = check::run_source(&Leaked, &store).await.unwrap();
+    assert_eq!(output["is_new"], false);
+    assert!(store.versions().is_empty());
+}
+
+#[tokio::test]
+async fn desktop_source_splits_cask_build_ids() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/spotify.json"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "version": "1.2.86.316,cd065fc0,1" })),
+        )
+        .mount(&server)
+        .await;
+
+    let config = Config::default();
+    let source = DesktopSource::new(
+        DesktopConfig {
+            url: format!("{}/spotify.json", server.uri()),
+            ..DesktopConfig::default()
+        },
+        config.http,
+    );
+    let entry = source.fetch().await.unwrap();
+
+    assert_eq!(entry.key, "1.2.86.316");
+    assert_eq!(entry.data["clientVersion"], "1.2.86.316");
+    assert_eq!(entry.data["release"], "1.2.86.316,cd065fc0,1");
+}
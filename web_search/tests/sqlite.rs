@@ -0,0 +1,47 @@
+use serde_json::json;
+use std::collections::HashMap;
+use tempfile::TempDir;
+use web_search::config::{Config, SqliteConfig};
+use web_search::store::sqlite::SqliteStore;
+use web_search::store::VersionStore;
+
+#[tokio::test]
+async fn sqlite_store_keeps_surfaces_apart_and_applies_removals() {
+    let dir = TempDir::new().unwrap();
+    let config = Config::default();
+    let sqlite = SqliteConfig {
+        path: dir.path().join("versions.db"),
+    };
+    let web = SqliteStore::connect(&sqlite, &config.surface("web").unwrap())
+        .await
+        .unwrap();
+    let embed = SqliteStore::connect(&sqlite, &config.surface("embed").unwrap())
+        .await
+        .unwrap();
+
+    let mut versions = HashMap::from([
+        (
+            "1.2.86.316".to_string(),
+            json!({ "buildDate": "2026-03-15" }),
+        ),
+        (
+            "1.2.85.519".to_string(),
+            json!({ "buildDate": "2026-03-01" }),
+        ),
+    ]);
+    web.save(&versions).await.unwrap();
+    embed
+        .save(&HashMap::from([("1.2.80.1".to_string(), json!({}))]))
+        .await
+        .unwrap();
+    assert_eq!(web.load().await.unwrap(), versions);
+
+    versions.remove("1.2.85.519");
+    versions.insert(
+        "1.2.86.316".to_string(),
+        json!({ "buildDate": "2026-03-16" }),
+    );
+    web.save(&versions).await.unwrap();
+    assert_eq!(web.load().await.unwrap(), versions);
+    assert_eq!(embed.load().await.unwrap().len(), 1);
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use web_search::stats;
+
+fn versions(entries: &[(&str, Value)]) -> HashMap<String, Value> {
+    entries
+        .iter()
+        .map(|(key, entry)| (key.to_string(), entry.clone()))
+        .collect()
+}
+
+#[test]
+fn detection_lag_summarises_per_build_month() {
+    let versions = versions(&[
+        (
+            "1.2.85.519",
+            json!({"buildDate": "2026-02-27", "firstSeen": "2026-02-27T06:00:00Z"}),
+        ),
+        (
+            "1.2.86.100",
+            json!({"buildDate": "2026-03-01", "firstSeen": "2026-03-01T02:00:00Z"}),
+        ),
+        (
+            "1.2.86.316",
+            json!({
+                "buildDate": "2026-03-15",
+                "buildVersion": "open-server_2026-03-15_1773590236035_cd065fc",
+                "firstSeen": "2026-03-15T16:57:17+00:00"
+            }),
+        ),
+        // Never seen by the crawler: left out
+        ("1.2.84.1", json!({"buildDate": "2026-01-10"})),
+    ]);
+
+    let lag = stats::detection_lag(&versions);
+
+    assert_eq!(lag["versions"], 3);
+    assert_eq!(lag["min_hours"], 1.0);
+    assert_eq!(lag["avg_hours"], 3.0);
+    assert_eq!(lag["max_hours"], 6.0);
+    assert_eq!(lag["p95_hours"], 6.0);
+    assert_eq!(lag["per_month"]["2026-03"]["versions"], 2);
+    assert_eq!(lag["per_month"]["2026-03"]["avg_hours"], 1.5);
+    assert_eq!(lag["per_month"]["2026-02"]["avg_hours"], 6.0);
+    assert!(lag["per_month"].get("2026-01").is_none());
+}
+
+#[test]
+fn build_dates_normalise_to_utc() {
+    for (raw, iso) in [
+        ("2026-03-15", "2026-03-15T00:00:00Z"),
+        ("2026/03/15", "2026-03-15T00:00:00Z"),
+        ("2026-03-15 10:20:30", "2026-03-15T10:20:30Z"),
+        ("2026-03-15T12:20:30+02:00", "2026-03-15T10:20:30Z"),
+        ("Sun, 15 Mar 2026 10:20:30 +0000", "2026-03-15T10:20:30Z"),
+    ] {
+        assert_eq!(stats::build_date_iso(raw).as_deref(), Some(iso), "{}", raw);
+    }
+    assert_eq!(stats::build_date_iso("yesterday"), None);
+
+    let entry = json!({"buildDate": "15.03.2026", "buildDateIso": "2026-03-15T00:00:00Z"});
+    assert_eq!(
+        stats::build_time(&entry).unwrap().to_rfc3339(),
+        "2026-03-15T00:00:00+00:00"
+    );
+}
+
+#[test]
+fn bundle_sizes_track_growth_over_builds() {
+    let versions = versions(&[
+        (
+            "1.2.85.519",
+            json!({ "buildDate": "2026-03-01", "bundleBytes": 2_400_000 }),
+        ),
+        (
+            "1.2.86.316",
+            json!({ "buildDate": "2026-03-15", "bundleBytes": 2_550_000 }),
+        ),
+        ("1.2.86.400", json!({ "buildDate": "2026-03-20" })),
+    ]);
+
+    let sizes = stats::bundle_sizes(&versions);
+    assert_eq!(sizes.len(), 2);
+    assert_eq!(sizes[1].0, "1.2.86.316");
+
+    let summary = &stats::compute(&versions)["bundle_size"];
+    assert_eq!(summary["versions"], 2);
+    assert_eq!(summary["growth_bytes"], 150_000);
+    assert_eq!(summary["last"]["key"], "1.2.86.316");
+
+    let svg = web_search::chart::size_svg("Sizes", &sizes);
+    assert!(svg.contains("2.55 MB"));
+    assert_eq!(svg.matches("<circle").count(), 2);
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use tempfile::TempDir;
+use web_search::config::Config;
+use web_search::paths;
+use web_search::query;
+use web_search::schema;
+use web_search::store::{
+    self, journal, load_existing_versions, save_versions, FileStore, VersionStore,
+};
+
+fn sample() -> HashMap<String, Value> {
+    HashMap::from([
+        (
+            "1.2.85.519".to_string(),
+            json!({ "buildDate": "2026-03-01", "clientVersion": "1.2.85.519.g1a2b3c4d" }),
+        ),
+        (
+            "1.2.86.316".to_string(),
+            json!({ "buildDate": "2026-03-15", "clientVersion": "1.2.86.316.gcd065fc0" }),
+        ),
+    ])
+}
+
+#[test]
+fn missing_file_loads_empty() {
+    let dir = TempDir::new().unwrap();
+    let versions = load_existing_versions(&dir.path().join("versions_web.json")).unwrap();
+    assert!(versions.is_empty());
+}
+
+#[test]
+fn round_trip_keeps_entries_newest_first() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("versions_web.json");
+
+    save_versions(&file, &sample()).unwrap();
+    assert_eq!(load_existing_versions(&file).unwrap(), sample());
+
+    let content = fs::read_to_string(&file).unwrap();
+    let newer = content.find("1.2.86.316").unwrap();
+    let older = content.find("1.2.85.519").unwrap();
+    assert!(newer < older);
+}
+
+#[test]
+fn save_creates_parent_directories_and_leaves_no_temp_file() {
+    let dir = TempDir::new().unwrap();
+    let file = dir
+        .path()
+        .join("data")
+        .join("web")
+        .join("versions_web.json");
+
+    save_versions(&file, &sample()).unwrap();
+
+    let entries: Vec<PathBuf> = fs::read_dir(file.parent().unwrap())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(entries, vec![file]);
+}
+
+#[test]
+fn read_only_file_is_reported_and_untouched() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("versions_web.json");
+    fs::write(&file, "{}").unwrap();
+    set_readonly(&file, true);
+
+    let error = save_versions(&file, &sample()).unwrap_err();
+
+    set_readonly(&file, false);
+    assert!(error.to_string().contains("read-only"), "{}", error);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "{}");
+}
+
+#[test]
+fn paths_beyond_max_path_work() {
+    let dir = TempDir::new().unwrap();
+    let mut nested = dir.path().to_path_buf();
+    for i in 0..8 {
+        nested.push(format!("{}-{}", i, "d".repeat(40)));
+    }
+    let file = nested.join("versions_web.json");
+    assert!(file.as_os_str().len() > 300);
+
+    save_versions(&file, &sample()).unwrap();
+    assert_eq!(load_existing_versions(&file).unwrap(), sample());
+}
+
+#[test]
+fn non_ascii_paths_work() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("версии данных").join("versions web.json");
+
+    save_versions(&file, &sample()).unwrap();
+    assert_eq!(load_existing_versions(&file).unwrap(), sample());
+}
+
+#[test]
+fn corrupt_file_error_names_the_path() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("versions_web.json");
+    fs::write(&file, "{ not json").unwrap();
+
+    let error = load_existing_versions(&file).unwrap_err();
+    assert!(error.to_string().contains("versions_web.json"), "{}", error);
+}
+
+#[test]
+fn relative_paths_resolve_under_data_dir() {
+    let dir = TempDir::new().unwrap();
+    let absolute = dir.path().join("elsewhere.json");
+
+    assert_eq!(
+        paths::resolve(dir.path(), Path::new("versions_web.json")),
+        dir.path().join("versions_web.json")
+    );
+    assert_eq!(paths::resolve(dir.path(), &absolute), absolute);
+
+    let mut config = Config {
+        data_dir: dir.path().to_path_buf(),
+        ..Config::default()
+    };
+    config.resolve_paths();
+    assert_eq!(config.versions_file, dir.path().join("versions_web.json"));
+    assert_eq!(config.bundle.archive_dir, dir.path().join("bundles"));
+}
+
+fn set_readonly(path: &Path, readonly: bool) {
+    let mut permissions = fs::metadata(path).unwrap().permissions();
+    permissions.set_readonly(readonly);
+    fs::set_permissions(path, permissions).unwrap();
+}
+
+#[test]
+fn migrations_run_in_order_up_to_the_target() {
+    fn add_surface(versions: &mut HashMap<String, Value>) {
+        for entry in versions.values_mut() {
+            entry["surface"] = json!("web");
+        }
+    }
+    fn rename_build_date(versions: &mut HashMap<String, Value>) {
+        for entry in versions.values_mut() {
+            let date = entry["buildDate"].take();
+            entry["builtOn"] = date;
+        }
+    }
+    let migrations = [
+        schema::Migration {
+            from: 2,
+            description: "rename buildDate",
+            apply: rename_build_date,
+        },
+        schema::Migration {
+            from: 1,
+            description: "add surface",
+            apply: add_surface,
+        },
+    ];
+
+    let mut versions = sample();
+    assert!(schema::migrate_with(&mut versions, 1, 3, &migrations).unwrap());
+    let entry = &versions["1.2.86.316"];
+    assert_eq!(entry["surface"], "web");
+    assert_eq!(entry["builtOn"], "2026-03-15");
+
+    let mut unchanged = sample();
+    assert!(!schema::migrate_with(&mut unchanged, 3, 3, &migrations).unwrap());
+    assert_eq!(unchanged, sample());
+}
+
+#[test]
+fn newer_schema_is_refused() {
+    let mut versions = sample();
+    assert!(
+        schema::migrate_with(&mut versions, schema::CURRENT + 1, schema::CURRENT, &[]).is_err()
+    );
+}
+
+#[tokio::test]
+async fn file_store_writes_the_schema_sidecar() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("versions_web.json");
+    let store = FileStore::new(&file);
+
+    assert_eq!(schema::read_version(&file).unwrap(), 1);
+    store.save(&sample()).await.unwrap();
+    assert_eq!(schema::read_version(&file).unwrap(), schema::CURRENT);
+    assert_eq!(store.load().await.unwrap(), sample());
+}
+
+#[tokio::test]
+async fn schema_1_files_gain_build_date_iso() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("versions_web.json");
+    save_versions(&file, &sample()).unwrap();
+
+    let versions = FileStore::new(&file).load().await.unwrap();
+
+    assert_eq!(versions["1.2.86.316"]["buildDate"], "2026-03-15");
+    assert_eq!(
+        versions["1.2.86.316"]["buildDateIso"],
+        "2026-03-15T00:00:00Z"
+    );
+    assert_eq!(schema::read_version(&file).unwrap(), schema::CURRENT);
+    assert_eq!(load_existing_versions(&file).unwrap(), versions);
+}
+
+#[test]
+fn annotations_are_set_removed_and_queryable() {
+    let mut versions = sample();
+    let set = [
+        ("note".to_string(), "broke DRM module".to_string()),
+        ("tag".to_string(), "regression".to_string()),
+    ];
+
+    let annotations = store::annotate(&mut versions, "1.2.86.316", &set, &[]).unwrap();
+    assert_eq!(
+        annotations,
+        json!({ "note": "broke DRM module", "tag": "regression" })
+    );
+    assert_eq!(
+        versions["1.2.86.316"][store::ANNOTATIONS]["tag"],
+        "regression"
+    );
+
+    let filter = query::parse("/x-annotations/tag == regression").unwrap();
+    let rows = query::run(&versions, Some(&filter), &["key".to_string()]);
+    assert_eq!(rows, [json!({ "key": "1.2.86.316" })]);
+
+    let unset = ["note".to_string(), "tag".to_string()];
+    store::annotate(&mut versions, "1.2.86.316", &[], &unset).unwrap();
+    assert!(versions["1.2.86.316"].get(store::ANNOTATIONS).is_none());
+    assert!(store::annotate(&mut versions, "1.2.1.1", &set, &[]).is_err());
+}
+
+#[tokio::test]
+async fn undo_reverts_journaled_saves_newest_first() {
+    let dir = TempDir::new().unwrap();
+    let file = dir.path().join("versions_web.json");
+    let store = FileStore::new(&file).with_journal(2);
+    store.save(&sample()).await.unwrap();
+
+    let mut versions = sample();
+    versions.insert("1.2.87.1".to_string(), json!({ "buildDate": "2026-04-01" }));
+    store.save(&versions).await.unwrap();
+    versions.insert("1.2.87.1".to_string(), json!({ "buildDate": "bogus" }));
+    store.save(&versions).await.unwrap();
+    // Unchanged saves are not journaled
+    store.save(&versions).await.unwrap();
+
+    let records = journal::load(&file).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].changes[0].key, "1.2.87.1");
+    assert_eq!(records[0].changes[0].before, None);
+
+    let reverted = store.undo().unwrap().unwrap();
+    assert_eq!(
+        reverted.changes[0].after,
+        Some(json!({ "buildDate": "bogus" }))
+    );
+    assert_eq!(
+        load_existing_versions(&file).unwrap()["1.2.87.1"]["buildDate"],
+        "2026-04-01"
+    );
+
+    store.undo().unwrap().unwrap();
+    assert_eq!(load_existing_versions(&file).unwrap(), sample());
+    assert_eq!(store.undo().unwrap(), None);
+}
@@ -0,0 +1,109 @@
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tempfile::TempDir;
+use web_search::config::Config;
+use web_search::store::{self, load_existing_versions};
+use web_search::{ignore, sync};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn versions(entries: &[(&str, Value)]) -> HashMap<String, Value> {
+    entries
+        .iter()
+        .map(|(key, entry)| (key.to_string(), entry.clone()))
+        .collect()
+}
+
+#[test]
+fn merge_keeps_local_edits_and_takes_upstream_changes() {
+    let base = versions(&[
+        ("1.2.85.1", json!({ "buildDate": "2026-03-01" })),
+        ("1.2.86.1", json!({ "buildDate": "2026-03-15" })),
+        ("1.2.86.9", json!({ "buildDate": "2026-03-20" })),
+    ]);
+    let local = versions(&[
+        (
+            "1.2.85.1",
+            json!({ "buildDate": "2026-03-01", "x-annotations": { "tag": "regression" } }),
+        ),
+        ("1.2.86.1", json!({ "buildDate": "2026-03-16" })),
+        ("1.2.86.9", json!({ "buildDate": "2026-03-20" })),
+    ]);
+    let remote = versions(&[
+        (
+            "1.2.85.1",
+            json!({ "buildDate": "2026-03-01", "regions": ["us"] }),
+        ),
+        ("1.2.86.1", json!({ "buildDate": "2026-03-17" })),
+        ("1.2.87.4", json!({ "buildDate": "2026-04-02" })),
+    ]);
+
+    let merged = sync::merge(&base, &local, &remote);
+
+    assert_eq!(
+        merged.versions["1.2.85.1"],
+        json!({
+            "buildDate": "2026-03-01",
+            "regions": ["us"],
+            "x-annotations": { "tag": "regression" }
+        })
+    );
+    assert_eq!(merged.versions["1.2.86.1"]["buildDate"], "2026-03-16");
+    assert_eq!(merged.added, ["1.2.87.4"]);
+    assert_eq!(merged.updated, ["1.2.85.1"]);
+    assert_eq!(merged.removed, ["1.2.86.9"]);
+    assert_eq!(merged.conflicts, ["1.2.86.1"]);
+}
+
+#[test]
+fn downloads_that_are_not_versions_maps_are_refused() {
+    assert!(sync::validate("{}").is_err());
+    assert!(sync::validate("[]").is_err());
+    assert!(sync::validate(r#"{"main": {"buildDate": "2026-03-01"}}"#).is_err());
+    assert!(sync::validate(r#"{"1.2.86.1": "2026-03-01"}"#).is_err());
+    assert_eq!(
+        sync::validate(r#"{"1.2.86.1": {}}"#).unwrap()["1.2.86.1"],
+        json!({})
+    );
+}
+
+#[tokio::test]
+async fn sync_merges_into_the_store_and_records_the_base() {
+    let dir = TempDir::new().unwrap();
+    let server = MockServer::start().await;
+    let published = json!({
+        "1.2.86.1": { "buildDate": "2026-03-15" },
+        "1.2.86.2": { "buildDate": "2026-03-16" }
+    });
+    Mock::given(method("GET"))
+        .and(path("/versions_web.json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&published))
+        .mount(&server)
+        .await;
+    let config = Config {
+        versions_file: dir.path().join("versions_web.json"),
+        ..Config::default()
+    };
+    let surface = config.surface("web").unwrap();
+    ignore::add(surface.versions_file, "1.2.86.2").unwrap();
+    let from = format!("{}/versions_web.json", server.uri());
+
+    let dry = sync::run(&config, &surface, &from, true).await.unwrap();
+    assert_eq!(dry["added"], json!(["1.2.86.1"]));
+    assert!(!config.versions_file.exists());
+
+    let output = sync::run(&config, &surface, &from, false).await.unwrap();
+    assert_eq!(output["success"], true);
+    assert_eq!(output["versions"], 1);
+    let saved = load_existing_versions(&config.versions_file).unwrap();
+    assert_eq!(saved["1.2.86.1"]["buildDate"], "2026-03-15");
+    assert!(!saved.contains_key("1.2.86.2"));
+    assert!(sync::base_path(&config.versions_file).exists());
+
+    let again = sync::run(&config, &surface, &from, false).await.unwrap();
+    assert_eq!(again["added"], json!([]));
+    assert_eq!(
+        store::sorted_keys(&load_existing_versions(&config.versions_file).unwrap()),
+        ["1.2.86.1"]
+    );
+}
@@ -0,0 +1,113 @@
+use std::cmp::Ordering;
+
+use chrono::{TimeZone, Utc};
+use proptest::prelude::*;
+use web_search::version::{compare_versions, first_seen_key, has_prefix, in_range, WebVersion};
+
+fn numeric_version() -> impl Strategy<Value = String> {
+    prop::collection::vec(0u64..2000, 1..6).prop_map(|parts| {
+        parts
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(".")
+    })
+}
+
+fn any_version() -> impl Strategy<Value = String> {
+    prop_oneof![numeric_version(), "[0-9a-z]{0,4}(\\.[0-9a-z]{0,4}){0,5}"]
+}
+
+proptest! {
+    #[test]
+    fn ordering_is_reflexive(a in any_version()) {
+        prop_assert_eq!(compare_versions(&a, &a), Ordering::Equal);
+    }
+
+    #[test]
+    fn ordering_is_antisymmetric(a in any_version(), b in any_version()) {
+        prop_assert_eq!(compare_versions(&a, &b), compare_versions(&b, &a).reverse());
+    }
+
+    #[test]
+    fn ordering_is_transitive(a in any_version(), b in any_version(), c in any_version()) {
+        let mut sorted = [WebVersion::new(&a), WebVersion::new(&b), WebVersion::new(&c)];
+        sorted.sort();
+        prop_assert!(sorted[0] <= sorted[1]);
+        prop_assert!(sorted[1] <= sorted[2]);
+        prop_assert!(sorted[0] <= sorted[2]);
+    }
+
+    #[test]
+    fn only_identical_strings_compare_equal(a in any_version(), b in any_version()) {
+        prop_assert_eq!(compare_versions(&a, &b) == Ordering::Equal, a == b);
+    }
+
+    #[test]
+    fn longer_version_with_nonzero_tail_is_newer(a in numeric_version(), tail in 1u64..1000) {
+        let longer = format!("{}.{}", a, tail);
+        prop_assert!(WebVersion::new(&longer) > WebVersion::new(&a));
+    }
+
+    #[test]
+    fn numeric_segments_are_not_compared_as_text(prefix in numeric_version(), x in 0u64..1000, y in 0u64..1000) {
+        let a = WebVersion::new(&format!("{}.{}", prefix, x));
+        let b = WebVersion::new(&format!("{}.{}", prefix, y));
+        prop_assert_eq!(a.cmp(&b), x.cmp(&y));
+    }
+}
+
+#[test]
+fn sorts_newest_first() {
+    let mut keys = vec!["1.2.9.100", "1.2.86.316", "1.2.10.5", "1.2.86.42"];
+    keys.sort_by(|a, b| compare_versions(a, b));
+    assert_eq!(keys, ["1.2.86.316", "1.2.86.42", "1.2.10.5", "1.2.9.100"]);
+}
+
+#[test]
+fn zero_padded_versions_have_a_stable_order() {
+    assert_eq!(compare_versions("1.2", "1.2.0"), Ordering::Greater);
+    assert_eq!(compare_versions("1.2.0", "1.2"), Ordering::Less);
+}
+
+#[test]
+fn hash_suffix_sorts_after_plain_build_number() {
+    assert!(WebVersion::new("1.2.86.316.gcd065fc0") > WebVersion::new("1.2.86.316"));
+    assert!(WebVersion::new("1.2.86.316.gcd065fc0") < WebVersion::new("1.2.86.317"));
+}
+
+#[test]
+fn prefixes_match_whole_segments() {
+    assert!(has_prefix("1.2.5.40", "1.2.5"));
+    assert!(has_prefix("1.2.5", "1.2.5"));
+    assert!(!has_prefix("1.2.55.1", "1.2.5"));
+    assert!(!has_prefix("1.2", "1.2.5"));
+    assert!(has_prefix("1.2.5.40", "1.2."));
+}
+
+#[test]
+fn ranges_are_inclusive_of_their_bounds_builds() {
+    let keys = [
+        "1.2.54.9",
+        "1.2.55.1",
+        "1.2.58.300",
+        "1.2.60.5",
+        "1.2.61.0",
+        "1.2.100.1",
+    ];
+    let kept: Vec<&str> = keys
+        .into_iter()
+        .filter(|key| in_range(key, Some("1.2.55"), Some("1.2.60")))
+        .collect();
+    assert_eq!(kept, ["1.2.55.1", "1.2.58.300", "1.2.60.5"]);
+    assert!(in_range("1.2.100.1", Some("1.2.61"), None));
+    assert!(in_range("1.2.9.1", None, Some("1.2.10")));
+}
+
+#[test]
+fn first_seen_keys_sort_by_time() {
+    let earlier = first_seen_key(Utc.with_ymd_and_hms(2026, 3, 15, 9, 45, 12).unwrap());
+    let later = first_seen_key(Utc.with_ymd_and_hms(2026, 11, 2, 8, 0, 0).unwrap());
+    assert_eq!(earlier, "2026.03.15.094512");
+    assert!(WebVersion::new(&earlier) < WebVersion::new(&later));
+}